@@ -3,7 +3,16 @@ import { invoke } from '@tauri-apps/api/core';
 import { listen } from '@tauri-apps/api/event';
 import { getCurrentWindow } from '@tauri-apps/api/window';
 import { WebviewWindow } from '@tauri-apps/api/webviewWindow';
-import { ClipboardItem as AppClipboardItem, FolderItem, Settings } from './types';
+import {
+  aiActionAppliesTo,
+  AiResult,
+  CaptureState,
+  ClipboardItem as AppClipboardItem,
+  ClipPage,
+  FolderItem,
+  Settings,
+  TemplateInfo,
+} from './types';
 import { ClipList } from './components/ClipList';
 import { ControlBar } from './components/ControlBar';
 import { DragPreview } from './components/DragPreview';
@@ -51,6 +60,7 @@ function App() {
   const [clipListResetToken, setClipListResetToken] = useState(0);
   const [isLoading, setIsLoading] = useState(true);
   const [hasMore, setHasMore] = useState(true);
+  const nextCursorRef = useRef<string | null>(null);
   const [theme, setTheme] = useState('system');
   const [settings, setSettings] = useState<Settings | null>(null);
 
@@ -146,29 +156,31 @@ function App() {
       try {
         setIsLoading(true);
 
-        const currentOffset = append ? clips.length : 0;
+        const cursor = append ? nextCursorRef.current : null;
 
-        let data: AppClipboardItem[];
+        let page: ClipPage;
 
         if (searchQuery.trim()) {
           if (perfLogEnabled) invokeStart = performance.now();
-          data = await invoke<AppClipboardItem[]>('search_clips', {
+          page = await invoke<ClipPage>('search_clips', {
             query: searchQuery,
             filterId: folderId,
             limit: 20,
-            offset: currentOffset,
+            cursor,
+            previewOnly: true,
           });
           if (perfLogEnabled) invokeEnd = performance.now();
         } else {
           if (perfLogEnabled) invokeStart = performance.now();
-          data = await invoke<AppClipboardItem[]>('get_clips', {
+          page = await invoke<ClipPage>('get_clips', {
             filterId: folderId,
             limit: 20,
-            offset: currentOffset,
+            cursor,
             previewOnly: true,
           });
           if (perfLogEnabled) invokeEnd = performance.now();
         }
+        const data = page.items;
 
         const imageCount = perfLogEnabled
           ? data.filter((item) => item.clip_type === 'image').length
@@ -190,8 +202,8 @@ function App() {
           setClips(data);
         }
 
-        // If we got fewer than limit, no more clips
-        setHasMore(data.length === 20);
+        nextCursorRef.current = page.next_cursor;
+        setHasMore(page.next_cursor !== null);
 
         if (perfLogEnabled) {
           const stateQueuedAt = performance.now();
@@ -203,7 +215,7 @@ function App() {
                 folderId: folderId ?? 'all',
                 append,
                 hasSearch: Boolean(searchQuery.trim()),
-                offset: currentOffset,
+                cursor,
                 itemCount: data.length,
                 imageCount,
                 totalContentChars,
@@ -221,7 +233,7 @@ function App() {
         setIsLoading(false);
       }
     },
-    [clips.length]
+    []
   );
 
   const loadFolders = useCallback(async () => {
@@ -373,6 +385,44 @@ function App() {
     refreshTotalCount();
   }, [refreshTotalCount]);
 
+  useEffect(() => {
+    const unlistenOpenFolder = listen<string>('open-folder', (event) => {
+      handleSelectFolder(event.payload);
+    });
+    const unlistenAi = listen<{ action: string; error: string | null }>(
+      'ai-shortcut-finished',
+      (event) => {
+        if (event.payload.error) {
+          toast.error(event.payload.error);
+        } else {
+          toast.success(`AI ${event.payload.action} finished`);
+        }
+      }
+    );
+    const unlistenPaused = listen<CaptureState>('capture-paused-changed', (event) => {
+      const { paused, resume_at } = event.payload;
+      if (!paused) {
+        toast.info('Clipboard capture resumed');
+      } else if (resume_at) {
+        const until = new Date(resume_at).toLocaleTimeString([], {
+          hour: '2-digit',
+          minute: '2-digit',
+        });
+        toast.info(`Clipboard capture paused until ${until}`);
+      } else {
+        toast.info('Clipboard capture paused');
+      }
+    });
+
+    return () => {
+      [unlistenOpenFolder, unlistenAi, unlistenPaused].forEach((p) =>
+        p.then((unlisten) => {
+          if (typeof unlisten === 'function') unlisten();
+        })
+      );
+    };
+  }, [handleSelectFolder]);
+
   useEffect(() => {
     const unlistenClipboard = listen('clipboard-change', () => {
       refreshCurrentFolder();
@@ -412,6 +462,19 @@ function App() {
     []
   );
 
+  // Ask for each `{{input:…}}` of a template clip; null if the user cancels.
+  const promptTemplateInputs = async (clipId: string) => {
+    const full = await invoke<AppClipboardItem>('get_clip', { id: clipId });
+    const info = await invoke<TemplateInfo>('validate_template', { template: full.content });
+    const inputs: Record<string, string> = {};
+    for (const name of info.inputs) {
+      const value = window.prompt(name);
+      if (value === null) return null;
+      inputs[name] = value;
+    }
+    return inputs;
+  };
+
   const handlePaste = async (clipId: string) => {
     try {
       const clip = clips.find((c) => c.id === clipId);
@@ -424,7 +487,10 @@ function App() {
         }
       }
 
-      invoke('paste_clip', { id: clipId }).catch(console.error);
+      const inputs = clip?.is_template ? await promptTemplateInputs(clipId) : undefined;
+      if (inputs === null) return;
+
+      invoke('paste_clip', { id: clipId, inputs }).catch(console.error);
     } catch (error) {
       console.error('Failed to paste clip:', error);
     }
@@ -438,7 +504,10 @@ function App() {
         await navigator.clipboard.write([new ClipboardItem({ [blob.type]: blob })]);
       }
 
-      await invoke('paste_clip', { id: clipId });
+      const inputs = clip?.is_template ? await promptTemplateInputs(clipId) : undefined;
+      if (inputs === null) return;
+
+      await invoke('paste_clip', { id: clipId, inputs });
 
       toast.success(t('common.copied'));
     } catch (error) {
@@ -544,29 +613,116 @@ function App() {
   const [editingFolderId, setEditingFolderId] = useState<string | null>(null);
 
   // AI Result State
-  const [aiResult, setAiResult] = useState({
+  const [aiResult, setAiResult] = useState<{
+    isOpen: boolean;
+    title: string;
+    content: string;
+    // Set while the result is still streaming in
+    requestId: string | null;
+    clipId: string | null;
+    action: string | null;
+    result: AiResult | null;
+    history: AiResult[];
+  }>({
     isOpen: false,
     title: '',
     content: '',
+    requestId: null,
+    clipId: null,
+    action: null,
+    result: null,
+    history: [],
   });
 
-  const handleAiAction = async (clipId: string, action: string, title: string) => {
+  const handleAiAction = async (
+    clipId: string,
+    action: string,
+    title: string,
+    refresh = false
+  ) => {
+    const requestId = crypto.randomUUID();
+    setAiResult({
+      isOpen: true,
+      title,
+      content: '',
+      requestId,
+      clipId,
+      action,
+      result: null,
+      history: [],
+    });
+    const unlisten = await listen<{ request_id: string; delta: string }>(
+      'ai-stream-delta',
+      (event) => {
+        if (event.payload.request_id !== requestId) return;
+        setAiResult((prev) =>
+          prev.requestId === requestId
+            ? { ...prev, content: prev.content + event.payload.delta }
+            : prev
+        );
+      }
+    );
     try {
-      const toastId = toast.loading(t('ai.processing'));
-      const result = await invoke<string>('ai_process_clip', { clipId, action });
-      toast.dismiss(toastId);
-      setAiResult({
-        isOpen: true,
-        title,
-        content: result,
+      const result = await invoke<AiResult>('ai_process_clip', {
+        clipId,
+        action,
+        requestId,
+        refresh,
       });
+      const history = await invoke<AiResult[]>('get_ai_history', { clipId }).catch(() => []);
+      setAiResult((prev) =>
+        prev.requestId === requestId
+          ? { ...prev, content: result.result, requestId: null, result, history }
+          : prev
+      );
     } catch (error) {
-      toast.dismiss();
-      console.error('AI Processing Failed:', error);
+      if (String(error) !== 'Cancelled') {
+        console.error('AI Processing Failed:', error);
+        toast.error(t('ai.error', { error: String(error) }));
+      }
+      setAiResult((prev) =>
+        prev.requestId === requestId ? { ...prev, isOpen: false, requestId: null } : prev
+      );
+    } finally {
+      unlisten();
+    }
+  };
+
+  const handleCloseAiResult = () => {
+    if (aiResult.requestId) {
+      invoke('cancel_ai_request', { requestId: aiResult.requestId });
+    }
+    setAiResult((prev) => ({ ...prev, isOpen: false, requestId: null }));
+  };
+
+  const handleRegenerateAiResult = () => {
+    if (aiResult.clipId && aiResult.action) {
+      handleAiAction(aiResult.clipId, aiResult.action, aiResult.title, true);
+    }
+  };
+
+  const handleSaveAiResultAsClip = async (result: AiResult) => {
+    try {
+      const clipId = await invoke<string>('save_ai_result_as_clip', { resultId: result.id });
+      setAiResult((prev) => ({
+        ...prev,
+        result:
+          prev.result?.id === result.id ? { ...result, result_clip_uuid: clipId } : prev.result,
+        history: prev.history.map((entry) =>
+          entry.id === result.id ? { ...entry, result_clip_uuid: clipId } : entry
+        ),
+      }));
+      toast.success(t('ai.savedAsClip'));
+    } catch (error) {
+      console.error('Failed to save AI result:', error);
       toast.error(t('ai.error', { error: String(error) }));
     }
   };
 
+  const handleSelectAiHistory = (result: AiResult) => {
+    setAiResult((prev) => ({ ...prev, content: result.result, result }));
+  };
+
   const handleContextMenu = useCallback(
     (e: React.MouseEvent, type: 'card' | 'folder', itemId: string) => {
       e.preventDefault();
@@ -663,30 +819,18 @@ function App() {
               options={
                 contextMenu.type === 'card'
                   ? [
-                      {
-                        label: `${settings?.ai_title_summarize || t('contextMenu.summarize')}`,
-                        onClick: () =>
-                          handleAiAction(contextMenu.itemId, 'summarize', t('ai.summary')),
-                      },
-                      {
-                        label: `${settings?.ai_title_translate || t('contextMenu.translate')}`,
-                        onClick: () =>
-                          handleAiAction(contextMenu.itemId, 'translate', t('ai.translation')),
-                      },
-                      {
-                        label: `${settings?.ai_title_explain_code || t('contextMenu.explainCode')}`,
-                        onClick: () =>
-                          handleAiAction(
-                            contextMenu.itemId,
-                            'explain_code',
-                            t('ai.codeExplanation')
-                          ),
-                      },
-                      {
-                        label: `${settings?.ai_title_fix_grammar || t('contextMenu.fixGrammar')}`,
-                        onClick: () =>
-                          handleAiAction(contextMenu.itemId, 'fix_grammar', t('ai.grammarCheck')),
-                      },
+                      ...(settings?.ai_actions ?? [])
+                        .filter((action) =>
+                          aiActionAppliesTo(
+                            action,
+                            clips.find((c) => c.id === contextMenu.itemId)?.clip_type
+                          )
+                        )
+                        .map((action) => ({
+                          label: action.title,
+                          onClick: () =>
+                            handleAiAction(contextMenu.itemId, action.id, action.title),
+                        })),
                       {
                         label: t('contextMenu.delete'),
                         danger: true,
@@ -779,7 +923,13 @@ function App() {
               isOpen={aiResult.isOpen}
               title={aiResult.title}
               content={aiResult.content}
-              onClose={() => setAiResult((prev) => ({ ...prev, isOpen: false }))}
+              isStreaming={aiResult.requestId !== null}
+              result={aiResult.result}
+              history={aiResult.history}
+              onRegenerate={handleRegenerateAiResult}
+              onSaveAsClip={handleSaveAiResultAsClip}
+              onSelectHistory={handleSelectAiHistory}
+              onClose={handleCloseAiResult}
             />
           </main>
           <Toaster richColors position="bottom-center" theme={effectiveTheme} />
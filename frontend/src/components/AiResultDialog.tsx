@@ -1,16 +1,37 @@
-import { X, Copy, Check } from 'lucide-react';
+import { X, Copy, Check, Loader2, RefreshCw, ClipboardPlus, History } from 'lucide-react';
 import { useState, useEffect } from 'react';
 import { toast } from 'sonner';
 import { useTranslation } from 'react-i18next';
+import { AiResult } from '../types';
 
 interface AiResultDialogProps {
   isOpen: boolean;
   title: string;
   content: string;
+  /** More text is still arriving; closing cancels it. */
+  isStreaming?: boolean;
+  /** The stored result being shown, once complete. */
+  result?: AiResult | null;
+  /** The clip's AI history, newest first. */
+  history?: AiResult[];
+  onRegenerate?: () => void;
+  onSaveAsClip?: (result: AiResult) => void;
+  onSelectHistory?: (result: AiResult) => void;
   onClose: () => void;
 }
 
-export function AiResultDialog({ isOpen, title, content, onClose }: AiResultDialogProps) {
+export function AiResultDialog({
+  isOpen,
+  title,
+  content,
+  isStreaming = false,
+  result = null,
+  history = [],
+  onRegenerate,
+  onSaveAsClip,
+  onSelectHistory,
+  onClose,
+}: AiResultDialogProps) {
   const { t } = useTranslation();
   const [copied, setCopied] = useState(false);
 
@@ -48,10 +69,34 @@ export function AiResultDialog({ isOpen, title, content, onClose }: AiResultDial
     <div className="fixed inset-0 z-50 flex items-center justify-center bg-black/50 backdrop-blur-sm">
       <div className="flex h-[80vh] w-[90vw] max-w-2xl flex-col rounded-2xl border border-border bg-background shadow-2xl">
         <div className="flex items-center justify-between border-b border-border p-4">
-          <h2 className="text-lg font-semibold">{title}</h2>
+          <h2 className="flex items-center gap-2 text-lg font-semibold">
+            {title}
+            {isStreaming && <Loader2 size={16} className="animate-spin text-muted-foreground" />}
+          </h2>
           <div className="flex items-center gap-2">
+            {onRegenerate && (
+              <button
+                onClick={onRegenerate}
+                disabled={isStreaming}
+                className="rounded-md p-2 hover:bg-accent hover:text-accent-foreground"
+                title={t('ai.regenerate')}
+              >
+                <RefreshCw size={18} />
+              </button>
+            )}
+            {onSaveAsClip && result && (
+              <button
+                onClick={() => onSaveAsClip(result)}
+                disabled={isStreaming || !!result.result_clip_uuid}
+                className="rounded-md p-2 hover:bg-accent hover:text-accent-foreground"
+                title={t('ai.saveAsClip')}
+              >
+                <ClipboardPlus size={18} />
+              </button>
+            )}
             <button
               onClick={handleCopy}
+              disabled={isStreaming}
               className="rounded-md p-2 hover:bg-accent hover:text-accent-foreground"
               title={t('settings.copyContent')}
             >
@@ -69,7 +114,37 @@ export function AiResultDialog({ isOpen, title, content, onClose }: AiResultDial
           <div className="prose dark:prose-invert max-w-none whitespace-pre-wrap text-sm leading-relaxed">
             {content}
           </div>
+          {result?.cached && !isStreaming && (
+            <p className="mt-4 text-xs text-muted-foreground">{t('ai.cached')}</p>
+          )}
         </div>
+        {history.length > 1 && (
+          <div className="max-h-40 overflow-y-auto border-t border-border p-4">
+            <h3 className="mb-2 flex items-center gap-2 text-xs font-medium text-muted-foreground">
+              <History size={14} />
+              {t('ai.history')}
+            </h3>
+            <ul className="space-y-1">
+              {history.map((entry) => (
+                <li key={entry.id}>
+                  <button
+                    onClick={() => onSelectHistory?.(entry)}
+                    className={`w-full truncate rounded-md px-2 py-1 text-left text-xs hover:bg-accent ${
+                      entry.id === result?.id ? 'bg-accent' : ''
+                    }`}
+                  >
+                    {[
+                      new Date(entry.created_at).toLocaleString(),
+                      entry.action_id,
+                      entry.model,
+                      entry.result,
+                    ].join(' · ')}
+                  </button>
+                </li>
+              ))}
+            </ul>
+          </div>
+        )}
       </div>
     </div>
   );
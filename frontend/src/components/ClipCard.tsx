@@ -229,7 +229,9 @@ export const ClipCard = memo(
             <span className="text-[11px] font-medium text-muted-foreground/50">
               {clip.clip_type === 'image'
                 ? t('clipList.imageSize', { size: imageSizeKb })
-                : t('clipList.textLength', { count: clip.content.length })}
+                : t('clipList.textLength', {
+                    count: clip.content_length ?? new TextEncoder().encode(clip.content).length,
+                  })}
             </span>
           </div>
         </div>
@@ -1,4 +1,11 @@
-import { Settings, FolderItem } from '../types';
+import {
+  AiAction,
+  AiOutputMode,
+  CLIP_TYPE_LABELS,
+  ClipType,
+  FolderItem,
+  Settings,
+} from '../types';
 import {
   X,
   Trash2,
@@ -35,64 +42,121 @@ interface SettingsPanelProps {
 
 type Tab = 'general' | 'ai' | 'folders';
 
-function PromptEditor({
-  label,
-  value,
-  titleValue,
-  placeholder,
+const AI_OUTPUT_MODES: AiOutputMode[] = ['metadata', 'new_clip', 'replace_clipboard'];
+
+function AiActionEditor({
+  action,
   onSave,
-  onSaveTitle,
+  onDelete,
 }: {
-  label: string;
-  value: string;
-  titleValue?: string;
-  placeholder: string;
-  onSave: (val: string) => void;
-  onSaveTitle?: (val: string) => void;
+  action: AiAction;
+  onSave: (action: AiAction) => void;
+  onDelete: () => void;
 }) {
   const { t } = useTranslation();
-  const [localValue, setLocalValue] = useState(value);
-  const [localTitle, setLocalTitle] = useState(titleValue || label);
+  const [local, setLocal] = useState(action);
 
   // Sync with prop if it changes externally
   useEffect(() => {
-    setLocalValue(value);
-  }, [value]);
+    setLocal(action);
+  }, [action]);
 
-  useEffect(() => {
-    setLocalTitle(titleValue || label);
-  }, [titleValue, label]);
+  const commit = (next: AiAction = local) => {
+    if (JSON.stringify(next) !== JSON.stringify(action)) {
+      onSave(next);
+    }
+  };
+
+  const toggleClipType = (clipType: ClipType) => {
+    const clip_types = local.clip_types.includes(clipType)
+      ? local.clip_types.filter((c) => c !== clipType)
+      : [...local.clip_types, clipType];
+    const next = { ...local, clip_types };
+    setLocal(next);
+    commit(next);
+  };
 
   return (
     <div className="space-y-2 rounded-lg border border-border/40 bg-accent/5 p-3">
       <div className="flex items-center justify-between gap-4">
         <input
           type="text"
-          value={localTitle}
-          onChange={(e) => setLocalTitle(e.target.value)}
-          onBlur={() => {
-            if (onSaveTitle && localTitle !== (titleValue || label)) {
-              onSaveTitle(localTitle);
-            }
-          }}
-          className="bg-transparent text-xs font-semibold text-foreground/70 outline-none transition-colors focus:text-primary"
-          title="Click to rename action"
+          value={local.title}
+          onChange={(e) => setLocal({ ...local, title: e.target.value })}
+          onBlur={() => commit()}
+          className="flex-1 bg-transparent text-xs font-semibold text-foreground/70 outline-none transition-colors focus:text-primary"
+          title={t('settings.actionName')}
         />
-        <span className="font-mono text-[10px] uppercase tracking-wider text-muted-foreground">
-          {t('settings.actionName')}
-        </span>
+        <input
+          type="text"
+          value={local.id}
+          onChange={(e) =>
+            setLocal({ ...local, id: e.target.value.toLowerCase().replace(/[^a-z0-9_]/g, '_') })
+          }
+          onBlur={() => commit()}
+          className="w-28 bg-transparent text-right font-mono text-[10px] text-muted-foreground outline-none focus:text-primary"
+          title={t('settings.aiActionId')}
+        />
+        <button
+          onClick={onDelete}
+          className="text-muted-foreground transition-colors hover:text-destructive"
+          title={t('settings.aiActionDelete')}
+        >
+          <Trash2 size={14} />
+        </button>
       </div>
       <textarea
-        value={localValue}
-        onChange={(e) => setLocalValue(e.target.value)}
-        onBlur={() => {
-          if (localValue !== value) {
-            onSave(localValue);
-          }
-        }}
-        placeholder={placeholder}
+        value={local.system_prompt}
+        onChange={(e) => setLocal({ ...local, system_prompt: e.target.value })}
+        onBlur={() => commit()}
+        placeholder={t('settings.aiActionPromptPlaceholder')}
         className="min-h-[60px] w-full resize-none rounded-md border border-border bg-input px-3 py-2 text-xs text-foreground transition-all focus:outline-none focus:ring-1 focus:ring-primary/30"
       />
+      <div className="flex gap-2">
+        <input
+          type="text"
+          value={local.model ?? ''}
+          onChange={(e) => setLocal({ ...local, model: e.target.value || null })}
+          onBlur={() => commit()}
+          placeholder={t('settings.aiActionModelPlaceholder')}
+          className="flex-1 rounded-md border border-border bg-input px-2 py-1 text-xs focus:outline-none focus:ring-1 focus:ring-primary/30"
+        />
+        <input
+          type="number"
+          min={0}
+          max={2}
+          step={0.1}
+          value={local.temperature}
+          onChange={(e) => setLocal({ ...local, temperature: Number(e.target.value) })}
+          onBlur={() => commit()}
+          title={t('settings.aiActionTemperature')}
+          className="w-16 rounded-md border border-border bg-input px-2 py-1 text-xs focus:outline-none focus:ring-1 focus:ring-primary/30"
+        />
+      </div>
+      <div className="flex flex-wrap gap-x-3 gap-y-1" title={t('settings.aiActionClipTypes')}>
+        {(Object.keys(CLIP_TYPE_LABELS) as ClipType[]).map((clipType) => (
+          <label key={clipType} className="flex items-center gap-1 text-[11px] text-muted-foreground">
+            <input
+              type="checkbox"
+              checked={local.clip_types.includes(clipType)}
+              onChange={() => toggleClipType(clipType)}
+            />
+            {CLIP_TYPE_LABELS[clipType]}
+          </label>
+        ))}
+      </div>
+      <Select
+        value={local.output}
+        onChange={(output) => {
+          const next = { ...local, output: output as AiOutputMode };
+          setLocal(next);
+          commit(next);
+        }}
+        options={AI_OUTPUT_MODES.map((mode) => ({
+          value: mode,
+          label: t(`settings.aiOutput.${mode}`),
+        }))}
+      />
     </div>
   );
 }
@@ -136,6 +200,14 @@ export function SettingsPanel({ settings: initialSettings, onClose }: SettingsPa
           if (updates.hotkey) {
             await invoke('register_global_shortcut', { hotkey: updates.hotkey });
           }
+          if (updates.paste_queue_hotkey !== undefined) {
+            await invoke('register_paste_queue_shortcut', { hotkey: updates.paste_queue_hotkey });
+          }
+          if (updates.quick_paste_modifiers !== undefined) {
+            await invoke('register_quick_paste_shortcuts', {
+              modifiers: updates.quick_paste_modifiers,
+            });
+          }
         } catch (error) {
           console.error(`Failed to save settings:`, error);
           toast.error(`Failed to save settings`);
@@ -592,6 +664,30 @@ export function SettingsPanel({ settings: initialSettings, onClose }: SettingsPa
                       </button>
                     </div>
 
+                    <div className="flex items-center justify-between rounded-lg border border-border bg-accent/20 p-3">
+                      <div>
+                        <span className="text-sm font-medium">
+                          {t('settings.restoreClipboardAfterPaste')}
+                        </span>
+                        <p className="text-xs text-muted-foreground">
+                          {t('settings.restoreClipboardAfterPasteDesc')}
+                        </p>
+                      </div>
+                      <button
+                        onClick={() =>
+                          updateSetting(
+                            'restore_clipboard_after_paste',
+                            !settings.restore_clipboard_after_paste
+                          )
+                        }
+                        className={`h-6 w-11 rounded-full transition-colors ${settings.restore_clipboard_after_paste ? 'bg-primary' : 'bg-accent'}`}
+                      >
+                        <div
+                          className={`h-5 w-5 rounded-full bg-white shadow-sm transition-transform ${settings.restore_clipboard_after_paste ? 'translate-x-5' : 'translate-x-0.5'}`}
+                        />
+                      </button>
+                    </div>
+
                     <div className="flex items-center justify-between rounded-lg border border-border bg-accent/20 p-3">
                       <div>
                         <span className="text-sm font-medium">
@@ -788,6 +884,21 @@ export function SettingsPanel({ settings: initialSettings, onClose }: SettingsPa
                             updates.ai_model = 'deepseek-chat';
                             setLocalBaseUrl('https://api.deepseek.com');
                             setLocalModel('deepseek-chat');
+                          } else if (newProvider === 'anthropic') {
+                            updates.ai_base_url = 'https://api.anthropic.com/v1';
+                            updates.ai_model = 'claude-sonnet-4-5';
+                            setLocalBaseUrl('https://api.anthropic.com/v1');
+                            setLocalModel('claude-sonnet-4-5');
+                          } else if (newProvider === 'gemini') {
+                            updates.ai_base_url = 'https://generativelanguage.googleapis.com/v1beta';
+                            updates.ai_model = 'gemini-2.5-flash';
+                            setLocalBaseUrl('https://generativelanguage.googleapis.com/v1beta');
+                            setLocalModel('gemini-2.5-flash');
+                          } else if (newProvider === 'ollama') {
+                            updates.ai_base_url = 'http://localhost:11434';
+                            updates.ai_model = 'llama3.2';
+                            setLocalBaseUrl('http://localhost:11434');
+                            setLocalModel('llama3.2');
                           }
 
                           updateSettings(updates);
@@ -795,6 +906,9 @@ export function SettingsPanel({ settings: initialSettings, onClose }: SettingsPa
                         options={[
                           { value: 'openai', label: t('settings.providerOpenAI') },
                           { value: 'deepseek', label: t('settings.providerDeepSeek') },
+                          { value: 'anthropic', label: t('settings.providerAnthropic') },
+                          { value: 'gemini', label: t('settings.providerGemini') },
+                          { value: 'ollama', label: t('settings.providerOllama') },
                           { value: 'custom', label: t('settings.providerCustom') },
                         ]}
                       />
@@ -854,49 +968,52 @@ export function SettingsPanel({ settings: initialSettings, onClose }: SettingsPa
 
                   <section className="space-y-4 border-t border-border/50 pt-4">
                     <h3 className="text-sm font-medium text-muted-foreground">
-                      {t('settings.customPrompts')}
+                      {t('settings.aiActions')}
                     </h3>
                     <p className="text-xs italic text-muted-foreground">
-                      {t('settings.customPromptsDesc')}
+                      {t('settings.aiActionsDesc')}
                     </p>
 
                     <div className="space-y-4">
-                      <PromptEditor
-                        label={t('settings.aiSummarize')}
-                        value={settings.ai_prompt_summarize || ''}
-                        titleValue={settings.ai_title_summarize}
-                        onSave={(val) => updateSetting('ai_prompt_summarize', val)}
-                        onSaveTitle={(val) => updateSetting('ai_title_summarize', val)}
-                        placeholder={t('settings.aiSummarizePlaceholder')}
-                      />
-
-                      <PromptEditor
-                        label={t('settings.aiTranslate')}
-                        value={settings.ai_prompt_translate || ''}
-                        titleValue={settings.ai_title_translate}
-                        onSave={(val) => updateSetting('ai_prompt_translate', val)}
-                        onSaveTitle={(val) => updateSetting('ai_title_translate', val)}
-                        placeholder={t('settings.aiTranslatePlaceholder')}
-                      />
-
-                      <PromptEditor
-                        label={t('settings.aiExplainCode')}
-                        value={settings.ai_prompt_explain_code || ''}
-                        titleValue={settings.ai_title_explain_code}
-                        onSave={(val) => updateSetting('ai_prompt_explain_code', val)}
-                        onSaveTitle={(val) => updateSetting('ai_title_explain_code', val)}
-                        placeholder={t('settings.aiExplainCodePlaceholder')}
-                      />
-
-                      <PromptEditor
-                        label={t('settings.aiFixGrammar')}
-                        value={settings.ai_prompt_fix_grammar || ''}
-                        titleValue={settings.ai_title_fix_grammar}
-                        onSave={(val) => updateSetting('ai_prompt_fix_grammar', val)}
-                        onSaveTitle={(val) => updateSetting('ai_title_fix_grammar', val)}
-                        placeholder={t('settings.aiFixGrammarPlaceholder')}
-                      />
+                      {(settings.ai_actions ?? []).map((action, index) => (
+                        <AiActionEditor
+                          key={index}
+                          action={action}
+                          onSave={(updated) =>
+                            updateSetting(
+                              'ai_actions',
+                              (settings.ai_actions ?? []).map((a, i) => (i === index ? updated : a))
+                            )
+                          }
+                          onDelete={() =>
+                            updateSetting(
+                              'ai_actions',
+                              (settings.ai_actions ?? []).filter((_, i) => i !== index)
+                            )
+                          }
+                        />
+                      ))}
                     </div>
+                    <button
+                      onClick={() =>
+                        updateSetting('ai_actions', [
+                          ...(settings.ai_actions ?? []),
+                          {
+                            id: `action_${Date.now().toString(36)}`,
+                            title: t('settings.aiActionNewTitle'),
+                            system_prompt: t('settings.aiActionNewPrompt'),
+                            model: null,
+                            temperature: 0.7,
+                            clip_types: [],
+                            output: 'metadata',
+                          },
+                        ])
+                      }
+                      className="btn btn-secondary px-3"
+                    >
+                      <Plus size={16} className="mr-1" />
+                      {t('settings.aiActionAdd')}
+                    </button>
                   </section>
                 </>
               )}
@@ -8,6 +8,141 @@ export interface ClipboardItem {
   source_app: string | null;
   source_icon: string | null;
   metadata: string | null;
+  is_sensitive?: boolean;
+  is_template?: boolean;
+  /** Seconds after pasting to clear the system clipboard; falls back to the folder's. */
+  clear_after_secs?: number | null;
+  /** Full length in characters when `content` is a cut-down list preview. */
+  content_length?: number;
+  search_match?: SearchMatch;
+}
+
+export interface SearchMatch {
+  score: number;
+  field: string;
+  ranges: [number, number][];
+}
+
+export interface ClipPage {
+  items: ClipboardItem[];
+  next_cursor: string | null;
+}
+
+export interface PasteTransform {
+  id: string;
+  name: string;
+}
+
+export type MergeSeparator =
+  | { type: 'newline' }
+  | { type: 'comma' }
+  | { type: 'tab' }
+  | { type: 'custom'; value: string };
+
+export interface MergeOptions {
+  separator: MergeSeparator;
+  /** Applied to each item: `{{item}}` is its text, `{{index}}` its 1-based position. */
+  wrapper?: string | null;
+  direction?: 'vertical' | 'horizontal';
+  output: 'paste' | 'new_clip';
+}
+
+export type PasteChord =
+  | 'default'
+  | 'ctrl_v'
+  | 'ctrl_shift_v'
+  | 'shift_insert'
+  | 'cmd_v'
+  | 'cmd_shift_v';
+
+export interface AppPasteProfile {
+  /** App name, executable, full path or bundle id, matched case-insensitively. */
+  app: string;
+  chord: PasteChord;
+  delay_ms: number;
+  plain_text: boolean;
+  restore_clipboard: boolean;
+}
+
+/** Whether clipboard changes are being recorded; `resume_at` ends a timed pause. */
+export interface CaptureState {
+  paused: boolean;
+  resume_at: string | null;
+}
+
+export type ShortcutAction =
+  | { type: 'toggle_window' }
+  | { type: 'paste_last' }
+  | { type: 'pause_capture' }
+  | { type: 'open_folder'; value: number }
+  /** An `AiAction` id, run on the most recent clip. */
+  | { type: 'run_ai_action'; value: string }
+  | { type: 'clear_clipboard' };
+
+export interface ShortcutBinding {
+  hotkey: string;
+  action: ShortcutAction;
+}
+
+/** A binding after registration; `error` says why it isn't active. */
+export interface BindingStatus extends ShortcutBinding {
+  error: string | null;
+}
+
+/** Payload of the `type-out-finished` event. */
+export interface TypeOutResult {
+  typed: number;
+  total: number;
+  aborted: boolean;
+}
+
+export interface TemplateError {
+  message: string;
+  /** Character offset of the placeholder in the template. */
+  position: number;
+}
+
+export interface TemplateInfo {
+  errors: TemplateError[];
+  /** `{{input:…}}` names to prompt for before pasting. */
+  inputs: string[];
+  uses_clipboard: boolean;
+  has_cursor: boolean;
+}
+
+export interface RuleConditions {
+  source_app?: string | null;
+  content_regex?: string | null;
+  clip_types?: ClipType[];
+  min_size?: number | null;
+  max_size?: number | null;
+}
+
+export type RuleAction =
+  | { type: 'move_to_folder'; folder_id: number }
+  | { type: 'add_tags'; tags: string[] }
+  /** An `AiAction` id. */
+  | { type: 'run_ai'; action: string }
+  | { type: 'mark_sensitive' }
+  | { type: 'discard' };
+
+export interface RoutingRule {
+  /** 0 for a rule that hasn't been saved yet. */
+  id: number;
+  name: string;
+  enabled: boolean;
+  position: number;
+  stop_processing: boolean;
+  conditions: RuleConditions;
+  actions: RuleAction[];
+}
+
+export interface RuleTestResult {
+  scanned: number;
+  matched: number;
+  /** Clips left unchecked once the time budget ran out. */
+  skipped: number;
+  clips: ClipboardItem[];
 }
 
 export interface FolderItem {
@@ -17,6 +152,9 @@ export interface FolderItem {
   color: string | null;
   is_system: boolean;
   item_count: number;
+  /** Saved search query for smart folders; `null` for regular folders. */
+  query?: string | null;
+  clear_after_secs?: number | null;
 }
 
 export interface Settings {
@@ -25,25 +163,66 @@ export interface Settings {
   startup_with_windows: boolean;
   show_in_taskbar: boolean;
   hotkey: string;
+  /** Pastes the next queued clip; empty disables it. */
+  paste_queue_hotkey: string;
+  quick_paste_modifiers?: string;
+  quick_paste_folder_id?: number | null;
+  type_out_delay_ms: number;
+  type_out_max_chars: number;
+  paste_profiles: AppPasteProfile[];
+  shortcuts?: ShortcutBinding[];
   theme: string;
   language?: string;
   mica_effect?: string;
   auto_paste: boolean;
+  restore_clipboard_after_paste?: boolean;
   ignore_ghost_clips: boolean;
   ai_provider?: string;
   ai_api_key?: string;
   ai_model?: string;
   ai_base_url?: string;
-  ai_prompt_summarize?: string;
-  ai_prompt_translate?: string;
-  ai_prompt_explain_code?: string;
-  ai_prompt_fix_grammar?: string;
-  ai_title_summarize?: string;
-  ai_title_translate?: string;
-  ai_title_explain_code?: string;
-  ai_title_fix_grammar?: string;
+  ai_actions?: AiAction[];
 }
 
+/** Where an AI action's result goes. */
+export type AiOutputMode = 'metadata' | 'new_clip' | 'replace_clipboard';
+
+export interface AiAction {
+  /** Lowercase letters, digits and `_`; rules and shortcuts refer to it. */
+  id: string;
+  title: string;
+  system_prompt: string;
+  /** Used instead of `ai_model` when set. */
+  model: string | null;
+  temperature: number;
+  /** Clip types it's offered for; empty for text, HTML and URLs. */
+  clip_types: ClipType[];
+  output: AiOutputMode;
+}
+
+/** One entry of a clip's AI history. */
+export interface AiResult {
+  id: number;
+  clip_uuid: string;
+  action_id: string;
+  model: string;
+  prompt_hash: string;
+  content_hash: string;
+  result: string;
+  /** The clip the result was saved as, if it was. */
+  result_clip_uuid: string | null;
+  created_at: string;
+  /** Reused from an earlier request for the same prompt, model and content. */
+  cached: boolean;
+}
+
+/** Whether `action` is offered for clips of `clipType`, matching the backend's check. */
+export const aiActionAppliesTo = (action: AiAction, clipType: string | undefined): boolean =>
+  !!clipType &&
+  (action.clip_types.length === 0
+    ? ['text', 'html', 'url'].includes(clipType)
+    : action.clip_types.includes(clipType as ClipType));
+
 export type ClipType = 'text' | 'image' | 'html' | 'rtf' | 'file' | 'url';
 
 export const CLIP_TYPE_LABELS: Record<ClipType, string> = {
@@ -1,91 +1,731 @@
+use once_cell::sync::Lazy;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
+use std::collections::HashMap;
 use std::error::Error;
+use std::sync::Arc;
+use std::time::Duration;
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use image::codecs::png::PngEncoder;
+use image::{ImageEncoder, ImageFormat};
+
+use crate::ai_provider::{provider_for, AiProvider, Chat, ChatImage, StreamEvent};
+use crate::models::{AiAction, AppSettings};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AiConfig {
-    pub provider: String, // "openai", "deepseek", "custom"
+    pub provider: String, // "openai", "deepseek", "anthropic", "gemini", "ollama", "custom"
     pub api_key: String,
     pub model: String,
     pub base_url: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub enum AiAction {
-    Summarize,
-    Translate,
-    ExplainCode,
-    FixGrammar,
+impl AiConfig {
+    pub fn from_settings(settings: &AppSettings) -> Self {
+        Self {
+            provider: settings.ai_provider.clone(),
+            api_key: settings.ai_api_key.clone(),
+            model: settings.ai_model.clone(),
+            base_url: if settings.ai_base_url.is_empty() {
+                None
+            } else {
+                Some(settings.ai_base_url.clone())
+            },
+        }
+    }
+
+    /// The settings' config, with the action's model if it has its own.
+    pub fn for_action(settings: &AppSettings, action: &AiAction) -> Self {
+        let mut config = Self::from_settings(settings);
+        if let Some(model) = action.model.as_deref().filter(|m| !m.trim().is_empty()) {
+            config.model = model.trim().to_string();
+        }
+        config
+    }
+
+    /// Where embeddings are requested: `ai_embedding_base_url` if set, otherwise the chat
+    /// settings when their provider serves embeddings. The error says why there's nowhere.
+    pub fn for_embeddings(settings: &AppSettings) -> Result<Self, String> {
+        let base_url = settings.ai_embedding_base_url.trim();
+        if !base_url.is_empty() {
+            return Ok(Self {
+                provider: "custom".to_string(),
+                api_key: settings.ai_embedding_api_key.clone(),
+                model: settings.ai_embedding_model.clone(),
+                base_url: Some(base_url.to_string()),
+            });
+        }
+        let mut config = Self::from_settings(settings);
+        if !config.provider().serves_embeddings() {
+            return Err(format!(
+                "The {} provider has no embeddings endpoint; set an embeddings base URL",
+                config.provider
+            ));
+        }
+        if config.api_key.is_empty() {
+            return Err("AI API Key is missing in settings".to_string());
+        }
+        config.model = settings.ai_embedding_model.clone();
+        Ok(config)
+    }
+
+    pub fn provider(&self) -> Box<dyn AiProvider> {
+        provider_for(&self.provider)
+    }
+
+    /// The OpenAI-compatible endpoint under the base URL, used for embeddings.
+    fn endpoint(&self, path: &str) -> String {
+        let base_url = self
+            .base_url
+            .as_deref()
+            .unwrap_or("https://api.openai.com/v1");
+        format!("{}/{}", base_url.trim_end_matches('/'), path)
+    }
 }
 
 #[derive(Deserialize)]
-struct OpenAiResponse {
-    choices: Vec<Choice>,
+struct EmbeddingResponse {
+    data: Vec<EmbeddingData>,
 }
 
 #[derive(Deserialize)]
-struct Choice {
-    message: Message,
+struct EmbeddingData {
+    embedding: Vec<f32>,
+    #[serde(default)]
+    index: usize,
 }
 
-#[derive(Deserialize)]
-struct Message {
-    content: String,
+/// Send a chat request, racing `cancel` if given.
+async fn send_chat(
+    provider: &dyn AiProvider,
+    config: &AiConfig,
+    chat: &Chat<'_>,
+    stream: bool,
+    cancel: Option<&tokio::sync::Notify>,
+) -> Result<reqwest::Response, Box<dyn Error>> {
+    let request = provider
+        .chat_request(&Client::new(), config, chat, stream)
+        .send();
+    let res = match cancel {
+        Some(cancel) => tokio::select! {
+            res = request => res?,
+            _ = cancel.notified() => return Err("Cancelled".into()),
+        },
+        None => request.await?,
+    };
+
+    if !res.status().is_success() {
+        let status = res.status();
+        let error_text = res.text().await?;
+        return Err(provider.parse_error(status, &error_text).into());
+    }
+    Ok(res)
+}
+
+/// Longest side of an image sent to a vision model. Larger images cost more tokens and are
+/// scaled down by the providers anyway.
+const MAX_IMAGE_SIDE: u32 = 1568;
+
+/// Scale an image clip down to `MAX_IMAGE_SIDE` and encode it for a vision request.
+pub fn prepare_image(bytes: &[u8]) -> Result<ChatImage, String> {
+    let img = image::load_from_memory(bytes).map_err(|e| e.to_string())?;
+    let fits = img.width().max(img.height()) <= MAX_IMAGE_SIDE;
+    let png = if fits && image::guess_format(bytes).ok() == Some(ImageFormat::Png) {
+        bytes.to_vec()
+    } else {
+        let img = if fits {
+            img
+        } else {
+            img.resize(
+                MAX_IMAGE_SIDE,
+                MAX_IMAGE_SIDE,
+                image::imageops::FilterType::Triangle,
+            )
+        };
+        let rgba = img.to_rgba8();
+        let mut png = Vec::new();
+        PngEncoder::new(&mut png)
+            .write_image(
+                rgba.as_raw(),
+                rgba.width(),
+                rgba.height(),
+                image::ColorType::Rgba8,
+            )
+            .map_err(|e| e.to_string())?;
+        png
+    };
+    Ok(ChatImage {
+        media_type: "image/png",
+        data: BASE64.encode(png),
+    })
 }
 
+/// Run `action` on `text`, or on `image` for vision models, returning the completion.
 pub async fn process_text(
     text: &str,
-    action: AiAction,
+    image: Option<&ChatImage>,
+    action: &AiAction,
     config: &AiConfig,
-    custom_prompt: Option<String>,
 ) -> Result<String, Box<dyn Error>> {
-    let client = Client::new();
+    let provider = config.provider();
+    let chat = Chat {
+        system: &action.system_prompt,
+        user: text,
+        image,
+        temperature: action.temperature,
+    };
 
-    let system_prompt = if let Some(prompt) = custom_prompt {
-        prompt
-    } else {
-        match action {
-            AiAction::Summarize => "You are a helpful assistant. Summarize the following text concisely.".to_string(),
-            AiAction::Translate => "You are a helpful assistant. Translate the following text to English (or the user's likely preferred language based on context).".to_string(),
-            AiAction::ExplainCode => "You are a helpful assistant. Explain what the following code does.".to_string(),
-            AiAction::FixGrammar => "You are a helpful assistant. Fix the grammar and improve the style of the following text.".to_string(),
+    let res = send_chat(provider.as_ref(), config, &chat, false, None).await?;
+    let body = res.text().await?;
+    Ok(provider.parse_response(&body)?)
+}
+
+/// Splits a streamed response into lines. Bytes are buffered until a full line arrives, so
+/// chunks may split lines and UTF-8 sequences.
+#[derive(Default)]
+pub struct LineBuffer {
+    buf: Vec<u8>,
+}
+
+impl LineBuffer {
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        let mut lines = Vec::new();
+        self.buf.extend_from_slice(chunk);
+        while let Some(end) = self.buf.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.buf.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            lines.push(line.trim_end_matches(['\r', '\n']).to_string());
         }
+        lines
+    }
+
+    /// Whatever followed the last newline, once the stream has ended.
+    pub fn finish(&mut self) -> Option<String> {
+        let rest = String::from_utf8_lossy(&std::mem::take(&mut self.buf)).into_owned();
+        Some(rest).filter(|rest| !rest.trim().is_empty())
+    }
+}
+
+/// Like `process_text`, streaming the completion: `on_delta` gets each piece of text as it
+/// arrives and the full text is returned at the end. A `cancel` notification stops it with
+/// a "Cancelled" error.
+pub async fn process_text_stream(
+    text: &str,
+    image: Option<&ChatImage>,
+    action: &AiAction,
+    config: &AiConfig,
+    cancel: &tokio::sync::Notify,
+    mut on_delta: impl FnMut(&str),
+) -> Result<String, Box<dyn Error>> {
+    let provider = config.provider();
+    let chat = Chat {
+        system: &action.system_prompt,
+        user: text,
+        image,
+        temperature: action.temperature,
     };
 
-    let base_url = config
-        .base_url
-        .as_deref()
-        .unwrap_or("https://api.openai.com/v1");
-    let url = format!("{}/chat/completions", base_url.trim_end_matches('/'));
+    let mut res = send_chat(provider.as_ref(), config, &chat, true, Some(cancel)).await?;
+
+    let mut lines = LineBuffer::default();
+    let mut result = String::new();
+    loop {
+        let chunk = tokio::select! {
+            chunk = res.chunk() => chunk?,
+            _ = cancel.notified() => return Err("Cancelled".into()),
+        };
+        let (batch, finished) = match chunk {
+            Some(chunk) => (lines.push(&chunk), false),
+            None => (lines.finish().into_iter().collect(), true),
+        };
+        for line in &batch {
+            match provider.parse_stream_line(line) {
+                Some(StreamEvent::Delta(delta)) => {
+                    on_delta(&delta);
+                    result.push_str(&delta);
+                }
+                Some(StreamEvent::Done) => return Ok(result),
+                Some(StreamEvent::Error(e)) => return Err(format!("API Error: {}", e).into()),
+                None => {}
+            }
+        }
+        if finished {
+            break;
+        }
+    }
+
+    if result.is_empty() {
+        Err("No response from AI".into())
+    } else {
+        Ok(result)
+    }
+}
+
+/// Streaming requests in flight, by request id, so they can be cancelled.
+static REQUESTS: Lazy<parking_lot::Mutex<HashMap<String, Arc<tokio::sync::Notify>>>> =
+    Lazy::new(|| parking_lot::Mutex::new(HashMap::new()));
+
+/// Track a streaming request; pass the returned handle to `process_text_stream`.
+pub fn begin_request(request_id: &str) -> Arc<tokio::sync::Notify> {
+    let cancel = Arc::new(tokio::sync::Notify::new());
+    REQUESTS
+        .lock()
+        .insert(request_id.to_string(), cancel.clone());
+    cancel
+}
+
+pub fn end_request(request_id: &str) {
+    REQUESTS.lock().remove(request_id);
+}
+
+/// Stop a streaming request. False if it already finished or never existed.
+pub fn cancel_request(request_id: &str) -> bool {
+    match REQUESTS.lock().remove(request_id) {
+        Some(cancel) => {
+            cancel.notify_one();
+            true
+        }
+        None => false,
+    }
+}
+
+/// One vector per input, in input order, from the OpenAI-compatible `/embeddings` endpoint.
+/// `config` comes from `AiConfig::for_embeddings`.
+pub async fn embed_texts(
+    texts: &[String],
+    model: &str,
+    config: &AiConfig,
+) -> Result<Vec<Vec<f32>>, Box<dyn Error>> {
+    let client = Client::new();
+    let url = config.endpoint("embeddings");
 
     let body = json!({
-        "model": config.model,
-        "messages": [
-            {"role": "system", "content": system_prompt},
-            {"role": "user", "content": text}
-        ],
-        "temperature": 0.7
+        "model": model,
+        "input": texts,
     });
 
-    let res = client
+    let mut request = client
         .post(&url)
-        .header("Authorization", format!("Bearer {}", config.api_key))
         .header("Content-Type", "application/json")
-        .json(&body)
-        .send()
-        .await?;
+        .timeout(Duration::from_secs(60))
+        .json(&body);
+    // Local embedding servers often need no key
+    if !config.api_key.is_empty() {
+        request = request.header("Authorization", format!("Bearer {}", config.api_key));
+    }
+    let res = request.send().await?;
 
     if !res.status().is_success() {
         let error_text = res.text().await?;
         return Err(format!("API Error: {}", error_text).into());
     }
 
-    let response_data: OpenAiResponse = res.json().await?;
+    let mut response_data: EmbeddingResponse = res.json().await?;
+    if response_data.data.len() != texts.len() {
+        return Err(format!(
+            "Expected {} embeddings, got {}",
+            texts.len(),
+            response_data.data.len()
+        )
+        .into());
+    }
+    response_data.data.sort_by_key(|d| d.index);
+    Ok(response_data
+        .data
+        .into_iter()
+        .map(|d| d.embedding)
+        .collect())
+}
 
-    if let Some(choice) = response_data.choices.first() {
-        Ok(choice.message.content.clone())
-    } else {
-        Err("No response from AI".into())
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    /// Serve one canned HTTP response and hand back the raw request.
+    fn stub_server(body: &'static str) -> (String, std::thread::JoinHandle<String>) {
+        stub_server_with_status("200 OK", body)
+    }
+
+    fn stub_server_with_status(
+        status: &'static str,
+        body: &'static str,
+    ) -> (String, std::thread::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/v1", listener.local_addr().unwrap());
+        let handle = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            loop {
+                let n = stream.read(&mut buf).unwrap();
+                request.extend_from_slice(&buf[..n]);
+                let text = String::from_utf8_lossy(&request);
+                if let Some(header_end) = text.find("\r\n\r\n") {
+                    let length = text[..header_end]
+                        .lines()
+                        .find_map(|l| {
+                            l.to_lowercase()
+                                .strip_prefix("content-length:")
+                                .map(|v| v.trim().parse::<usize>().unwrap())
+                        })
+                        .unwrap_or(0);
+                    if request.len() >= header_end + 4 + length {
+                        break;
+                    }
+                }
+            }
+            let response = format!(
+                "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            stream.write_all(response.as_bytes()).unwrap();
+            String::from_utf8_lossy(&request).into_owned()
+        });
+        (url, handle)
+    }
+
+    fn config(provider: &str, base_url: String) -> AiConfig {
+        AiConfig {
+            provider: provider.to_string(),
+            api_key: "key".to_string(),
+            model: "chat".to_string(),
+            base_url: Some(base_url),
+        }
+    }
+
+    fn action() -> AiAction {
+        AiAction {
+            id: "summarize".to_string(),
+            title: "Summarize".to_string(),
+            system_prompt: "Summarize this.".to_string(),
+            temperature: 0.2,
+            ..Default::default()
+        }
+    }
+
+    async fn stream(config: &AiConfig) -> (String, Vec<String>) {
+        let mut deltas = Vec::new();
+        let result = process_text_stream(
+            "text",
+            None,
+            &action(),
+            config,
+            &tokio::sync::Notify::new(),
+            |delta| deltas.push(delta.to_string()),
+        )
+        .await
+        .unwrap();
+        (result, deltas)
+    }
+
+    #[test]
+    fn test_line_buffer_across_chunks() {
+        let mut lines = LineBuffer::default();
+        assert!(lines.push(b"data: {\"a\":").is_empty());
+        assert_eq!(
+            lines.push(b"1}\r\n\n: comment\ndata:[DONE]\n{\"done\""),
+            vec![r#"data: {"a":1}"#, "", ": comment", "data:[DONE]"]
+        );
+        assert_eq!(lines.finish().as_deref(), Some(r#"{"done""#));
+        // A multi-byte character split between chunks
+        let bytes = "data: é\n".as_bytes();
+        assert!(lines.push(&bytes[..7]).is_empty());
+        assert_eq!(lines.push(&bytes[7..]), vec!["data: é"]);
+    }
+
+    #[tokio::test]
+    async fn test_openai_compatible() {
+        let (url, server) =
+            stub_server(r#"{"choices":[{"message":{"role":"assistant","content":"Hi"}}]}"#);
+        let result = process_text("text", None, &action(), &config("openai", url))
+            .await
+            .unwrap();
+        assert_eq!(result, "Hi");
+        let request = server.join().unwrap();
+        assert!(request.starts_with("POST /v1/chat/completions "));
+        assert!(request.to_lowercase().contains("authorization: bearer key"));
+        assert!(request.contains(r#""role":"user""#));
+        assert!(request.contains(r#""content":"text""#));
+
+        let (url, server) = stub_server(concat!(
+            "data: {\"choices\":[{\"delta\":{\"role\":\"assistant\"}}]}\n\n",
+            "data: {\"choices\":[{\"delta\":{\"content\":\"Hel\"}}]}\n\n",
+            "data: {\"choices\":[{\"delta\":{\"content\":\"lo\"}}]}\n\n",
+            "data: [DONE]\n\n"
+        ));
+        let (result, deltas) = stream(&config("custom", url)).await;
+        assert_eq!(result, "Hello");
+        assert_eq!(deltas, vec!["Hel", "lo"]);
+        assert!(server.join().unwrap().contains(r#""stream":true"#));
+    }
+
+    #[tokio::test]
+    async fn test_anthropic() {
+        let (url, server) = stub_server(
+            r#"{"content":[{"type":"text","text":"Hi"},{"type":"text","text":" there"}]}"#,
+        );
+        let result = process_text("text", None, &action(), &config("anthropic", url))
+            .await
+            .unwrap();
+        assert_eq!(result, "Hi there");
+        let request = server.join().unwrap();
+        let lower = request.to_lowercase();
+        assert!(request.starts_with("POST /v1/messages "));
+        assert!(lower.contains("x-api-key: key"));
+        assert!(lower.contains("anthropic-version: 2023-06-01"));
+        assert!(!lower.contains("authorization:"));
+        assert!(request.contains(r#""max_tokens":4096"#));
+        assert!(request.contains(r#""system":"Summarize this.""#));
+        assert!(request.contains(r#""temperature":0.2"#));
+
+        let (url, server) = stub_server(concat!(
+            "event: message_start\ndata: {\"type\":\"message_start\",\"message\":{}}\n\n",
+            "event: content_block_delta\ndata: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"Hel\"}}\n\n",
+            "event: content_block_delta\ndata: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"lo\"}}\n\n",
+            "event: message_stop\ndata: {\"type\":\"message_stop\"}\n\n"
+        ));
+        let (result, deltas) = stream(&config("anthropic", url)).await;
+        assert_eq!(result, "Hello");
+        assert_eq!(deltas, vec!["Hel", "lo"]);
+        assert!(server.join().unwrap().contains(r#""stream":true"#));
+    }
+
+    #[tokio::test]
+    async fn test_gemini() {
+        let (url, server) =
+            stub_server(r#"{"candidates":[{"content":{"role":"model","parts":[{"text":"Hi"}]}}]}"#);
+        let result = process_text("text", None, &action(), &config("gemini", url))
+            .await
+            .unwrap();
+        assert_eq!(result, "Hi");
+        let request = server.join().unwrap();
+        assert!(request.starts_with("POST /v1/models/chat:generateContent "));
+        assert!(request.to_lowercase().contains("x-goog-api-key: key"));
+        assert!(request.contains(r#""parts":[{"text":"text"}]"#));
+        assert!(request.contains(r#""systemInstruction":"#));
+
+        // No end marker: the stream just ends, here without a trailing newline
+        let (url, server) = stub_server(concat!(
+            "data: {\"candidates\":[{\"content\":{\"parts\":[{\"text\":\"Hel\"}]}}]}\r\n\r\n",
+            "data: {\"candidates\":[{\"content\":{\"parts\":[{\"text\":\"lo\"}]}}]}"
+        ));
+        let (result, deltas) = stream(&config("gemini", url)).await;
+        assert_eq!(result, "Hello");
+        assert_eq!(deltas, vec!["Hel", "lo"]);
+        assert!(server
+            .join()
+            .unwrap()
+            .starts_with("POST /v1/models/chat:streamGenerateContent?alt=sse "));
+    }
+
+    #[tokio::test]
+    async fn test_ollama() {
+        let (url, server) =
+            stub_server(r#"{"message":{"role":"assistant","content":"Hi"},"done":true}"#);
+        let mut keyless = config("ollama", url);
+        keyless.api_key = String::new();
+        assert!(!keyless.provider().requires_api_key());
+        let result = process_text("text", None, &action(), &keyless)
+            .await
+            .unwrap();
+        assert_eq!(result, "Hi");
+        let request = server.join().unwrap();
+        assert!(request.starts_with("POST /v1/api/chat "));
+        assert!(!request.to_lowercase().contains("authorization:"));
+        assert!(request.contains(r#""stream":false"#));
+
+        let (url, server) = stub_server(concat!(
+            "{\"message\":{\"role\":\"assistant\",\"content\":\"Hel\"},\"done\":false}\n",
+            "{\"message\":{\"role\":\"assistant\",\"content\":\"lo\"},\"done\":false}\n",
+            "{\"message\":{\"role\":\"assistant\",\"content\":\"\"},\"done\":true}\n"
+        ));
+        let (result, deltas) = stream(&config("ollama", url)).await;
+        assert_eq!(result, "Hello");
+        assert_eq!(deltas, vec!["Hel", "lo"]);
+        assert!(server.join().unwrap().contains(r#""stream":true"#));
+    }
+
+    #[tokio::test]
+    async fn test_provider_errors() {
+        let cases = [
+            (
+                "openai",
+                "401 Unauthorized",
+                r#"{"error":{"message":"Incorrect API key provided","type":"invalid_request_error"}}"#,
+                "API Error (401 Unauthorized): Incorrect API key provided",
+            ),
+            (
+                "anthropic",
+                "401 Unauthorized",
+                r#"{"type":"error","error":{"type":"authentication_error","message":"invalid x-api-key"}}"#,
+                "API Error (401 Unauthorized): invalid x-api-key",
+            ),
+            (
+                "gemini",
+                "400 Bad Request",
+                r#"[{"error":{"code":400,"message":"API key not valid.","status":"INVALID_ARGUMENT"}}]"#,
+                "API Error (400 Bad Request): API key not valid.",
+            ),
+            (
+                "ollama",
+                "404 Not Found",
+                r#"{"error":"model \"chat\" not found"}"#,
+                r#"API Error (404 Not Found): model "chat" not found"#,
+            ),
+            (
+                "custom",
+                "502 Bad Gateway",
+                "upstream unavailable",
+                "API Error (502 Bad Gateway): upstream unavailable",
+            ),
+        ];
+        for (provider, status, body, expected) in cases {
+            let (url, server) = stub_server_with_status(status, body);
+            let err = process_text("text", None, &action(), &config(provider, url))
+                .await
+                .unwrap_err();
+            assert_eq!(err.to_string(), expected, "{}", provider);
+            server.join().unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stream_error_event() {
+        let (url, server) = stub_server(concat!(
+            "event: content_block_delta\ndata: {\"type\":\"content_block_delta\",\"delta\":{\"type\":\"text_delta\",\"text\":\"Hel\"}}\n\n",
+            "event: error\ndata: {\"type\":\"error\",\"error\":{\"type\":\"overloaded_error\",\"message\":\"Overloaded\"}}\n\n"
+        ));
+        let err = process_text_stream(
+            "text",
+            None,
+            &action(),
+            &config("anthropic", url),
+            &tokio::sync::Notify::new(),
+            |_| {},
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.to_string(), "API Error: Overloaded");
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_prepare_image_downscales() {
+        let png = |width, height| {
+            let mut bytes = Vec::new();
+            PngEncoder::new(&mut bytes)
+                .write_image(
+                    &vec![255; (width * height * 4) as usize],
+                    width,
+                    height,
+                    image::ColorType::Rgba8,
+                )
+                .unwrap();
+            bytes
+        };
+        let decode = |image: ChatImage| {
+            image::load_from_memory(&BASE64.decode(image.data).unwrap()).unwrap()
+        };
+
+        let small = png(40, 20);
+        assert_eq!(prepare_image(&small).unwrap().data, BASE64.encode(&small));
+
+        let large = decode(prepare_image(&png(3136, 400)).unwrap());
+        assert_eq!((large.width(), large.height()), (1568, 200));
+    }
+
+    #[tokio::test]
+    async fn test_vision_requests() {
+        let image = ChatImage {
+            media_type: "image/png",
+            data: "aW1n".to_string(),
+        };
+        let cases = [
+            (
+                "openai",
+                r#"{"choices":[{"message":{"content":"A cat"}}]}"#,
+                r#""url":"data:image/png;base64,aW1n""#,
+            ),
+            (
+                "anthropic",
+                r#"{"content":[{"type":"text","text":"A cat"}]}"#,
+                r#""data":"aW1n""#,
+            ),
+            (
+                "gemini",
+                r#"{"candidates":[{"content":{"parts":[{"text":"A cat"}]}}]}"#,
+                r#""mimeType":"image/png""#,
+            ),
+            (
+                "ollama",
+                r#"{"message":{"content":"A cat"},"done":true}"#,
+                r#""images":["aW1n"]"#,
+            ),
+        ];
+        for (provider, response, expected) in cases {
+            let (url, server) = stub_server(response);
+            let result = process_text("", Some(&image), &action(), &config(provider, url))
+                .await
+                .unwrap();
+            assert_eq!(result, "A cat");
+            let request = server.join().unwrap();
+            assert!(request.contains(expected), "{}: {}", provider, request);
+            assert!(request.contains("aW1n"), "{}", provider);
+            // No empty text part next to the image
+            assert!(!request.contains(r#""text":"""#), "{}", provider);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_embed_texts_orders_by_index() {
+        let (url, server) = stub_server(
+            r#"{"data":[{"index":1,"embedding":[0.0,1.0]},{"index":0,"embedding":[1.0,0.0]}]}"#,
+        );
+        let vectors = embed_texts(
+            &["a".to_string(), "b".to_string()],
+            "embed-model",
+            &config("custom", url),
+        )
+        .await
+        .unwrap();
+        assert_eq!(vectors, vec![vec![1.0, 0.0], vec![0.0, 1.0]]);
+
+        let request = server.join().unwrap();
+        assert!(request.starts_with("POST /v1/embeddings "));
+        assert!(request.contains(r#""model":"embed-model""#));
+    }
+
+    #[test]
+    fn test_embedding_config() {
+        let mut settings = AppSettings {
+            ai_api_key: "key".to_string(),
+            ..Default::default()
+        };
+        let config = AiConfig::for_embeddings(&settings).unwrap();
+        assert_eq!(config.model, "text-embedding-3-small");
+        assert_eq!(config.api_key, "key");
+
+        // Chat providers without an embeddings endpoint need their own base URL
+        settings.ai_provider = "anthropic".to_string();
+        settings.ai_base_url = "https://api.anthropic.com/v1".to_string();
+        assert!(AiConfig::for_embeddings(&settings).is_err());
+        settings.ai_embedding_base_url = "http://localhost:11434/v1".to_string();
+        let config = AiConfig::for_embeddings(&settings).unwrap();
+        assert_eq!(
+            config.base_url.as_deref(),
+            Some("http://localhost:11434/v1")
+        );
+        assert_eq!(config.api_key, "");
+
+        settings.ai_provider = "openai".to_string();
+        settings.ai_api_key = String::new();
+        settings.ai_embedding_base_url = String::new();
+        assert!(AiConfig::for_embeddings(&settings).is_err());
     }
 }
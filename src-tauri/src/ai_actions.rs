@@ -0,0 +1,249 @@
+//! User-defined AI actions, and the migration from the four built-in ones whose prompts and
+//! titles used to be separate settings.
+
+use crate::models::AiAction;
+use crate::search_query::CLIP_TYPES;
+use serde_json::Value;
+use std::collections::HashSet;
+
+/// Clip types an action without `clip_types` applies to.
+pub const TEXT_CLIP_TYPES: &[&str] = &["text", "html", "url"];
+
+/// The former built-in actions: id, metadata key, default title and default prompt.
+const BUILT_IN: &[(&str, &str, &str, &str)] = &[
+    (
+        "summarize",
+        "ai_summary",
+        "Summarize",
+        "Summarize this content concisely.",
+    ),
+    (
+        "translate",
+        "ai_translation",
+        "Translate",
+        "Translate this to English (or user language).",
+    ),
+    (
+        "explain_code",
+        "ai_explanation",
+        "Explain Code",
+        "Explain this code snippet.",
+    ),
+    (
+        "fix_grammar",
+        "ai_grammar_fix",
+        "Fix Grammar",
+        "Fix grammar and spelling.",
+    ),
+];
+
+impl AiAction {
+    pub fn applies_to(&self, clip_type: &str) -> bool {
+        if self.clip_types.is_empty() {
+            TEXT_CLIP_TYPES.contains(&clip_type)
+        } else {
+            self.clip_types.iter().any(|t| t == clip_type)
+        }
+    }
+
+    pub fn metadata_key(&self) -> String {
+        metadata_key(&self.id)
+    }
+}
+
+/// Key an action's result is stored under in clip metadata. The former built-in actions keep
+/// their old keys so earlier results still show up.
+pub fn metadata_key(action_id: &str) -> String {
+    BUILT_IN
+        .iter()
+        .find(|(id, ..)| *id == action_id)
+        .map(|(_, key, ..)| key.to_string())
+        .unwrap_or_else(|| format!("ai_{}", action_id))
+}
+
+pub fn defaults() -> Vec<AiAction> {
+    from_legacy(|_| None)
+}
+
+/// Actions for image clips, sent to vision-capable models.
+fn image_actions() -> Vec<AiAction> {
+    [
+        (
+            "describe_image",
+            "Describe Image",
+            "Describe this image concisely, including any charts, diagrams or handwriting in it.",
+        ),
+        (
+            "extract_text",
+            "Extract Text",
+            "Extract all text from this image, keeping its line breaks. Reply with the text only.",
+        ),
+    ]
+    .into_iter()
+    .map(|(id, title, prompt)| AiAction {
+        id: id.to_string(),
+        title: title.to_string(),
+        system_prompt: prompt.to_string(),
+        temperature: 0.2,
+        clip_types: vec!["image".to_string()],
+        ..Default::default()
+    })
+    .collect()
+}
+
+/// The default actions, with the former built-in ones' titles and prompts taken from the old
+/// `ai_title_<id>` and `ai_prompt_<id>` settings where `get` has them. Blank ones meant "use
+/// the default" in the old settings UI.
+pub fn from_legacy(get: impl Fn(&str) -> Option<String>) -> Vec<AiAction> {
+    let legacy = |key: &str| get(key).filter(|v| !v.trim().is_empty());
+    BUILT_IN
+        .iter()
+        .map(|(id, _, title, prompt)| AiAction {
+            id: id.to_string(),
+            title: legacy(&format!("ai_title_{}", id)).unwrap_or_else(|| title.to_string()),
+            system_prompt: legacy(&format!("ai_prompt_{}", id))
+                .unwrap_or_else(|| prompt.to_string()),
+            ..Default::default()
+        })
+        .chain(image_actions())
+        .collect()
+}
+
+/// Replace the old per-action settings in a settings file with `ai_actions`, unless it
+/// already has them.
+pub fn migrate_legacy(settings: &mut Value) {
+    let Some(obj) = settings.as_object_mut() else {
+        return;
+    };
+    if obj.contains_key("ai_actions") {
+        return;
+    }
+    let actions = from_legacy(|key| obj.get(key).and_then(Value::as_str).map(str::to_string));
+    obj.retain(|key, _| !key.starts_with("ai_title_") && !key.starts_with("ai_prompt_"));
+    obj.insert(
+        "ai_actions".to_string(),
+        serde_json::to_value(actions).unwrap_or_default(),
+    );
+}
+
+pub fn find<'a>(actions: &'a [AiAction], id: &str) -> Option<&'a AiAction> {
+    actions.iter().find(|action| action.id == id)
+}
+
+/// Ids are lowercase letters, digits and underscores, since they end up in metadata keys
+/// and `has:` searches.
+pub fn validate(actions: &[AiAction]) -> Result<(), String> {
+    let mut ids = HashSet::new();
+    for action in actions {
+        if action.id.is_empty()
+            || !action
+                .id
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        {
+            return Err(format!(
+                "AI action id '{}' may only use a-z, 0-9 and _",
+                action.id
+            ));
+        }
+        if !ids.insert(action.id.as_str()) {
+            return Err(format!("Duplicate AI action id '{}'", action.id));
+        }
+        if action.title.trim().is_empty() {
+            return Err(format!("AI action '{}' needs a title", action.id));
+        }
+        if action.system_prompt.trim().is_empty() {
+            return Err(format!("AI action '{}' needs a prompt", action.id));
+        }
+        if !(0.0..=2.0).contains(&action.temperature) {
+            return Err(format!(
+                "AI action '{}' temperature must be between 0 and 2",
+                action.id
+            ));
+        }
+        if let Some(clip_type) = action
+            .clip_types
+            .iter()
+            .find(|t| !CLIP_TYPES.contains(&t.as_str()))
+        {
+            return Err(format!("Unknown clip type '{}'", clip_type));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::AiOutputMode;
+    use serde_json::json;
+
+    #[test]
+    fn test_migrate_legacy() {
+        let mut settings = json!({
+            "theme": "dark",
+            "ai_prompt_summarize": "TL;DR please.",
+            "ai_title_translate": "To Spanish",
+            "ai_title_fix_grammar": "Fix Grammar",
+            "ai_title_explain_code": "",
+            "ai_prompt_translate": "  "
+        });
+        migrate_legacy(&mut settings);
+        assert_eq!(settings["theme"], "dark");
+        assert!(settings.get("ai_prompt_summarize").is_none());
+
+        let actions: Vec<AiAction> =
+            serde_json::from_value(settings["ai_actions"].clone()).unwrap();
+        assert_eq!(actions.len(), 6);
+        assert_eq!(actions[0].id, "summarize");
+        assert_eq!(actions[0].title, "Summarize");
+        assert_eq!(actions[0].system_prompt, "TL;DR please.");
+        assert_eq!(actions[1].title, "To Spanish");
+        assert_eq!(actions[1].output, AiOutputMode::Metadata);
+        // Blank values fall back to the defaults, so the migrated actions validate
+        assert_eq!(
+            actions[1].system_prompt,
+            "Translate this to English (or user language)."
+        );
+        assert_eq!(actions[2].title, "Explain Code");
+        assert!(validate(&actions).is_ok());
+
+        // Already migrated settings are left alone
+        let mut migrated = json!({"ai_actions": [], "ai_title_summarize": "x"});
+        migrate_legacy(&mut migrated);
+        assert_eq!(migrated["ai_actions"], json!([]));
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate(&defaults()).is_ok());
+        let action = |id: &str| AiAction {
+            id: id.to_string(),
+            title: "Title".to_string(),
+            system_prompt: "Prompt".to_string(),
+            ..Default::default()
+        };
+        assert!(validate(&[action("Bad id")]).is_err());
+        assert!(validate(&[action("a"), action("a")]).is_err());
+        assert!(validate(&[AiAction {
+            temperature: 3.0,
+            ..action("hot")
+        }])
+        .is_err());
+        assert!(validate(&[AiAction {
+            clip_types: vec!["video".to_string()],
+            ..action("video")
+        }])
+        .is_err());
+    }
+
+    #[test]
+    fn test_metadata_key() {
+        assert_eq!(metadata_key("summarize"), "ai_summary");
+        assert_eq!(metadata_key("to_spanish"), "ai_to_spanish");
+        assert!(find(&defaults(), "explain_code").unwrap().applies_to("url"));
+        assert!(!find(&defaults(), "explain_code")
+            .unwrap()
+            .applies_to("image"));
+    }
+}
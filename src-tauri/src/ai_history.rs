@@ -0,0 +1,204 @@
+//! Every AI result for a clip, with the action, model and prompt that produced it. A request
+//! for the same prompt and model on the same content is answered from here instead of the
+//! provider.
+
+use crate::models::AiAction;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{FromRow, SqlitePool};
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct AiResult {
+    pub id: i64,
+    pub clip_uuid: String,
+    pub action_id: String,
+    pub model: String,
+    /// See `prompt_hash`.
+    pub prompt_hash: String,
+    /// The source clip's `content_hash` when the result was made.
+    pub content_hash: String,
+    pub result: String,
+    /// The clip the result was saved as, if it was.
+    pub result_clip_uuid: Option<String>,
+    pub created_at: DateTime<Utc>,
+    /// Served from an earlier identical request.
+    #[sqlx(skip)]
+    pub cached: bool,
+}
+
+/// Hash of what an action sends besides the content. The temperature is included since a
+/// changed one should give a fresh answer.
+pub fn prompt_hash(action: &AiAction) -> String {
+    crate::clipboard::calculate_hash(
+        format!("{}\n{}", action.temperature, action.system_prompt).as_bytes(),
+    )
+}
+
+/// The latest result for this prompt, model and content, from any clip.
+pub async fn find_cached(
+    pool: &SqlitePool,
+    prompt_hash: &str,
+    model: &str,
+    content_hash: &str,
+) -> Result<Option<AiResult>, String> {
+    sqlx::query_as(
+        r#"
+        SELECT * FROM ai_results
+        WHERE prompt_hash = ? AND model = ? AND content_hash = ?
+        ORDER BY created_at DESC, id DESC LIMIT 1
+        "#,
+    )
+    .bind(prompt_hash)
+    .bind(model)
+    .bind(content_hash)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+pub async fn record(
+    pool: &SqlitePool,
+    clip_uuid: &str,
+    action_id: &str,
+    model: &str,
+    prompt_hash: &str,
+    content_hash: &str,
+    result: &str,
+) -> Result<AiResult, String> {
+    let id = sqlx::query(
+        r#"
+        INSERT INTO ai_results (clip_uuid, action_id, model, prompt_hash, content_hash, result, created_at)
+        VALUES (?, ?, ?, ?, ?, ?, CURRENT_TIMESTAMP)
+        "#,
+    )
+    .bind(clip_uuid)
+    .bind(action_id)
+    .bind(model)
+    .bind(prompt_hash)
+    .bind(content_hash)
+    .bind(result)
+    .execute(pool)
+    .await
+    .map_err(|e| e.to_string())?
+    .last_insert_rowid();
+    get(pool, id).await
+}
+
+pub async fn get(pool: &SqlitePool, id: i64) -> Result<AiResult, String> {
+    sqlx::query_as(r#"SELECT * FROM ai_results WHERE id = ?"#)
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "AI result not found".to_string())
+}
+
+/// A clip's results, newest first.
+pub async fn for_clip(pool: &SqlitePool, clip_uuid: &str) -> Result<Vec<AiResult>, String> {
+    sqlx::query_as(
+        r#"SELECT * FROM ai_results WHERE clip_uuid = ? ORDER BY created_at DESC, id DESC"#,
+    )
+    .bind(clip_uuid)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// Save a result as a text clip whose metadata links back to the source clip, or bump the
+/// clip that already has that text. Returns the clip's uuid.
+pub async fn save_as_clip(pool: &SqlitePool, entry: &mut AiResult) -> Result<String, String> {
+    let uuid = crate::merge::save_clip(pool, "text", entry.result.as_bytes(), None).await?;
+    // An action that changed nothing gives back the source clip itself
+    if uuid != entry.clip_uuid {
+        let metadata: Option<String> =
+            sqlx::query_scalar(r#"SELECT metadata FROM clips WHERE uuid = ?"#)
+                .bind(&uuid)
+                .fetch_one(pool)
+                .await
+                .map_err(|e| e.to_string())?;
+        let mut metadata: serde_json::Value = metadata
+            .and_then(|m| serde_json::from_str(&m).ok())
+            .unwrap_or(serde_json::json!({}));
+        metadata["source_clip_id"] = serde_json::json!(entry.clip_uuid);
+        metadata["source_action"] = serde_json::json!(entry.action_id);
+        sqlx::query("UPDATE clips SET metadata = ? WHERE uuid = ?")
+            .bind(metadata.to_string())
+            .bind(&uuid)
+            .execute(pool)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    sqlx::query("UPDATE ai_results SET result_clip_uuid = ? WHERE id = ?")
+        .bind(&uuid)
+        .bind(entry.id)
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    entry.result_clip_uuid = Some(uuid.clone());
+    Ok(uuid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::TestDb;
+
+    #[tokio::test]
+    async fn test_history_and_cache() {
+        let db = TestDb::new("ai_history").await;
+        let pool = &db.pool;
+        sqlx::query(
+            "INSERT INTO clips (uuid, clip_type, content, text_preview, content_hash) VALUES ('src', 'text', 'teh cat', '', 'h1')",
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+
+        let action = AiAction {
+            id: "fix".to_string(),
+            system_prompt: "Fix it.".to_string(),
+            ..Default::default()
+        };
+        let hash = prompt_hash(&action);
+        assert_ne!(
+            hash,
+            prompt_hash(&AiAction {
+                temperature: 0.1,
+                ..action.clone()
+            })
+        );
+
+        record(pool, "src", "fix", "m1", &hash, "h1", "the cat")
+            .await
+            .unwrap();
+        let mut latest = record(pool, "src", "fix", "m1", &hash, "h1", "The cat.")
+            .await
+            .unwrap();
+        assert_eq!(for_clip(pool, "src").await.unwrap().len(), 2);
+
+        let hit = find_cached(pool, &hash, "m1", "h1").await.unwrap().unwrap();
+        assert_eq!(hit.id, latest.id);
+        assert!(find_cached(pool, &hash, "m2", "h1")
+            .await
+            .unwrap()
+            .is_none());
+        assert!(find_cached(pool, &hash, "m1", "h2")
+            .await
+            .unwrap()
+            .is_none());
+
+        let uuid = save_as_clip(pool, &mut latest).await.unwrap();
+        let metadata: String = sqlx::query_scalar("SELECT metadata FROM clips WHERE uuid = ?")
+            .bind(&uuid)
+            .fetch_one(pool)
+            .await
+            .unwrap();
+        assert!(metadata.contains(r#""source_clip_id":"src""#));
+        assert_eq!(
+            get(pool, latest.id).await.unwrap().result_clip_uuid,
+            Some(uuid)
+        );
+
+        db.close().await;
+    }
+}
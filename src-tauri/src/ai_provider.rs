@@ -0,0 +1,417 @@
+//! The chat APIs AI actions can run against. Each provider builds its own requests (endpoint,
+//! auth headers, body) and reads its own responses, streamed lines and error bodies.
+
+use reqwest::{Client, RequestBuilder, StatusCode};
+use serde_json::{json, Value};
+
+use crate::ai::AiConfig;
+
+/// One chat turn: the action's system prompt and the clip's text or image.
+pub struct Chat<'a> {
+    pub system: &'a str,
+    /// May be empty when there's an image.
+    pub user: &'a str,
+    pub image: Option<&'a ChatImage>,
+    pub temperature: f64,
+}
+
+/// An image for vision-capable models, base64-encoded.
+pub struct ChatImage {
+    pub media_type: &'static str,
+    pub data: String,
+}
+
+/// What one line of a streamed response means.
+#[derive(Debug, PartialEq)]
+pub enum StreamEvent {
+    Delta(String),
+    Done,
+    Error(String),
+}
+
+pub trait AiProvider: Send + Sync {
+    /// Base URL used when none is configured.
+    fn default_base_url(&self) -> &'static str;
+
+    /// Whether requests fail without an API key.
+    fn requires_api_key(&self) -> bool {
+        true
+    }
+
+    /// Whether the base URL also serves the OpenAI-compatible `/embeddings` endpoint.
+    fn serves_embeddings(&self) -> bool {
+        false
+    }
+
+    /// The chat request, with auth headers, streaming or not.
+    fn chat_request(
+        &self,
+        client: &Client,
+        config: &AiConfig,
+        chat: &Chat,
+        stream: bool,
+    ) -> RequestBuilder;
+
+    /// The completion text in a successful non-streaming response.
+    fn parse_response(&self, body: &str) -> Result<String, String>;
+
+    /// What one line of a streaming response carries, if anything.
+    fn parse_stream_line(&self, line: &str) -> Option<StreamEvent>;
+
+    /// A readable message for a failed request.
+    fn parse_error(&self, status: StatusCode, body: &str) -> String;
+}
+
+/// The provider for the `ai_provider` setting; anything unknown is treated as OpenAI-compatible.
+pub fn provider_for(name: &str) -> Box<dyn AiProvider> {
+    match name {
+        "anthropic" => Box::new(Anthropic),
+        "gemini" => Box::new(Gemini),
+        "ollama" => Box::new(Ollama),
+        _ => Box::new(OpenAiCompatible),
+    }
+}
+
+fn endpoint(config: &AiConfig, provider: &dyn AiProvider, path: &str) -> String {
+    let base_url = config
+        .base_url
+        .as_deref()
+        .unwrap_or(provider.default_base_url());
+    format!("{}/{}", base_url.trim_end_matches('/'), path)
+}
+
+/// The payload of a server-sent event `data:` line.
+fn sse_data(line: &str) -> Option<&str> {
+    line.strip_prefix("data:").map(str::trim_start)
+}
+
+/// "API Error (status): message", falling back to the raw body when there's no message.
+fn api_error(status: StatusCode, message: Option<&str>, body: &str) -> String {
+    let message = message.unwrap_or(body.trim());
+    if message.is_empty() {
+        format!("API Error ({})", status)
+    } else {
+        format!("API Error ({}): {}", status, message)
+    }
+}
+
+fn parse_json(body: &str) -> Result<Value, String> {
+    serde_json::from_str(body).map_err(|e| format!("Invalid AI response: {}", e))
+}
+
+fn non_empty(text: Option<&str>) -> Option<String> {
+    text.filter(|t| !t.is_empty()).map(str::to_string)
+}
+
+/// OpenAI's chat completions API, also served by DeepSeek and most self-hosted gateways.
+pub struct OpenAiCompatible;
+
+impl AiProvider for OpenAiCompatible {
+    fn default_base_url(&self) -> &'static str {
+        "https://api.openai.com/v1"
+    }
+
+    fn serves_embeddings(&self) -> bool {
+        true
+    }
+
+    fn chat_request(
+        &self,
+        client: &Client,
+        config: &AiConfig,
+        chat: &Chat,
+        stream: bool,
+    ) -> RequestBuilder {
+        let user = match chat.image {
+            Some(image) => {
+                let mut parts = vec![json!({
+                    "type": "image_url",
+                    "image_url": {"url": format!("data:{};base64,{}", image.media_type, image.data)}
+                })];
+                if !chat.user.is_empty() {
+                    parts.push(json!({"type": "text", "text": chat.user}));
+                }
+                json!(parts)
+            }
+            None => json!(chat.user),
+        };
+        let mut body = json!({
+            "model": config.model,
+            "messages": [
+                {"role": "system", "content": chat.system},
+                {"role": "user", "content": user}
+            ],
+            "temperature": chat.temperature
+        });
+        if stream {
+            body["stream"] = json!(true);
+        }
+        client
+            .post(endpoint(config, self, "chat/completions"))
+            .header("Authorization", format!("Bearer {}", config.api_key))
+            .json(&body)
+    }
+
+    fn parse_response(&self, body: &str) -> Result<String, String> {
+        let value = parse_json(body)?;
+        value["choices"][0]["message"]["content"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| "No response from AI".to_string())
+    }
+
+    fn parse_stream_line(&self, line: &str) -> Option<StreamEvent> {
+        let data = sse_data(line)?;
+        if data == "[DONE]" {
+            return Some(StreamEvent::Done);
+        }
+        let value: Value = serde_json::from_str(data).ok()?;
+        if let Some(message) = value["error"]["message"].as_str() {
+            return Some(StreamEvent::Error(message.to_string()));
+        }
+        non_empty(value["choices"][0]["delta"]["content"].as_str()).map(StreamEvent::Delta)
+    }
+
+    fn parse_error(&self, status: StatusCode, body: &str) -> String {
+        let value: Value = serde_json::from_str(body).unwrap_or_default();
+        api_error(status, value["error"]["message"].as_str(), body)
+    }
+}
+
+/// Anthropic's Messages API.
+pub struct Anthropic;
+
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+/// The Messages API requires a cap on the reply length.
+const ANTHROPIC_MAX_TOKENS: u32 = 4096;
+
+impl AiProvider for Anthropic {
+    fn default_base_url(&self) -> &'static str {
+        "https://api.anthropic.com/v1"
+    }
+
+    fn chat_request(
+        &self,
+        client: &Client,
+        config: &AiConfig,
+        chat: &Chat,
+        stream: bool,
+    ) -> RequestBuilder {
+        let user = match chat.image {
+            Some(image) => {
+                let mut blocks = vec![json!({
+                    "type": "image",
+                    "source": {"type": "base64", "media_type": image.media_type, "data": image.data}
+                })];
+                if !chat.user.is_empty() {
+                    blocks.push(json!({"type": "text", "text": chat.user}));
+                }
+                json!(blocks)
+            }
+            None => json!(chat.user),
+        };
+        let mut body = json!({
+            "model": config.model,
+            "system": chat.system,
+            "messages": [{"role": "user", "content": user}],
+            "max_tokens": ANTHROPIC_MAX_TOKENS,
+            "temperature": chat.temperature
+        });
+        if stream {
+            body["stream"] = json!(true);
+        }
+        client
+            .post(endpoint(config, self, "messages"))
+            .header("x-api-key", &config.api_key)
+            .header("anthropic-version", ANTHROPIC_VERSION)
+            .json(&body)
+    }
+
+    fn parse_response(&self, body: &str) -> Result<String, String> {
+        let value = parse_json(body)?;
+        let text: String = value["content"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter(|block| block["type"] == "text")
+            .filter_map(|block| block["text"].as_str())
+            .collect();
+        if text.is_empty() {
+            Err("No response from AI".to_string())
+        } else {
+            Ok(text)
+        }
+    }
+
+    fn parse_stream_line(&self, line: &str) -> Option<StreamEvent> {
+        // The event type is repeated in the data, so `event:` lines can be skipped
+        let value: Value = serde_json::from_str(sse_data(line)?).ok()?;
+        match value["type"].as_str()? {
+            "content_block_delta" => {
+                non_empty(value["delta"]["text"].as_str()).map(StreamEvent::Delta)
+            }
+            "message_stop" => Some(StreamEvent::Done),
+            "error" => Some(StreamEvent::Error(
+                value["error"]["message"]
+                    .as_str()
+                    .unwrap_or("Stream error")
+                    .to_string(),
+            )),
+            _ => None,
+        }
+    }
+
+    fn parse_error(&self, status: StatusCode, body: &str) -> String {
+        let value: Value = serde_json::from_str(body).unwrap_or_default();
+        api_error(status, value["error"]["message"].as_str(), body)
+    }
+}
+
+/// Google's Gemini API.
+pub struct Gemini;
+
+impl Gemini {
+    fn text(value: &Value) -> String {
+        value["candidates"][0]["content"]["parts"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|part| part["text"].as_str())
+            .collect()
+    }
+}
+
+impl AiProvider for Gemini {
+    fn default_base_url(&self) -> &'static str {
+        "https://generativelanguage.googleapis.com/v1beta"
+    }
+
+    fn chat_request(
+        &self,
+        client: &Client,
+        config: &AiConfig,
+        chat: &Chat,
+        stream: bool,
+    ) -> RequestBuilder {
+        let mut parts = Vec::new();
+        if let Some(image) = chat.image {
+            parts.push(json!({"inlineData": {"mimeType": image.media_type, "data": image.data}}));
+        }
+        if !chat.user.is_empty() || parts.is_empty() {
+            parts.push(json!({"text": chat.user}));
+        }
+        let body = json!({
+            "systemInstruction": {"parts": [{"text": chat.system}]},
+            "contents": [{"role": "user", "parts": parts}],
+            "generationConfig": {"temperature": chat.temperature}
+        });
+        let method = if stream {
+            "streamGenerateContent?alt=sse"
+        } else {
+            "generateContent"
+        };
+        let path = format!("models/{}:{}", config.model, method);
+        client
+            .post(endpoint(config, self, &path))
+            .header("x-goog-api-key", &config.api_key)
+            .json(&body)
+    }
+
+    fn parse_response(&self, body: &str) -> Result<String, String> {
+        let text = Self::text(&parse_json(body)?);
+        if text.is_empty() {
+            Err("No response from AI".to_string())
+        } else {
+            Ok(text)
+        }
+    }
+
+    fn parse_stream_line(&self, line: &str) -> Option<StreamEvent> {
+        // Each event is a partial response; the stream just ends when the reply is complete
+        let value: Value = serde_json::from_str(sse_data(line)?).ok()?;
+        if let Some(message) = value["error"]["message"].as_str() {
+            return Some(StreamEvent::Error(message.to_string()));
+        }
+        non_empty(Some(&Self::text(&value))).map(StreamEvent::Delta)
+    }
+
+    fn parse_error(&self, status: StatusCode, body: &str) -> String {
+        // Errors may come wrapped in a one-element array
+        let value: Value = serde_json::from_str(body).unwrap_or_default();
+        let error = if value.is_array() {
+            &value[0]["error"]
+        } else {
+            &value["error"]
+        };
+        api_error(status, error["message"].as_str(), body)
+    }
+}
+
+/// A local Ollama server's native chat API.
+pub struct Ollama;
+
+impl AiProvider for Ollama {
+    fn default_base_url(&self) -> &'static str {
+        "http://localhost:11434"
+    }
+
+    fn requires_api_key(&self) -> bool {
+        false
+    }
+
+    fn chat_request(
+        &self,
+        client: &Client,
+        config: &AiConfig,
+        chat: &Chat,
+        stream: bool,
+    ) -> RequestBuilder {
+        let mut user = json!({"role": "user", "content": chat.user});
+        if let Some(image) = chat.image {
+            // Ollama takes bare base64 images next to the message text
+            user["images"] = json!([image.data]);
+        }
+        let body = json!({
+            "model": config.model,
+            "messages": [
+                {"role": "system", "content": chat.system},
+                user
+            ],
+            "stream": stream,
+            "options": {"temperature": chat.temperature}
+        });
+        let request = client.post(endpoint(config, self, "api/chat")).json(&body);
+        // Only sent for servers behind an authenticating proxy
+        if config.api_key.is_empty() {
+            request
+        } else {
+            request.header("Authorization", format!("Bearer {}", config.api_key))
+        }
+    }
+
+    fn parse_response(&self, body: &str) -> Result<String, String> {
+        let value = parse_json(body)?;
+        value["message"]["content"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| "No response from AI".to_string())
+    }
+
+    fn parse_stream_line(&self, line: &str) -> Option<StreamEvent> {
+        // Newline-delimited JSON rather than server-sent events
+        let value: Value = serde_json::from_str(line.trim()).ok()?;
+        if let Some(message) = value["error"].as_str() {
+            return Some(StreamEvent::Error(message.to_string()));
+        }
+        match non_empty(value["message"]["content"].as_str()) {
+            Some(delta) => Some(StreamEvent::Delta(delta)),
+            None if value["done"] == true => Some(StreamEvent::Done),
+            None => None,
+        }
+    }
+
+    fn parse_error(&self, status: StatusCode, body: &str) -> String {
+        let value: Value = serde_json::from_str(body).unwrap_or_default();
+        api_error(status, value["error"].as_str(), body)
+    }
+}
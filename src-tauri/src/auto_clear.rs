@@ -0,0 +1,106 @@
+//! Clearing the system clipboard a while after pasting a clip set to "clear after N seconds",
+//! directly or through its folder. Anything copied in the meantime is left alone.
+
+use sqlx::SqlitePool;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tauri::AppHandle;
+use tauri_plugin_clipboard_x::read_text;
+
+/// Bumped by every `schedule`, so only the latest paste's timer can clear.
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// The clip's own delay, else its folder's. `None` when neither is set or it's not positive.
+pub async fn delay_for(pool: &SqlitePool, clip_uuid: &str) -> Option<u64> {
+    let secs: Option<i64> = sqlx::query_scalar(
+        r#"
+        SELECT COALESCE(c.clear_after_secs, f.clear_after_secs)
+        FROM clips c LEFT JOIN folders f ON f.id = c.folder_id
+        WHERE c.uuid = ?
+        "#,
+    )
+    .bind(clip_uuid)
+    .fetch_optional(pool)
+    .await
+    .ok()
+    .flatten()
+    .flatten();
+    secs.filter(|s| *s > 0).map(|s| s as u64)
+}
+
+/// After pasting `text` from `clip_uuid`, clear the clipboard once its delay is up.
+pub async fn schedule_for_clip(app: &AppHandle, pool: &SqlitePool, clip_uuid: &str, text: &str) {
+    if let Some(secs) = delay_for(pool, clip_uuid).await {
+        schedule(app, text, secs);
+    }
+}
+
+/// Clear the clipboard in `secs` seconds if it still holds `text` by then.
+pub fn schedule(app: &AppHandle, text: &str, secs: u64) {
+    let generation = GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    let hash = crate::clipboard::calculate_hash(text.as_bytes());
+    let app = app.clone();
+    log::info!("CLIPBOARD: Clearing clipboard in {}s", secs);
+
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_secs(secs)).await;
+        if GENERATION.load(Ordering::SeqCst) != generation {
+            return;
+        }
+
+        // Synchronize clipboard access across the app
+        let _guard = crate::clipboard::CLIPBOARD_SYNC.lock().await;
+
+        let current = read_text().await.ok();
+        if current.map(|t| crate::clipboard::calculate_hash(t.as_bytes())) != Some(hash) {
+            log::info!("CLIPBOARD: Clipboard changed since pasting, not clearing");
+            return;
+        }
+        if let Err(e) = crate::clipboard::clear_system_clipboard(&app).await {
+            log::error!("CLIPBOARD: Failed to clear clipboard: {}", e);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::TestDb;
+
+    #[tokio::test]
+    async fn test_delay_for() {
+        let db = TestDb::new("auto_clear").await;
+        let pool = &db.pool;
+        sqlx::query("INSERT INTO folders (id, name, clear_after_secs) VALUES (1, 'Secrets', 30), (2, 'Off', 0)")
+            .execute(pool)
+            .await
+            .unwrap();
+        for (uuid, folder_id, secs) in [
+            ("own", Some(1), Some(10)),
+            ("folder", Some(1), None),
+            ("none", None, None),
+            ("zero", None, Some(0)),
+            ("negative", Some(1), Some(-5)),
+            ("zero_folder", Some(2), None),
+        ] {
+            sqlx::query("INSERT INTO clips (uuid, clip_type, content, text_preview, content_hash, folder_id, clear_after_secs) VALUES (?, 'text', 'x', '', ?, ?, ?)")
+                .bind(uuid)
+                .bind(uuid)
+                .bind(folder_id)
+                .bind(secs)
+                .execute(pool)
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(delay_for(pool, "own").await, Some(10));
+        assert_eq!(delay_for(pool, "folder").await, Some(30));
+        assert_eq!(delay_for(pool, "none").await, None);
+        assert_eq!(delay_for(pool, "zero").await, None);
+        // The clip's own value wins over its folder's, even when it turns clearing off
+        assert_eq!(delay_for(pool, "negative").await, None);
+        assert_eq!(delay_for(pool, "zero_folder").await, None);
+        assert_eq!(delay_for(pool, "missing").await, None);
+
+        db.close().await;
+    }
+}
@@ -0,0 +1,87 @@
+//! Pausing clipboard capture, e.g. during a screen share: nothing copied is recorded until
+//! capture is resumed, by hand or when the chosen number of minutes is up.
+
+use chrono::{DateTime, Duration, Utc};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use tauri::{AppHandle, Emitter};
+
+/// Longer timed pauses are cut to a day.
+const MAX_PAUSE_MINUTES: u64 = 24 * 60;
+
+static PAUSED: AtomicBool = AtomicBool::new(false);
+
+/// When a timed pause ends; `None` while paused until resumed by hand.
+static RESUME_AT: Lazy<parking_lot::Mutex<Option<DateTime<Utc>>>> =
+    Lazy::new(|| parking_lot::Mutex::new(None));
+
+/// Bumped by every pause and resume, so a stale timer doesn't resume a newer pause.
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CaptureState {
+    pub paused: bool,
+    /// RFC 3339 time a timed pause ends.
+    pub resume_at: Option<String>,
+}
+
+pub fn is_paused() -> bool {
+    PAUSED.load(Ordering::SeqCst)
+}
+
+pub fn state() -> CaptureState {
+    let paused = is_paused();
+    let resume_at = *RESUME_AT.lock();
+    CaptureState {
+        paused,
+        resume_at: resume_at.filter(|_| paused).map(|at| at.to_rfc3339()),
+    }
+}
+
+/// Stop recording clipboard changes, for `minutes` (at most a day) if given or until `resume`.
+pub fn pause(app: &AppHandle, minutes: Option<u64>) {
+    let generation = GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    let minutes = minutes.filter(|m| *m > 0).map(|m| m.min(MAX_PAUSE_MINUTES));
+    *RESUME_AT.lock() = minutes.map(|m| Utc::now() + Duration::minutes(m as i64));
+    PAUSED.store(true, Ordering::SeqCst);
+    match minutes {
+        Some(m) => log::info!("CLIPBOARD: Capture paused for {} minutes", m),
+        None => log::info!("CLIPBOARD: Capture paused"),
+    }
+    changed(app);
+
+    if let Some(m) = minutes {
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_secs(m * 60)).await;
+            if GENERATION.load(Ordering::SeqCst) == generation {
+                resume(&app);
+            }
+        });
+    }
+}
+
+pub fn resume(app: &AppHandle) {
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+    *RESUME_AT.lock() = None;
+    if PAUSED.swap(false, Ordering::SeqCst) {
+        log::info!("CLIPBOARD: Capture resumed");
+    }
+    changed(app);
+}
+
+/// Pause until resumed, or resume if paused.
+pub fn toggle(app: &AppHandle) {
+    if is_paused() {
+        resume(app);
+    } else {
+        pause(app, None);
+    }
+}
+
+fn changed(app: &AppHandle) {
+    let state = state();
+    crate::update_tray(app, &state);
+    let _ = app.emit("capture-paused-changed", state);
+}
@@ -0,0 +1,357 @@
+//! Paged clip lists for `get_clips` and `search_clips`.
+//!
+//! Pages are keyed on `(created_at, id)` instead of `OFFSET`, so deep pages cost the same as the first,
+//! and the list projection leaves full text and image bytes in the database.
+
+use crate::models::Clip;
+use crate::search_query::{self, SearchQuery};
+use serde::Serialize;
+use sqlx::{FromRow, SqlitePool};
+use std::collections::HashMap;
+
+/// Text clips carry at most this many bytes of content in list results; `get_clip_detail` has the rest.
+pub const LIST_CONTENT_BYTES: i64 = 4096;
+
+/// Position to continue a list from, handed to the frontend as an opaque string.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cursor {
+    /// Rows strictly older than this `(created_at, id)`.
+    After { created_at: String, id: i64 },
+    /// Plain offset, for ranked results that have no stable sort key.
+    Offset(i64),
+}
+
+impl Cursor {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let invalid = || format!("Invalid cursor: {}", value);
+        if let Some(rest) = value.strip_prefix("k:") {
+            let (created_at, id) = rest.rsplit_once('|').ok_or_else(invalid)?;
+            Ok(Cursor::After {
+                created_at: created_at.to_string(),
+                id: id.parse().map_err(|_| invalid())?,
+            })
+        } else if let Some(offset) = value.strip_prefix("o:") {
+            Ok(Cursor::Offset(offset.parse().map_err(|_| invalid())?))
+        } else {
+            Err(invalid())
+        }
+    }
+
+    pub fn encode(&self) -> String {
+        match self {
+            Cursor::After { created_at, id } => format!("k:{}|{}", created_at, id),
+            Cursor::Offset(offset) => format!("o:{}", offset),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ClipPage<T> {
+    pub items: Vec<T>,
+    /// Pass back as `cursor` to load the next page; `None` on the last page.
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Debug, FromRow)]
+pub struct ClipListRow {
+    #[sqlx(flatten)]
+    pub clip: Clip,
+    /// `created_at` exactly as stored, for the next cursor.
+    pub sort_key: String,
+    /// Full content size in bytes, since `content` may be cut short. Read from the row header,
+    /// so large clips aren't loaded just to measure them.
+    pub content_length: i64,
+}
+
+fn list_columns(preview_only: bool) -> String {
+    let content = if preview_only {
+        // Images are shown from their file; text only needs enough for the card.
+        format!(
+            "CASE WHEN clip_type = 'image' THEN x'' ELSE substr(content, 1, {}) END AS content",
+            LIST_CONTENT_BYTES
+        )
+    } else {
+        "content".to_string()
+    };
+    format!(
+        "id, uuid, clip_type, {}, text_preview, content_hash, folder_id, is_deleted, is_thumbnail, \
+         is_sensitive, is_template, clear_after_secs, source_app, source_icon, metadata, created_at, last_accessed, \
+         CAST(created_at AS TEXT) AS sort_key, length(content) AS content_length",
+        content
+    )
+}
+
+/// Drop a UTF-8 sequence split by the byte cut so it doesn't render as a replacement character.
+fn trim_partial_char(content: &mut Vec<u8>) {
+    if let Err(e) = std::str::from_utf8(content) {
+        if e.error_len().is_none() {
+            content.truncate(e.valid_up_to());
+        }
+    }
+}
+
+/// One page of non-deleted clips matching `query`, newest first.
+pub async fn fetch_page(
+    pool: &SqlitePool,
+    folder_id: Option<i64>,
+    query: &SearchQuery,
+    cursor: Option<&Cursor>,
+    limit: i64,
+    preview_only: bool,
+) -> Result<(Vec<ClipListRow>, Option<String>), String> {
+    let limit = limit.max(0);
+    let (where_sql, args) = query.to_sql();
+    let sql = format!(
+        "SELECT {} FROM clips WHERE is_deleted = 0{}{} AND ({}) ORDER BY created_at DESC, id DESC LIMIT ? OFFSET ?",
+        list_columns(preview_only),
+        if folder_id.is_some() {
+            " AND folder_id = ?"
+        } else {
+            ""
+        },
+        if matches!(cursor, Some(Cursor::After { .. })) {
+            " AND (created_at, id) < (?, ?)"
+        } else {
+            ""
+        },
+        where_sql
+    );
+
+    let mut rows_query = sqlx::query_as::<_, ClipListRow>(&sql);
+    if let Some(id) = folder_id {
+        rows_query = rows_query.bind(id);
+    }
+    let mut offset = 0;
+    match cursor {
+        Some(Cursor::After { created_at, id }) => {
+            rows_query = rows_query.bind(created_at).bind(id);
+        }
+        Some(Cursor::Offset(value)) => offset = (*value).max(0),
+        None => {}
+    }
+    // One extra row tells us whether there is a next page.
+    let mut rows = search_query::bind_args(rows_query, &args)
+        .bind(limit + 1)
+        .bind(offset)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let next_cursor = if rows.len() as i64 > limit {
+        rows.truncate(limit as usize);
+        rows.last().map(|row| {
+            match cursor {
+                Some(Cursor::Offset(_)) => Cursor::Offset(offset + limit),
+                _ => Cursor::After {
+                    created_at: row.sort_key.clone(),
+                    id: row.clip.id,
+                },
+            }
+            .encode()
+        })
+    } else {
+        None
+    };
+
+    if preview_only {
+        for row in &mut rows {
+            trim_partial_char(&mut row.clip.content);
+        }
+    }
+
+    Ok((rows, next_cursor))
+}
+
+/// Clips for `uuids` in the same order, with the list projection; missing ones are skipped.
+pub async fn fetch_by_uuid(
+    pool: &SqlitePool,
+    uuids: &[String],
+    preview_only: bool,
+) -> Result<Vec<ClipListRow>, String> {
+    if uuids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let placeholders: Vec<&str> = uuids.iter().map(|_| "?").collect();
+    let sql = format!(
+        "SELECT {} FROM clips WHERE uuid IN ({})",
+        list_columns(preview_only),
+        placeholders.join(",")
+    );
+    let mut rows_query = sqlx::query_as::<_, ClipListRow>(&sql);
+    for uuid in uuids {
+        rows_query = rows_query.bind(uuid);
+    }
+    let mut by_uuid: HashMap<String, ClipListRow> = rows_query
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|mut row| {
+            if preview_only {
+                trim_partial_char(&mut row.clip.content);
+            }
+            (row.clip.uuid.clone(), row)
+        })
+        .collect();
+    Ok(uuids
+        .iter()
+        .filter_map(|uuid| by_uuid.remove(uuid))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::TestDb;
+    use std::time::Instant;
+
+    #[test]
+    fn test_cursor_roundtrip() {
+        let cursor = Cursor::After {
+            created_at: "2026-02-14 10:00:00".to_string(),
+            id: 42,
+        };
+        assert_eq!(Cursor::parse(&cursor.encode()).unwrap(), cursor);
+        assert_eq!(Cursor::parse("o:40").unwrap(), Cursor::Offset(40));
+        assert!(Cursor::parse("40").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_pages_cover_all_rows_once() {
+        let db = TestDb::new("pages").await;
+        let pool = db.pool.clone();
+        for i in 0..7 {
+            // Same timestamp for all rows, so paging relies on the id tiebreak.
+            sqlx::query(
+                "INSERT INTO clips (uuid, clip_type, content, text_preview, content_hash, created_at) VALUES (?, 'text', ?, '', ?, '2026-01-01 00:00:00')",
+            )
+            .bind(format!("u{}", i))
+            .bind(format!("a{}", "é".repeat(2999)).into_bytes())
+            .bind(format!("h{}", i))
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        let mut seen = Vec::new();
+        let mut cursor = None;
+        loop {
+            let (rows, next) = fetch_page(
+                &pool,
+                None,
+                &SearchQuery::default(),
+                cursor.as_ref(),
+                3,
+                true,
+            )
+            .await
+            .unwrap();
+            for row in &rows {
+                assert_eq!(row.content_length, 1 + 2 * 2999);
+                assert!(std::str::from_utf8(&row.clip.content).is_ok());
+                assert!(row.clip.content.len() as i64 <= LIST_CONTENT_BYTES);
+            }
+            seen.extend(rows.into_iter().map(|row| row.clip.uuid));
+            match next {
+                Some(next) => cursor = Some(Cursor::parse(&next).unwrap()),
+                None => break,
+            }
+        }
+        let expected: Vec<String> = (0..7).rev().map(|i| format!("u{}", i)).collect();
+        assert_eq!(seen, expected);
+
+        db.close().await;
+    }
+
+    /// `cargo test --release bench_list_pagination -- --ignored --nocapture`
+    #[tokio::test]
+    #[ignore]
+    async fn bench_list_pagination() {
+        const CLIPS: i64 = 100_000;
+        const PAGE: i64 = 20;
+
+        let db = TestDb::new("bench").await;
+        let pool = db.pool.clone();
+
+        // 100k clips over about a year: mostly short text, with images (thumbnail in `content`)
+        // and long pastes mixed in.
+        let mut tx = pool.begin().await.unwrap();
+        for i in 0..CLIPS {
+            let (clip_type, content) = match i % 10 {
+                0 => ("image", vec![0x89u8; 16 * 1024]),
+                5 => ("text", format!("clip {} ", i).repeat(800).into_bytes()),
+                _ => (
+                    "text",
+                    format!("clip {} ", i)
+                        .repeat(20 + (i % 4) as usize * 30)
+                        .into_bytes(),
+                ),
+            };
+            sqlx::query(
+                "INSERT INTO clips (uuid, clip_type, content, text_preview, content_hash, created_at) VALUES (?, ?, ?, ?, ?, datetime('2026-01-01', ? || ' minutes'))",
+            )
+            .bind(format!("bench-{}", i))
+            .bind(clip_type)
+            .bind(&content)
+            .bind(format!("clip {}", i))
+            .bind(format!("hash-{}", i))
+            .bind((i * 5).to_string())
+            .execute(&mut *tx)
+            .await
+            .unwrap();
+        }
+        tx.commit().await.unwrap();
+
+        for depth in [0, 1_000, 10_000, 50_000, 99_000] {
+            let started = Instant::now();
+            let legacy: Vec<Clip> = sqlx::query_as(
+                "SELECT * FROM clips WHERE is_deleted = 0 ORDER BY created_at DESC LIMIT ? OFFSET ?",
+            )
+            .bind(PAGE)
+            .bind(depth)
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+            let legacy_ms = started.elapsed().as_secs_f64() * 1000.0;
+            let legacy_bytes: usize = legacy.iter().map(|c| c.content.len()).sum();
+
+            // The cursor a client would hold after scrolling to `depth`.
+            let cursor = if depth == 0 {
+                None
+            } else {
+                let (created_at, id): (String, i64) = sqlx::query_as(
+                    "SELECT CAST(created_at AS TEXT), id FROM clips ORDER BY created_at DESC, id DESC LIMIT 1 OFFSET ?",
+                )
+                .bind(depth - 1)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+                Some(Cursor::After { created_at, id })
+            };
+            let started = Instant::now();
+            let (keyset, _) = fetch_page(
+                &pool,
+                None,
+                &SearchQuery::default(),
+                cursor.as_ref(),
+                PAGE,
+                true,
+            )
+            .await
+            .unwrap();
+            let keyset_ms = started.elapsed().as_secs_f64() * 1000.0;
+            let keyset_bytes: usize = keyset.iter().map(|r| r.clip.content.len()).sum();
+
+            assert_eq!(
+                legacy.iter().map(|c| &c.uuid).collect::<Vec<_>>(),
+                keyset.iter().map(|r| &r.clip.uuid).collect::<Vec<_>>()
+            );
+            println!(
+                "depth={:>6} offset+SELECT *: {:>8.2} ms {:>7} bytes | keyset+projection: {:>6.2} ms {:>6} bytes",
+                depth, legacy_ms, legacy_bytes, keyset_ms, keyset_bytes
+            );
+        }
+
+        db.close().await;
+    }
+}
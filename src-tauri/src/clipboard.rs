@@ -1,6 +1,8 @@
 use tauri::{AppHandle, Emitter, Listener};
 // Import functions directly from the crate root
 use crate::database::Database;
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+use crate::models::PasteChord;
 #[cfg(target_os = "windows")]
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
 use clipboard_rs::common::RustImage;
@@ -12,7 +14,7 @@ use std::ffi::OsStr;
 #[cfg(target_os = "windows")]
 use std::os::windows::ffi::OsStrExt;
 use std::sync::Arc;
-use tauri_plugin_clipboard_x::{read_text, start_listening};
+use tauri_plugin_clipboard_x::{read_text, start_listening, stop_listening};
 use uuid::Uuid;
 #[cfg(target_os = "windows")]
 use windows::Win32::Foundation::MAX_PATH;
@@ -34,7 +36,8 @@ use windows::Win32::System::ProcessStatus::{GetModuleBaseNameW, GetModuleFileNam
 use windows::Win32::System::Threading::{OpenProcess, PROCESS_QUERY_INFORMATION, PROCESS_VM_READ};
 #[cfg(target_os = "windows")]
 use windows::Win32::UI::Input::KeyboardAndMouse::{
-    SendInput, INPUT, INPUT_KEYBOARD, KEYBDINPUT, KEYEVENTF_KEYUP, VK_INSERT, VK_SHIFT,
+    SendInput, INPUT, INPUT_KEYBOARD, KEYBDINPUT, KEYBD_EVENT_FLAGS, KEYEVENTF_KEYUP, VIRTUAL_KEY,
+    VK_CONTROL, VK_INSERT, VK_LEFT, VK_SHIFT,
 };
 #[cfg(target_os = "windows")]
 use windows::Win32::UI::Shell::{
@@ -63,6 +66,26 @@ pub fn set_ignore_hash(hash: String) {
     *lock = Some(hash);
 }
 
+// Clipboard changes before this instant are not recorded, e.g. while a snapshot is restored.
+static SUPPRESS_UNTIL: Lazy<parking_lot::Mutex<Option<std::time::Instant>>> =
+    Lazy::new(|| parking_lot::Mutex::new(None));
+
+/// Skip recording clipboard changes for `duration` from now.
+pub fn suppress_capture_for(duration: std::time::Duration) {
+    *SUPPRESS_UNTIL.lock() = Some(std::time::Instant::now() + duration);
+}
+
+fn capture_suppressed() -> bool {
+    let mut lock = SUPPRESS_UNTIL.lock();
+    match *lock {
+        Some(until) if std::time::Instant::now() < until => true,
+        _ => {
+            *lock = None;
+            false
+        }
+    }
+}
+
 pub fn init(app: &AppHandle, db: Arc<Database>) {
     let app_clone = app.clone();
     let db_clone = db.clone();
@@ -83,6 +106,9 @@ pub fn init(app: &AppHandle, db: Arc<Database>) {
     let event_name = "plugin:clipboard-x://clipboard_changed";
 
     app.listen(event_name, move |_event| {
+        if crate::capture_pause::is_paused() {
+            return;
+        }
         let app = app_clone.clone();
         let db = db_clone.clone();
 
@@ -347,6 +373,15 @@ async fn process_clipboard_change(
     let mut was_existing = false;
     let _guard = CLIPBOARD_SYNC.lock().await;
 
+    if capture_suppressed() {
+        log::info!("CLIPBOARD: Ignoring change while the previous clipboard is restored");
+        return;
+    }
+    if crate::capture_pause::is_paused() {
+        log::info!("CLIPBOARD: Capture paused, ignoring change");
+        return;
+    }
+
     let mut clip_type = "text";
     let mut clip_content = Vec::new();
     let mut full_image_content: Option<Vec<u8>> = None;
@@ -448,9 +483,10 @@ async fn process_clipboard_change(
         is_explicit_owner
     );
 
-    // Check settings (cached via SettingsManager)
     use crate::settings_manager::SettingsManager;
     use tauri::Manager;
+
+    // Check settings (cached via SettingsManager)
     let manager = app.state::<Arc<SettingsManager>>();
     let settings = manager.get();
 
@@ -488,8 +524,61 @@ async fn process_clipboard_change(
         }
     }
 
+    // Route the clip to the profile bound to its source app. Ignored apps never get here, so
+    // copying from one doesn't switch profiles.
+    let profiles = app.state::<Arc<crate::profiles::ProfileManager>>();
+    if let Some(profile_id) = profiles.auto_switch_target(&[
+        source_app.as_deref(),
+        exe_name.as_deref(),
+        full_path.as_deref(),
+    ]) {
+        if let Err(e) = crate::profiles::switch_profile_locked(&app, &profile_id).await {
+            log::error!("CLIPBOARD: Failed to auto-switch profile: {}", e);
+        }
+    }
+
     // DB Logic
-    let pool = &db.pool;
+    let pool = &db.pool();
+
+    // Routing rules
+    let rule_text = if clip_type == "image" {
+        clip_preview.clone()
+    } else {
+        crate::search_match::match_text(&clip_content)
+    };
+    let facts = crate::rules::ClipFacts {
+        clip_type,
+        app_names: [
+            source_app.as_deref(),
+            exe_name.as_deref(),
+            full_path.as_deref(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect(),
+        text: &rule_text,
+        size: full_image_content
+            .as_ref()
+            .map_or(clip_content.len(), |bytes| bytes.len()) as i64,
+    };
+    let outcome = match crate::rules::enabled_rules(pool).await {
+        Ok(rules) => crate::rules::evaluate(&rules, &facts),
+        Err(e) => {
+            log::error!("CLIPBOARD: Failed to load routing rules: {}", e);
+            crate::rules::RuleOutcome::default()
+        }
+    };
+    if outcome.discard {
+        log::info!(
+            "CLIPBOARD: Discarded by rule '{}'",
+            outcome
+                .matched
+                .last()
+                .map(String::as_str)
+                .unwrap_or_default()
+        );
+        return;
+    }
 
     let db_lookup_started = std::time::Instant::now();
     let existing_uuid: Option<String> =
@@ -620,6 +709,14 @@ async fn process_clipboard_change(
     };
     let db_write_ms = db_write_started.elapsed().as_millis();
 
+    if !outcome.matched.is_empty() {
+        log::info!("CLIPBOARD: Routing rules matched: {:?}", outcome.matched);
+        if let Err(e) = crate::rules::apply(pool, &emitted_id, &outcome).await {
+            log::error!("CLIPBOARD: Failed to apply routing rules: {}", e);
+        }
+    }
+    crate::embeddings::wake();
+
     let emit_started = std::time::Instant::now();
     let _ = app.emit(
         "clipboard-change",
@@ -634,6 +731,32 @@ async fn process_clipboard_change(
     );
     let emit_ms = emit_started.elapsed().as_millis();
 
+    // Sensitive clips stay out of AI features, even when a rule asks for them. A capture deduped
+    // onto a clip already marked sensitive is refused by `run_ai_action`.
+    if !outcome.ai_actions.is_empty() && !outcome.sensitive {
+        let app = app.clone();
+        let pool = pool.clone();
+        let clip_id = emitted_id.clone();
+        let actions = outcome.ai_actions.clone();
+        tauri::async_runtime::spawn(async move {
+            for action in actions {
+                match crate::commands::run_ai_action(&app, &pool, &clip_id, &action, None, false)
+                    .await
+                {
+                    Ok(_) => {
+                        let _ = app.emit("clipboard-change", ());
+                    }
+                    Err(e) => log::warn!(
+                        "CLIPBOARD: Rule AI action {} failed for {}: {}",
+                        action,
+                        clip_id,
+                        e
+                    ),
+                }
+            }
+        });
+    }
+
     log::info!(
         "[perf][clipboard_ingest] type={} existing={} full_bytes={} thumb_bytes={} image_read_ms={} decode_ms={} text_read_ms={} db_lookup_ms={} db_write_ms={} emit_ms={} total_ms={}",
         clip_type,
@@ -649,24 +772,15 @@ async fn process_clipboard_change(
         started.elapsed().as_millis()
     );
 }
-fn calculate_hash(content: &[u8]) -> String {
+pub fn calculate_hash(content: &[u8]) -> String {
     let mut hasher = Sha256::new();
     hasher.update(content);
     let result = hasher.finalize();
     format!("{:x}", result)
 }
 
-fn get_image_store_dir() -> std::path::PathBuf {
-    let current_dir = std::env::current_dir().unwrap_or(std::path::PathBuf::from("."));
-    let app_data_dir = match dirs::data_dir() {
-        Some(path) => path.join("PastePaw"),
-        None => current_dir.join("PastePaw"),
-    };
-    app_data_dir.join("images")
-}
-
 pub fn persist_full_image_file(clip_uuid: &str, png_bytes: &[u8]) -> Result<String, String> {
-    let dir = get_image_store_dir();
+    let dir = crate::data_dir::images_dir();
     std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
     let file_path = dir.join(format!("{}.png", clip_uuid));
     std::fs::write(&file_path, png_bytes).map_err(|e| e.to_string())?;
@@ -677,6 +791,25 @@ pub fn read_full_image_file(file_path: &str) -> Result<Vec<u8>, String> {
     std::fs::read(file_path).map_err(|e| e.to_string())
 }
 
+/// Empty the system clipboard without recording the change. Call with `CLIPBOARD_SYNC` held.
+pub async fn clear_system_clipboard(app: &AppHandle) -> Result<(), String> {
+    // Stop monitor
+    if let Err(e) = stop_listening().await {
+        log::error!("Failed to stop listener: {}", e);
+    }
+    let res = ClipboardContext::new()
+        .and_then(|ctx| ctx.clear())
+        .map_err(|e| e.to_string());
+    if res.is_ok() {
+        log::info!("CLIPBOARD: Cleared clipboard");
+    }
+    // Restart monitor
+    if let Err(e) = start_listening(app.clone()).await {
+        log::error!("Failed to restart listener: {}", e);
+    }
+    res
+}
+
 pub fn remove_full_image_file(file_path: &str) {
     if let Err(e) = std::fs::remove_file(file_path) {
         if e.kind() != std::io::ErrorKind::NotFound {
@@ -1056,6 +1189,126 @@ pub fn send_paste_input() {
     }
 }
 
+/// Process id plus name, executable and path of the app owning the foreground window.
+#[cfg(target_os = "windows")]
+fn foreground_app_names() -> Option<(u32, Vec<String>)> {
+    unsafe {
+        let hwnd = GetForegroundWindow();
+        if hwnd.0.is_null() {
+            return None;
+        }
+        let mut process_id = 0;
+        GetWindowThreadProcessId(hwnd, Some(&mut process_id));
+        if process_id == 0 {
+            return None;
+        }
+        let process_handle = OpenProcess(
+            PROCESS_QUERY_INFORMATION | PROCESS_VM_READ,
+            false,
+            process_id,
+        )
+        .ok()?;
+
+        let mut names = Vec::new();
+        let mut name_buffer = [0u16; MAX_PATH as usize];
+        let name_size = GetModuleBaseNameW(process_handle, None, &mut name_buffer);
+        if name_size > 0 {
+            names.push(String::from_utf16_lossy(&name_buffer[..name_size as usize]));
+        }
+        let mut path_buffer = [0u16; MAX_PATH as usize];
+        let path_size = GetModuleFileNameExW(Some(process_handle), None, &mut path_buffer);
+        if path_size > 0 {
+            let full_path = String::from_utf16_lossy(&path_buffer[..path_size as usize]);
+            if let Some(desc) = get_app_description(&full_path) {
+                names.push(desc);
+            }
+            names.push(full_path);
+        }
+        let _ = windows::Win32::Foundation::CloseHandle(process_handle);
+        Some((process_id, names))
+    }
+}
+
+#[cfg(target_os = "windows")]
+static LAST_TARGET_APP: Lazy<parking_lot::Mutex<Vec<String>>> =
+    Lazy::new(|| parking_lot::Mutex::new(Vec::new()));
+
+/// Remember the foreground app before PastePaw's window takes focus; pastes go back to it.
+#[cfg(target_os = "windows")]
+pub fn remember_target_app() {
+    if let Some((process_id, names)) = foreground_app_names() {
+        if process_id != std::process::id() {
+            *LAST_TARGET_APP.lock() = names;
+        }
+    }
+}
+
+/// Names of the app a paste will land in, for matching paste profiles.
+#[cfg(target_os = "windows")]
+pub fn target_app_names() -> Vec<String> {
+    match foreground_app_names() {
+        Some((process_id, names)) if process_id != std::process::id() => names,
+        _ => LAST_TARGET_APP.lock().clone(),
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn send_key_chord(modifiers: &[VIRTUAL_KEY], key: VIRTUAL_KEY) {
+    let input = |vk, flags| INPUT {
+        r#type: INPUT_KEYBOARD,
+        Anonymous: windows::Win32::UI::Input::KeyboardAndMouse::INPUT_0 {
+            ki: KEYBDINPUT {
+                wVk: vk,
+                dwFlags: flags,
+                ..Default::default()
+            },
+        },
+    };
+    let mut inputs: Vec<INPUT> = modifiers
+        .iter()
+        .map(|&m| input(m, KEYBD_EVENT_FLAGS(0)))
+        .collect();
+    inputs.push(input(key, KEYBD_EVENT_FLAGS(0)));
+    inputs.push(input(key, KEYEVENTF_KEYUP));
+    inputs.extend(modifiers.iter().rev().map(|&m| input(m, KEYEVENTF_KEYUP)));
+    unsafe {
+        SendInput(&inputs, std::mem::size_of::<INPUT>() as i32);
+    }
+}
+
+/// Send the paste keystroke for `chord`; macOS-only chords fall back to Shift+Insert.
+#[cfg(target_os = "windows")]
+pub fn send_paste_chord(chord: PasteChord) {
+    // Virtual-key code for V
+    let v_key = VIRTUAL_KEY(0x56);
+    match chord {
+        PasteChord::CtrlV => send_key_chord(&[VK_CONTROL], v_key),
+        PasteChord::CtrlShiftV => send_key_chord(&[VK_CONTROL, VK_SHIFT], v_key),
+        _ => send_paste_input(),
+    }
+}
+
+/// Press Left `count` times, e.g. to put the caret on a template's `{{cursor}}` after pasting.
+#[cfg(target_os = "windows")]
+pub fn send_cursor_left(count: usize) {
+    let key = |flags| INPUT {
+        r#type: INPUT_KEYBOARD,
+        Anonymous: windows::Win32::UI::Input::KeyboardAndMouse::INPUT_0 {
+            ki: KEYBDINPUT {
+                wVk: VK_LEFT,
+                dwFlags: flags,
+                ..Default::default()
+            },
+        },
+    };
+    let inputs: Vec<INPUT> = (0..count)
+        .flat_map(|_| [key(Default::default()), key(KEYEVENTF_KEYUP)])
+        .collect();
+    unsafe {
+        SendInput(&inputs, std::mem::size_of::<INPUT>() as i32);
+    }
+}
+
 #[cfg(target_os = "macos")]
 fn get_clipboard_owner_app_info() -> (
     Option<String>,
@@ -1160,3 +1413,97 @@ pub fn send_paste_input() {
 
     log::info!("CLIPBOARD: Sent Cmd+V via CoreGraphics");
 }
+
+/// Names of the app a paste will land in, for matching paste profiles.
+#[cfg(target_os = "macos")]
+pub fn target_app_names() -> Vec<String> {
+    let (name, _icon, bundle_id, _, _) = crate::source_app_macos::get_frontmost_app_info();
+    [name, bundle_id].into_iter().flatten().collect()
+}
+
+/// Send the paste keystroke for `chord`; Windows-only chords fall back to Cmd+V.
+#[cfg(target_os = "macos")]
+pub fn send_paste_chord(chord: PasteChord) {
+    use core_graphics::event::{CGEvent, CGEventFlags, CGEventTapLocation, CGKeyCode};
+    use core_graphics::event_source::{CGEventSource, CGEventSourceStateID};
+
+    // kVK_Command = 0x37, kVK_Shift = 0x38, kVK_Control = 0x3B
+    let (modifiers, flags): (&[CGKeyCode], CGEventFlags) = match chord {
+        PasteChord::CtrlV => (&[0x3B], CGEventFlags::CGEventFlagControl),
+        PasteChord::CtrlShiftV => (
+            &[0x3B, 0x38],
+            CGEventFlags::CGEventFlagControl | CGEventFlags::CGEventFlagShift,
+        ),
+        PasteChord::CmdShiftV => (
+            &[0x37, 0x38],
+            CGEventFlags::CGEventFlagCommand | CGEventFlags::CGEventFlagShift,
+        ),
+        _ => return send_paste_input(),
+    };
+
+    #[cfg(feature = "app-store")]
+    {
+        if !crate::source_app_macos::is_accessibility_enabled() {
+            log::warn!(
+                "CLIPBOARD: Auto-paste failed because Accessibility permissions are not granted."
+            );
+            return;
+        }
+    }
+
+    let source = match CGEventSource::new(CGEventSourceStateID::HIDSystemState) {
+        Ok(src) => src,
+        Err(e) => {
+            log::error!("CLIPBOARD: Failed to create CGEventSource: {:?}", e);
+            return;
+        }
+    };
+    let post = |key: CGKeyCode, key_down: bool, flags: CGEventFlags| {
+        if let Ok(event) = CGEvent::new_keyboard_event(source.clone(), key, key_down) {
+            event.set_flags(flags);
+            event.post(CGEventTapLocation::HID);
+        }
+    };
+
+    // kVK_ANSI_V = 0x09
+    for &modifier in modifiers {
+        post(modifier, true, flags);
+    }
+    post(0x09, true, flags);
+    post(0x09, false, flags);
+    for &modifier in modifiers.iter().rev() {
+        post(modifier, false, CGEventFlags::empty());
+    }
+    log::info!("CLIPBOARD: Sent {:?} via CoreGraphics", chord);
+}
+
+/// Press Left `count` times, e.g. to put the caret on a template's `{{cursor}}` after pasting.
+#[cfg(target_os = "macos")]
+pub fn send_cursor_left(count: usize) {
+    use core_graphics::event::{CGEvent, CGEventTapLocation, CGKeyCode};
+    use core_graphics::event_source::{CGEventSource, CGEventSourceStateID};
+
+    // kVK_LeftArrow = 0x7B
+    let left_key: CGKeyCode = 0x7B;
+
+    let source = match CGEventSource::new(CGEventSourceStateID::HIDSystemState) {
+        Ok(src) => src,
+        Err(e) => {
+            log::error!("CLIPBOARD: Failed to create CGEventSource: {:?}", e);
+            return;
+        }
+    };
+
+    for _ in 0..count {
+        for key_down in [true, false] {
+            if let Ok(event) = CGEvent::new_keyboard_event(source.clone(), left_key, key_down) {
+                event.post(CGEventTapLocation::HID);
+            }
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+pub fn target_app_names() -> Vec<String> {
+    Vec::new()
+}
@@ -0,0 +1,101 @@
+//! A copy of what's on the clipboard, put back after an auto-paste overwrote it.
+
+use clipboard_rs::{Clipboard, ClipboardContent, ClipboardContext, ContentFormat};
+
+pub struct ClipboardSnapshot {
+    contents: Vec<ClipboardContent>,
+}
+
+impl std::fmt::Debug for ClipboardSnapshot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ClipboardSnapshot({} flavors)", self.contents.len())
+    }
+}
+
+impl ClipboardSnapshot {
+    /// Read the text, RTF, HTML, image and file list flavors, whichever are present, plus any
+    /// app-specific formats (Office, IDE or design tool payloads) as raw bytes.
+    pub fn capture() -> Result<Self, String> {
+        let ctx = ClipboardContext::new().map_err(|e| e.to_string())?;
+        let mut contents = ctx
+            .get(&[
+                ContentFormat::Text,
+                ContentFormat::Rtf,
+                ContentFormat::Html,
+                ContentFormat::Image,
+                ContentFormat::Files,
+            ])
+            .map_err(|e| e.to_string())?;
+
+        let formats = ctx.available_formats().unwrap_or_default();
+        for format in formats.into_iter().filter(|f| !is_standard_format(f)) {
+            match ctx.get_buffer(&format) {
+                Ok(buffer) => contents.push(ClipboardContent::Other(format, buffer)),
+                Err(e) => log::debug!("CLIPBOARD: Skipped format {} in snapshot: {}", format, e),
+            }
+        }
+        Ok(Self { contents })
+    }
+
+    /// Put every captured flavor back at once; an empty snapshot clears the clipboard.
+    pub fn restore(self) -> Result<(), String> {
+        let ctx = ClipboardContext::new().map_err(|e| e.to_string())?;
+        if self.contents.is_empty() {
+            ctx.clear().map_err(|e| e.to_string())
+        } else {
+            ctx.set(self.contents).map_err(|e| e.to_string())
+        }
+    }
+}
+
+/// Formats already read through the typed flavors above, or that the platform makes up on its
+/// own (X11 selection targets), so they aren't written back twice.
+const STANDARD_FORMATS: &[&str] = &[
+    // Windows
+    "CF_TEXT",
+    "CF_UNICODETEXT",
+    "CF_OEMTEXT",
+    "CF_LOCALE",
+    "CF_BITMAP",
+    "CF_DIB",
+    "CF_DIBV5",
+    "CF_HDROP",
+    "Rich Text Format",
+    "HTML Format",
+    "PNG",
+    // macOS
+    "public.utf8-plain-text",
+    "public.utf16-plain-text",
+    "NSStringPboardType",
+    "public.rtf",
+    "NeXT Rich Text Format v1.0 pasteboard type",
+    "public.html",
+    "Apple HTML pasteboard type",
+    "public.png",
+    "public.tiff",
+    "NeXT TIFF v4.0 pasteboard type",
+    "public.file-url",
+    "NSFilenamesPboardType",
+    // X11 / Wayland
+    "TARGETS",
+    "TIMESTAMP",
+    "MULTIPLE",
+    "SAVE_TARGETS",
+    "UTF8_STRING",
+    "STRING",
+    "TEXT",
+    "COMPOUND_TEXT",
+    "text/plain",
+    "text/plain;charset=utf-8",
+    "text/rtf",
+    "text/richtext",
+    "text/html",
+    "image/png",
+    "text/uri-list",
+];
+
+fn is_standard_format(format: &str) -> bool {
+    STANDARD_FORMATS
+        .iter()
+        .any(|standard| standard.eq_ignore_ascii_case(format))
+}
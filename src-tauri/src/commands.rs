@@ -1,106 +1,255 @@
 use tauri::{AppHandle, Emitter, Manager};
-use tauri_plugin_clipboard_x::{start_listening, stop_listening, write_text};
-
-use crate::ai::{self, AiAction, AiConfig};
+use tauri_plugin_clipboard_x::{read_text, start_listening, stop_listening, write_text};
+
+use crate::ai::{self, AiConfig};
+use crate::ai_actions;
+use crate::ai_history::{self, AiResult};
+use crate::auto_clear;
+use crate::capture_pause::{self, CaptureState};
+use crate::clip_list::{self, ClipListRow, ClipPage, Cursor};
 use crate::database::Database;
-use crate::models::{Clip, ClipboardItem, Folder, FolderItem};
+use crate::embeddings;
+use crate::merge;
+use crate::models::{
+    AiOutputMode, Clip, ClipboardItem, Folder, FolderItem, SearchMatch, ShortcutBinding,
+};
+use crate::paste_queue;
+use crate::paste_strategy;
+use crate::quick_paste;
+use crate::search_match::{self, Matcher, SearchMode};
+use crate::search_query::{self, SearchQuery};
 use crate::settings_manager::SettingsManager;
+use crate::shortcuts::{self, BindingStatus};
+use crate::templates;
+use crate::transforms;
+use crate::type_out;
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
 use sqlx::SqlitePool;
 use std::collections::HashMap;
 use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Instant;
-use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut};
+use tauri_plugin_global_shortcut::Shortcut;
 
+/// With a `request_id`, the result is streamed as `ai-stream-delta` events carrying that id
+/// and can be stopped with `cancel_ai_request`. Resolves to the full result either way.
+/// An earlier result for the same prompt, model and content is reused unless `refresh` is set.
 #[tauri::command]
 pub async fn ai_process_clip(
     app: AppHandle,
     clip_id: String,
     action: String,
+    request_id: Option<String>,
+    refresh: Option<bool>,
+    db: tauri::State<'_, Arc<Database>>,
+) -> Result<AiResult, String> {
+    run_ai_action(
+        &app,
+        &db.pool(),
+        &clip_id,
+        &action,
+        request_id.as_deref(),
+        refresh.unwrap_or(false),
+    )
+    .await
+}
+
+/// Stop a streaming `ai_process_clip`; nothing is stored for it. False if it already finished.
+#[tauri::command]
+pub fn cancel_ai_request(request_id: String) -> bool {
+    ai::cancel_request(&request_id)
+}
+
+/// Every AI result for a clip, newest first.
+#[tauri::command]
+pub async fn get_ai_history(
+    clip_id: String,
+    db: tauri::State<'_, Arc<Database>>,
+) -> Result<Vec<AiResult>, String> {
+    ai_history::for_clip(&db.pool(), &clip_id).await
+}
+
+/// Save an AI result as a clip linked to the clip it came from. Returns the new clip's id.
+#[tauri::command]
+pub async fn save_ai_result_as_clip(
+    app: AppHandle,
+    result_id: i64,
     db: tauri::State<'_, Arc<Database>>,
 ) -> Result<String, String> {
-    let pool = &db.pool;
+    let pool = db.pool();
+    let mut entry = ai_history::get(&pool, result_id).await?;
+    let uuid = ai_history::save_as_clip(&pool, &mut entry).await?;
+    embeddings::wake();
+    let _ = app.emit("clipboard-change", ());
+    Ok(uuid)
+}
 
+/// Run an AI action on a clip, record the result in the clip's AI history and, once it's
+/// complete, store it in the clip's metadata, save it as a new linked clip or put it on the
+/// clipboard, as the action says. A `stream_id` streams the result, see `ai_process_clip`.
+pub async fn run_ai_action(
+    app: &AppHandle,
+    pool: &SqlitePool,
+    clip_id: &str,
+    action: &str,
+    stream_id: Option<&str>,
+    refresh: bool,
+) -> Result<AiResult, String> {
     // 1. Get Clip
-    let clip: Clip = sqlx::query_as(r#"SELECT * FROM clips WHERE uuid = ?"#)
-        .bind(&clip_id)
+    let mut clip: Clip = sqlx::query_as(r#"SELECT * FROM clips WHERE uuid = ?"#)
+        .bind(clip_id)
         .fetch_optional(pool)
         .await
         .map_err(|e| e.to_string())?
         .ok_or("Clip not found")?;
-
-    let text_content =
-        if clip.clip_type == "text" || clip.clip_type == "html" || clip.clip_type == "url" {
-            String::from_utf8_lossy(&clip.content).to_string()
-        } else {
-            return Err("AI processing only supported for text content".to_string());
-        };
+    // Covers every caller: the menu, shortcuts and rules on clips deduped onto a sensitive one
+    if clip.is_sensitive {
+        return Err("Sensitive clips are never sent to AI".to_string());
+    }
 
     // 2. Get AI Config
     let manager = app.state::<Arc<SettingsManager>>();
     let settings = manager.get();
 
-    if settings.ai_api_key.is_empty() {
-        return Err("AI API Key is missing in settings".to_string());
+    let ai_action = ai_actions::find(&settings.ai_actions, action)
+        .ok_or_else(|| format!("Unknown AI action '{}'", action))?
+        .clone();
+    if !ai_action.applies_to(&clip.clip_type) {
+        return Err(format!(
+            "{} doesn't apply to {} clips",
+            ai_action.title, clip.clip_type
+        ));
     }
+    if clip.clip_type != "image" && !ai_actions::TEXT_CLIP_TYPES.contains(&clip.clip_type.as_str())
+    {
+        return Err("AI processing only supported for text and image content".to_string());
+    }
+    let config = AiConfig::for_action(&settings, &ai_action);
+    let prompt_hash = ai_history::prompt_hash(&ai_action);
 
-    let config = AiConfig {
-        provider: settings.ai_provider,
-        api_key: settings.ai_api_key,
-        model: settings.ai_model,
-        base_url: if settings.ai_base_url.is_empty() {
-            None
-        } else {
-            Some(settings.ai_base_url)
-        },
-    };
-
-    let ai_action = match action.as_str() {
-        "summarize" => AiAction::Summarize,
-        "translate" => AiAction::Translate,
-        "explain_code" => AiAction::ExplainCode,
-        "fix_grammar" => AiAction::FixGrammar,
-        _ => return Err("Invalid AI action".to_string()),
-    };
-
-    let custom_prompt = match ai_action {
-        AiAction::Summarize => Some(settings.ai_prompt_summarize),
-        AiAction::Translate => Some(settings.ai_prompt_translate),
-        AiAction::ExplainCode => Some(settings.ai_prompt_explain_code),
-        AiAction::FixGrammar => Some(settings.ai_prompt_fix_grammar),
-    };
-
-    // 3. Call AI
-    let result = ai::process_text(&text_content, ai_action.clone(), &config, custom_prompt)
-        .await
-        .map_err(|e| e.to_string())?;
-
-    // 4. Update Metadata
-    let mut metadata: serde_json::Value = if let Some(meta_str) = &clip.metadata {
-        serde_json::from_str(meta_str).unwrap_or(serde_json::json!({}))
+    // 3. Reuse an earlier result, or call AI
+    let cached = if refresh {
+        None
     } else {
-        serde_json::json!({})
+        ai_history::find_cached(pool, &prompt_hash, &config.model, &clip.content_hash).await?
     };
-
-    let key = match ai_action {
-        AiAction::Summarize => "ai_summary",
-        AiAction::Translate => "ai_translation",
-        AiAction::ExplainCode => "ai_explanation",
-        AiAction::FixGrammar => "ai_grammar_fix",
+    let mut entry = match cached {
+        Some(hit) => {
+            log::info!(
+                "AI: Reusing result {} for {} on {}",
+                hit.id,
+                ai_action.id,
+                clip_id
+            );
+            if let Some(request_id) = stream_id {
+                let _ = app.emit(
+                    "ai-stream-delta",
+                    serde_json::json!({ "request_id": request_id, "delta": hit.result }),
+                );
+            }
+            // Identical content in another clip still gets an entry in this clip's history
+            let mut entry = if hit.clip_uuid == clip_id {
+                hit
+            } else {
+                ai_history::record(
+                    pool,
+                    clip_id,
+                    &ai_action.id,
+                    &config.model,
+                    &prompt_hash,
+                    &clip.content_hash,
+                    &hit.result,
+                )
+                .await?
+            };
+            entry.cached = true;
+            entry
+        }
+        None => {
+            if config.api_key.is_empty() && config.provider().requires_api_key() {
+                return Err("AI API Key is missing in settings".to_string());
+            }
+            // Images go to vision-capable models on their own, scaled down
+            let (text_content, image) = if clip.clip_type == "image" {
+                let bytes = load_full_image_content(pool, &mut clip).await?;
+                let image = tauri::async_runtime::spawn_blocking(move || ai::prepare_image(&bytes))
+                    .await
+                    .map_err(|e| e.to_string())??;
+                (String::new(), Some(image))
+            } else {
+                (String::from_utf8_lossy(&clip.content).to_string(), None)
+            };
+
+            let result = match stream_id {
+                Some(request_id) => {
+                    let cancel = ai::begin_request(request_id);
+                    let res = ai::process_text_stream(
+                        &text_content,
+                        image.as_ref(),
+                        &ai_action,
+                        &config,
+                        &cancel,
+                        |delta| {
+                            let _ = app.emit(
+                                "ai-stream-delta",
+                                serde_json::json!({ "request_id": request_id, "delta": delta }),
+                            );
+                        },
+                    )
+                    .await
+                    .map_err(|e| e.to_string());
+                    ai::end_request(request_id);
+                    res
+                }
+                None => ai::process_text(&text_content, image.as_ref(), &ai_action, &config)
+                    .await
+                    .map_err(|e| e.to_string()),
+            }?;
+            ai_history::record(
+                pool,
+                clip_id,
+                &ai_action.id,
+                &config.model,
+                &prompt_hash,
+                &clip.content_hash,
+                &result,
+            )
+            .await?
+        }
     };
+    let result = entry.result.clone();
+
+    // 4. Store the result
+    match ai_action.output {
+        AiOutputMode::Metadata => {
+            // The latest result, so `has:` searches and the clip card find it
+            let mut metadata: serde_json::Value = if let Some(meta_str) = &clip.metadata {
+                serde_json::from_str(meta_str).unwrap_or(serde_json::json!({}))
+            } else {
+                serde_json::json!({})
+            };
 
-    metadata[key] = serde_json::json!(result);
-    let new_metadata_str = metadata.to_string();
+            metadata[ai_action.metadata_key()] = serde_json::json!(result);
+            let new_metadata_str = metadata.to_string();
 
-    sqlx::query("UPDATE clips SET metadata = ? WHERE uuid = ?")
-        .bind(&new_metadata_str)
-        .bind(&clip_id)
-        .execute(pool)
-        .await
-        .map_err(|e| e.to_string())?;
+            sqlx::query("UPDATE clips SET metadata = ? WHERE uuid = ?")
+                .bind(&new_metadata_str)
+                .bind(clip_id)
+                .execute(pool)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+        AiOutputMode::NewClip => {
+            ai_history::save_as_clip(pool, &mut entry).await?;
+            embeddings::wake();
+            let _ = app.emit("clipboard-change", ());
+        }
+        AiOutputMode::ReplaceClipboard => {
+            write_text_to_clipboard(&app, &result).await?;
+        }
+    }
 
-    Ok(result)
+    Ok(entry)
 }
 
 fn clip_to_list_item(clip: &Clip, image_path: Option<&str>) -> ClipboardItem {
@@ -120,6 +269,11 @@ fn clip_to_list_item(clip: &Clip, image_path: Option<&str>) -> ClipboardItem {
         source_app: clip.source_app.clone(),
         source_icon: clip.source_icon.clone(),
         metadata: clip.metadata.clone(),
+        is_sensitive: clip.is_sensitive,
+        is_template: clip.is_template,
+        clear_after_secs: clip.clear_after_secs,
+        content_length: None,
+        search_match: None,
     }
 }
 
@@ -140,6 +294,11 @@ fn clip_to_detail_item(clip: &Clip, full_image_content: Option<&[u8]>) -> Clipbo
         source_app: clip.source_app.clone(),
         source_icon: clip.source_icon.clone(),
         metadata: clip.metadata.clone(),
+        is_sensitive: clip.is_sensitive,
+        is_template: clip.is_template,
+        clear_after_secs: clip.clear_after_secs,
+        content_length: None,
+        search_match: None,
     }
 }
 
@@ -321,72 +480,17 @@ async fn load_full_image_content(pool: &SqlitePool, clip: &mut Clip) -> Result<V
     Err("Image content missing".to_string())
 }
 
-#[tauri::command]
-pub async fn get_clips(
-    filter_id: Option<String>,
-    limit: i64,
-    offset: i64,
-    preview_only: Option<bool>,
-    db: tauri::State<'_, Arc<Database>>,
+/// Image paths for the image rows, then the list items in row order.
+pub(crate) async fn clip_list_items(
+    pool: &SqlitePool,
+    rows: &[ClipListRow],
 ) -> Result<Vec<ClipboardItem>, String> {
-    let pool = &db.pool;
-    let preview_only = preview_only.unwrap_or(false);
-    let started = Instant::now();
-
-    log::info!(
-        "get_clips called with filter_id: {:?}, preview_only: {}",
-        filter_id,
-        preview_only
-    );
-
-    let sql_started = Instant::now();
-    let clips: Vec<Clip> = match filter_id.as_deref() {
-        Some(id) => {
-            let folder_id_num = id.parse::<i64>().ok();
-            if let Some(numeric_id) = folder_id_num {
-                log::info!("Querying for folder_id: {}", numeric_id);
-                sqlx::query_as(
-                    r#"
-                    SELECT * FROM clips WHERE is_deleted = 0 AND folder_id = ?
-                    ORDER BY created_at DESC LIMIT ? OFFSET ?
-                "#,
-                )
-                .bind(numeric_id)
-                .bind(limit)
-                .bind(offset)
-                .fetch_all(pool)
-                .await
-                .map_err(|e| e.to_string())?
-            } else {
-                log::info!("Unknown folder_id, returning empty");
-                Vec::new()
-            }
-        }
-        None => {
-            log::info!("Querying for items, offset: {}, limit: {}", offset, limit);
-            sqlx::query_as(
-                r#"
-                SELECT * FROM clips WHERE is_deleted = 0
-                ORDER BY created_at DESC LIMIT ? OFFSET ?
-            "#,
-            )
-            .bind(limit)
-            .bind(offset)
-            .fetch_all(pool)
-            .await
-            .map_err(|e| e.to_string())?
-        }
-    };
-    let sql_ms = sql_started.elapsed().as_millis();
-
-    log::info!("DB: Found {} clips", clips.len());
-
     // Batch fetch image paths
     let mut image_path_map: HashMap<String, String> = HashMap::new();
-    let image_uuids: Vec<String> = clips
+    let image_uuids: Vec<&str> = rows
         .iter()
-        .filter(|c| c.clip_type == "image")
-        .map(|c| c.uuid.clone())
+        .filter(|row| row.clip.clip_type == "image")
+        .map(|row| row.clip.uuid.as_str())
         .collect();
 
     if !image_uuids.is_empty() {
@@ -415,44 +519,144 @@ pub async fn get_clips(
         }
     }
 
-    let image_rows = image_uuids.len();
-    let raw_bytes: usize = clips.iter().map(|clip| clip.content.len()).sum();
-    let map_started = Instant::now();
-    let items: Vec<ClipboardItem> = clips
+    Ok(rows
         .iter()
-        .enumerate()
-        .map(|(idx, clip)| {
-            let item = clip_to_list_item(clip, image_path_map.get(&clip.uuid).map(|s| s.as_str()));
-            // Only log first 10 clips to reduce noise
-            if idx < 10 {
-                log::trace!(
-                    "{} Clip {}: type='{}', content_len={}",
-                    idx,
-                    clip.uuid,
-                    clip.clip_type,
-                    item.content.len()
-                );
-            }
+        .map(|row| {
+            let mut item = clip_to_list_item(
+                &row.clip,
+                image_path_map.get(&row.clip.uuid).map(|s| s.as_str()),
+            );
+            item.content_length = Some(row.content_length);
             item
         })
-        .collect();
+        .collect())
+}
+
+/// Folder id and saved query a `filter_id` scopes a list to; `None` if it can't name a folder.
+/// Smart folders have no clips of their own, so they scope by their query instead of an id.
+async fn resolve_filter(
+    pool: &SqlitePool,
+    filter_id: Option<&str>,
+) -> Result<Option<(Option<i64>, SearchQuery)>, String> {
+    let Some(id) = filter_id else {
+        return Ok(Some((None, SearchQuery::default())));
+    };
+    let Ok(folder_id) = id.parse::<i64>() else {
+        return Ok(None);
+    };
+    let query: Option<String> = sqlx::query_scalar("SELECT query FROM folders WHERE id = ?")
+        .bind(folder_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| e.to_string())?
+        .flatten();
+    match query {
+        Some(query) => Ok(Some((
+            None,
+            search_query::parse(&query).map_err(|e| e.to_string())?,
+        ))),
+        None => Ok(Some((Some(folder_id), SearchQuery::default()))),
+    }
+}
+
+/// Live count of clips in a smart folder.
+async fn count_matching(pool: &SqlitePool, query: &SearchQuery) -> Result<i64, String> {
+    let (where_sql, args) = query.to_sql();
+    let sql = format!(
+        "SELECT COUNT(*) FROM clips WHERE is_deleted = 0 AND ({})",
+        where_sql
+    );
+    let (count,) = search_query::bind_args(sqlx::query_as::<_, (i64,)>(&sql), &args)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(count)
+}
+
+/// Trimmed smart folder query, checked to parse; blank means a regular folder.
+fn normalize_folder_query(query: Option<String>) -> Result<Option<String>, String> {
+    match query.map(|q| q.trim().to_string()) {
+        Some(q) if !q.is_empty() => {
+            search_query::parse(&q).map_err(|e| e.to_string())?;
+            Ok(Some(q))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// `cursor` comes from the previous page's `next_cursor`; `offset` is only honoured without one.
+#[tauri::command]
+pub async fn get_clips(
+    filter_id: Option<String>,
+    limit: i64,
+    offset: Option<i64>,
+    cursor: Option<String>,
+    preview_only: Option<bool>,
+    db: tauri::State<'_, Arc<Database>>,
+) -> Result<ClipPage<ClipboardItem>, String> {
+    let pool = &db.pool();
+    let preview_only = preview_only.unwrap_or(false);
+    let started = Instant::now();
+
+    log::info!(
+        "get_clips called with filter_id: {:?}, preview_only: {}",
+        filter_id,
+        preview_only
+    );
+
+    let page_cursor = match cursor.as_deref() {
+        Some(value) => Some(Cursor::parse(value)?),
+        None => offset.filter(|o| *o > 0).map(Cursor::Offset),
+    };
+    let sql_started = Instant::now();
+    let (folder_id, scope) = match resolve_filter(pool, filter_id.as_deref()).await? {
+        Some(resolved) => resolved,
+        None => {
+            log::info!("Unknown folder_id, returning empty");
+            return Ok(ClipPage {
+                items: Vec::new(),
+                next_cursor: None,
+            });
+        }
+    };
+
+    let (rows, next_cursor) = clip_list::fetch_page(
+        pool,
+        folder_id,
+        &scope,
+        page_cursor.as_ref(),
+        limit,
+        preview_only,
+    )
+    .await?;
+    let sql_ms = sql_started.elapsed().as_millis();
+
+    log::info!("DB: Found {} clips", rows.len());
+
+    let image_rows = rows
+        .iter()
+        .filter(|row| row.clip.clip_type == "image")
+        .count();
+    let raw_bytes: usize = rows.iter().map(|row| row.clip.content.len()).sum();
+    let map_started = Instant::now();
+    let items = clip_list_items(pool, &rows).await?;
     let map_ms = map_started.elapsed().as_millis();
     let total_ms = started.elapsed().as_millis();
     log::info!(
-        "[perf][get_clips] sql_ms={} map_ms={} total_ms={} rows={} images={} raw_bytes={} preview_only={} filter_id={:?} offset={} limit={}",
+        "[perf][get_clips] sql_ms={} map_ms={} total_ms={} rows={} images={} raw_bytes={} preview_only={} filter_id={:?} cursor={:?} limit={}",
         sql_ms,
         map_ms,
         total_ms,
-        clips.len(),
+        rows.len(),
         image_rows,
         raw_bytes,
         preview_only,
         filter_id,
-        offset,
+        page_cursor,
         limit
     );
 
-    Ok(items)
+    Ok(ClipPage { items, next_cursor })
 }
 
 #[tauri::command]
@@ -460,7 +664,7 @@ pub async fn get_clip(
     id: String,
     db: tauri::State<'_, Arc<Database>>,
 ) -> Result<ClipboardItem, String> {
-    let pool = &db.pool;
+    let pool = &db.pool();
 
     let clip: Option<Clip> = sqlx::query_as(r#"SELECT * FROM clips WHERE uuid = ?"#)
         .bind(&id)
@@ -481,145 +685,585 @@ pub async fn get_clip(
     }
 }
 
-// TODO(xueshi) get_clip is same as get_clip_detail???
+// TODO(xueshi) get_clip is same as get_clip_detail???
+#[tauri::command]
+pub async fn get_clip_detail(
+    id: String,
+    db: tauri::State<'_, Arc<Database>>,
+) -> Result<ClipboardItem, String> {
+    get_clip(id, db).await
+}
+
+#[tauri::command]
+pub async fn paste_clip(
+    id: String,
+    inputs: Option<HashMap<String, String>>,
+    app: AppHandle,
+    window: tauri::WebviewWindow,
+    db: tauri::State<'_, Arc<Database>>,
+) -> Result<(), String> {
+    let pool = &db.pool();
+
+    let clip: Option<Clip> = sqlx::query_as(r#"SELECT * FROM clips WHERE uuid = ?"#)
+        .bind(&id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    match clip {
+        Some(mut clip) => {
+            let mut plan = paste_strategy::plan(&app, false).await;
+            let text = write_clip_for_paste(
+                &app,
+                pool,
+                &mut clip,
+                &mut plan,
+                &inputs.unwrap_or_default(),
+                false,
+            )
+            .await?;
+            let content = text.unwrap_or_else(|| "[Image]".to_string());
+            finish_paste(&app, &window, &content, plan);
+            Ok(())
+        }
+        None => Err("Clip not found".to_string()),
+    }
+}
+
+/// Put a clip on the clipboard ready to paste and move it to the top of the history.
+/// Returns the text written, or `None` for an image. On Windows and Linux the webview writes
+/// images itself, unless `write_image` asks for it here (when pasting without the window).
+pub(crate) async fn write_clip_for_paste(
+    app: &AppHandle,
+    pool: &SqlitePool,
+    clip: &mut Clip,
+    plan: &mut paste_strategy::PastePlan,
+    inputs: &HashMap<String, String>,
+    write_image: bool,
+) -> Result<Option<String>, String> {
+    write_transformed_clip_for_paste(app, pool, clip, plan, inputs, &[], write_image).await
+}
+
+/// `write_clip_for_paste` with `transforms` applied to the clip's text first.
+async fn write_transformed_clip_for_paste(
+    app: &AppHandle,
+    pool: &SqlitePool,
+    clip: &mut Clip,
+    plan: &mut paste_strategy::PastePlan,
+    inputs: &HashMap<String, String>,
+    transforms: &[String],
+    write_image: bool,
+) -> Result<Option<String>, String> {
+    paste_strategy::check_clip_type(&plan.profile, &clip.clip_type)?;
+
+    let text = if clip.clip_type == "image" {
+        None
+    } else if clip.is_template {
+        let expanded = expand_template(clip, inputs).await?;
+        // A transform can move the text around the cursor mark
+        if transforms.is_empty() {
+            plan.cursor_back = expanded.cursor_back;
+        }
+        Some(transforms::apply_all(&expanded.text, transforms)?)
+    } else {
+        let content_str =
+            transforms::apply_all(&String::from_utf8_lossy(&clip.content), transforms)?;
+        Some(paste_strategy::text_for(
+            &plan.profile,
+            &clip.clip_type,
+            content_str,
+        ))
+    };
+
+    let final_res = match &text {
+        Some(text) => write_text_to_clipboard(app, text).await,
+        None => write_image_for_paste(app, pool, clip, write_image).await,
+    };
+
+    if final_res.is_ok() {
+        // Manually perform the LRU bump (update created_at)
+        let _ = sqlx::query(r#"UPDATE clips SET created_at = CURRENT_TIMESTAMP WHERE uuid = ?"#)
+            .bind(&clip.uuid)
+            .execute(pool)
+            .await;
+
+        // Sensitive clips are cleared from the clipboard again after a while
+        if let Some(text) = &text {
+            auto_clear::schedule_for_clip(app, pool, &clip.uuid, text).await;
+        }
+    }
+
+    final_res.map(|_| text)
+}
+
+/// Put an image clip on the clipboard without the monitor capturing it again.
+async fn write_image_for_paste(
+    app: &AppHandle,
+    pool: &SqlitePool,
+    clip: &mut Clip,
+    write_image: bool,
+) -> Result<(), String> {
+    // Synchronize clipboard access across the app
+    let _guard = crate::clipboard::CLIPBOARD_SYNC.lock().await;
+
+    // Stop monitor
+    if let Err(e) = stop_listening().await {
+        log::error!("Failed to stop listener: {}", e);
+    }
+
+    crate::clipboard::set_ignore_hash(clip.content_hash.clone());
+
+    // Write PNG to temp file + file URL on pasteboard (fast path via disk)
+    #[cfg(target_os = "macos")]
+    let res = {
+        let _ = write_image;
+        match load_full_image_content(pool, clip).await {
+            Ok(bytes) => crate::clipboard::write_png_to_pasteboard(&bytes)
+                .map_err(|e| format!("Failed to write image to clipboard: {}", e)),
+            Err(e) => Err(e),
+        }
+    };
+
+    // Normally the frontend already writes image via navigator.clipboard API.
+    // Avoid redundant backend file read to keep paste path fast.
+    #[cfg(not(target_os = "macos"))]
+    let res = if write_image {
+        match load_full_image_content(pool, clip).await {
+            Ok(bytes) => write_png_with_clipboard_rs(&bytes),
+            Err(e) => Err(e),
+        }
+    } else {
+        Ok(())
+    };
+
+    // Restart monitor
+    if let Err(e) = start_listening(app.clone()).await {
+        log::error!("Failed to restart listener: {}", e);
+    }
+    res
+}
+
+#[cfg(not(target_os = "macos"))]
+fn write_png_with_clipboard_rs(png: &[u8]) -> Result<(), String> {
+    use clipboard_rs::common::{RustImage, RustImageData};
+    use clipboard_rs::{Clipboard, ClipboardContext};
+
+    let image = RustImageData::from_bytes(png).map_err(|e| e.to_string())?;
+    let ctx = ClipboardContext::new().map_err(|e| e.to_string())?;
+    ctx.set_image(image)
+        .map_err(|e| format!("Failed to write image to clipboard: {}", e))
+}
+
+/// Fill in a template clip's placeholders; `{{clipboard}}` is what's on the clipboard right now.
+pub(crate) async fn expand_template(
+    clip: &Clip,
+    inputs: &HashMap<String, String>,
+) -> Result<templates::Expanded, String> {
+    let segments = templates::parse(&String::from_utf8_lossy(&clip.content)).map_err(|errors| {
+        errors
+            .into_iter()
+            .next()
+            .map(|e| e.message)
+            .unwrap_or_default()
+    })?;
+    let clipboard = if segments.contains(&templates::Segment::Clipboard) {
+        read_text().await.ok()
+    } else {
+        None
+    };
+    templates::expand(
+        &segments,
+        &templates::Context {
+            now: chrono::Local::now(),
+            clipboard: clipboard.as_deref(),
+            inputs,
+        },
+    )
+}
+
+/// Write text to the clipboard without the monitor capturing it as a new clip.
+pub(crate) async fn write_text_to_clipboard(app: &AppHandle, text: &str) -> Result<(), String> {
+    // Synchronize clipboard access across the app
+    let _guard = crate::clipboard::CLIPBOARD_SYNC.lock().await;
+
+    // Stop monitor
+    if let Err(e) = stop_listening().await {
+        log::error!("Failed to stop listener: {}", e);
+    }
+
+    crate::clipboard::set_ignore_hash(crate::clipboard::calculate_hash(text.as_bytes()));
+    let res = write_text_with_retry(text).await;
+
+    // Restart monitor
+    if let Err(e) = start_listening(app.clone()).await {
+        log::error!("Failed to restart listener: {}", e);
+    }
+    res
+}
+
+/// Write text to the clipboard, retrying while another app holds it open.
+pub(crate) async fn write_text_with_retry(text: &str) -> Result<(), String> {
+    let mut last_err = String::new();
+    for i in 0..5 {
+        match write_text(text.to_string()).await {
+            Ok(_) => return Ok(()),
+            Err(e) => {
+                last_err = e.to_string();
+                log::warn!(
+                    "Clipboard write (text) attempt {} failed: {}. Retrying...",
+                    i + 1,
+                    last_err
+                );
+                tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+            }
+        }
+    }
+    Err(format!("Failed to set clipboard text: {}", last_err))
+}
+
+/// After a successful clipboard write: notify the UI, hide the window and auto-paste if enabled.
+fn finish_paste(
+    app: &AppHandle,
+    window: &tauri::WebviewWindow,
+    content: &str,
+    plan: paste_strategy::PastePlan,
+) {
+    let _ = window.emit("clipboard-write", content);
+
+    // Check auto_paste setting
+    let manager = app.state::<Arc<SettingsManager>>();
+    let settings = manager.get();
+    let auto_paste = settings.auto_paste;
+
+    if auto_paste {
+        // Auto-Paste Logic
+        // 1. Hide window immediately to trigger focus switch to previous app
+        let app_handle = app.clone();
+        crate::animate_window_hide(
+            window,
+            Some(Box::new(move || {
+                // 2. Callback executed AFTER window is hidden
+                // Small buffer to ensure OS focus switch is complete
+                #[cfg(target_os = "windows")]
+                std::thread::sleep(std::time::Duration::from_millis(200));
+                #[cfg(target_os = "macos")]
+                std::thread::sleep(std::time::Duration::from_millis(100));
+                paste_strategy::send(&app_handle, plan);
+            })),
+        );
+    } else {
+        crate::animate_window_hide(window, None);
+    }
+}
+
+/// A non-image clip, for features that rewrite its text before pasting.
+async fn text_clip(pool: &SqlitePool, id: &str) -> Result<Clip, String> {
+    let clip: Clip = sqlx::query_as(r#"SELECT * FROM clips WHERE uuid = ?"#)
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or("Clip not found")?;
+    if clip.clip_type == "image" {
+        return Err("This only works on text clips".to_string());
+    }
+    Ok(clip)
+}
+
+#[tauri::command]
+pub fn get_paste_transforms() -> Vec<transforms::TransformInfo> {
+    transforms::list()
+}
+
+/// The clip's text after `transforms`, without touching the clipboard. Templates are
+/// expanded first, so this is what `paste_clip_transformed` would paste.
+#[tauri::command]
+pub async fn preview_transform(
+    id: String,
+    transforms: Vec<String>,
+    inputs: Option<HashMap<String, String>>,
+    db: tauri::State<'_, Arc<Database>>,
+) -> Result<String, String> {
+    let clip = text_clip(&db.pool(), &id).await?;
+    let text = if clip.is_template {
+        expand_template(&clip, &inputs.unwrap_or_default())
+            .await?
+            .text
+    } else {
+        String::from_utf8_lossy(&clip.content).to_string()
+    };
+    transforms::apply_all(&text, &transforms)
+}
+
+/// Like `paste_clip`, with `transforms` applied to the text first. The stored clip is unchanged.
+#[tauri::command]
+pub async fn paste_clip_transformed(
+    id: String,
+    transforms: Vec<String>,
+    inputs: Option<HashMap<String, String>>,
+    app: AppHandle,
+    window: tauri::WebviewWindow,
+    db: tauri::State<'_, Arc<Database>>,
+) -> Result<(), String> {
+    let pool = &db.pool();
+
+    let mut clip = text_clip(pool, &id).await?;
+    let mut plan = paste_strategy::plan(&app, false).await;
+    let text = write_transformed_clip_for_paste(
+        &app,
+        pool,
+        &mut clip,
+        &mut plan,
+        &inputs.unwrap_or_default(),
+        &transforms,
+        false,
+    )
+    .await?
+    .unwrap_or_default();
+    finish_paste(&app, &window, &text, plan);
+    Ok(())
+}
+
+/// Type the clip's text into the previously focused app instead of pasting it. Emits
+/// `type-out-finished` with a `TypeOutResult` once done, or once Escape stops it.
+#[tauri::command]
+pub async fn type_out_clip(
+    id: String,
+    inputs: Option<HashMap<String, String>>,
+    app: AppHandle,
+    window: tauri::WebviewWindow,
+    db: tauri::State<'_, Arc<Database>>,
+) -> Result<(), String> {
+    if !cfg!(any(target_os = "windows", target_os = "macos")) {
+        return Err("Type-out isn't supported on this platform".to_string());
+    }
+
+    let clip = text_clip(&db.pool(), &id).await?;
+    let text = if clip.is_template {
+        expand_template(&clip, &inputs.unwrap_or_default())
+            .await?
+            .text
+    } else {
+        String::from_utf8_lossy(&clip.content).to_string()
+    };
+
+    let settings = app.state::<Arc<SettingsManager>>().get();
+    let length = text.chars().count() as u64;
+    if length > settings.type_out_max_chars {
+        return Err(format!(
+            "Too long to type out: {} characters, the limit is {}",
+            length, settings.type_out_max_chars
+        ));
+    }
+
+    let guard = type_out::TypingGuard::acquire()?;
+    let delay = std::time::Duration::from_millis(settings.type_out_delay_ms);
+    let app_handle = app.clone();
+    // Typing starts once the window is hidden and the previous app has focus again. It runs on
+    // its own thread so the animation lock is released and the window can be shown meanwhile.
+    let started = crate::animate_window_hide(
+        &window,
+        Some(Box::new(move || {
+            std::thread::spawn(move || {
+                let _guard = guard;
+                std::thread::sleep(std::time::Duration::from_millis(200));
+                let result = type_out::type_text(&text, delay);
+                let _ = app_handle.emit("type-out-finished", &result);
+            });
+        })),
+    );
+    if !started {
+        return Err("The window is still animating, try again".to_string());
+    }
+    Ok(())
+}
+
+/// Combine clips in the order of `ids`. Text is pasted directly or saved as a new clip. Images
+/// are always stitched into a new image clip; its id is returned so the frontend can paste it.
+#[tauri::command]
+pub async fn merge_clips(
+    ids: Vec<String>,
+    options: merge::MergeOptions,
+    app: AppHandle,
+    window: tauri::WebviewWindow,
+    db: tauri::State<'_, Arc<Database>>,
+) -> Result<Option<String>, String> {
+    let pool = &db.pool();
+
+    if ids.len() < 2 {
+        return Err("Select at least two clips to merge".to_string());
+    }
+    let mut clips: Vec<Clip> = Vec::with_capacity(ids.len());
+    for id in &ids {
+        let clip: Clip = sqlx::query_as(r#"SELECT * FROM clips WHERE uuid = ?"#)
+            .bind(id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or("Clip not found")?;
+        clips.push(clip);
+    }
+
+    let image_count = clips.iter().filter(|c| c.clip_type == "image").count();
+    if image_count == clips.len() {
+        let mut pngs = Vec::with_capacity(clips.len());
+        for clip in &mut clips {
+            pngs.push(load_full_image_content(pool, clip).await?);
+        }
+        let (png, width, height) = merge::stitch_images(&pngs, options.direction)?;
+        let uuid = merge::save_clip(pool, "image", &[], Some((&png, width, height))).await?;
+        let _ = app.emit("clipboard-change", ());
+        return Ok(Some(uuid));
+    }
+    if image_count > 0 {
+        return Err("Images can't be merged with text".to_string());
+    }
+
+    let items: Vec<String> = clips
+        .iter()
+        .map(|c| String::from_utf8_lossy(&c.content).to_string())
+        .collect();
+    let text = merge::join_text(&items, &options.separator, options.wrapper.as_deref());
+
+    match options.output {
+        merge::MergeOutput::NewClip => {
+            let uuid = merge::save_clip(pool, "text", text.as_bytes(), None).await?;
+            embeddings::wake();
+            let _ = app.emit("clipboard-change", ());
+            Ok(Some(uuid))
+        }
+        merge::MergeOutput::Paste => {
+            let plan = paste_strategy::plan(&app, false).await;
+            write_text_to_clipboard(&app, &text).await?;
+
+            // Cleared as soon as the first of the merged clips would be
+            let mut clear_after = None;
+            for clip in &clips {
+                if let Some(secs) = auto_clear::delay_for(pool, &clip.uuid).await {
+                    clear_after = Some(clear_after.map_or(secs, |c: u64| c.min(secs)));
+                }
+            }
+            if let Some(secs) = clear_after {
+                auto_clear::schedule(&app, &text, secs);
+            }
+
+            finish_paste(&app, &window, &text, plan);
+            Ok(None)
+        }
+    }
+}
+
+/// Queue clips for the paste-queue hotkey, after any already queued.
+#[tauri::command]
+pub async fn enqueue_clips(
+    ids: Vec<String>,
+    app: AppHandle,
+    db: tauri::State<'_, Arc<Database>>,
+) -> Result<(), String> {
+    let pool = &db.pool();
+    let rows = clip_list::fetch_by_uuid(pool, &ids, true).await?;
+    if rows.iter().any(|row| row.clip.clip_type == "image") {
+        return Err("Image clips can't be queued".to_string());
+    }
+    // The queue pastes without showing the window, so there's nowhere to ask for inputs
+    for row in rows.iter().filter(|row| row.clip.is_template) {
+        let content: Vec<u8> = sqlx::query_scalar(r#"SELECT content FROM clips WHERE uuid = ?"#)
+            .bind(&row.clip.uuid)
+            .fetch_one(pool)
+            .await
+            .map_err(|e| e.to_string())?;
+        if !templates::validate(&String::from_utf8_lossy(&content))
+            .inputs
+            .is_empty()
+        {
+            return Err("Templates that ask for input can't be queued".to_string());
+        }
+    }
+    paste_queue::enqueue(&app, rows.into_iter().map(|row| row.clip.uuid).collect());
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_paste_queue(
+    db: tauri::State<'_, Arc<Database>>,
+) -> Result<Vec<ClipboardItem>, String> {
+    let pool = &db.pool();
+    let rows = clip_list::fetch_by_uuid(pool, &paste_queue::list(), true).await?;
+    clip_list_items(pool, &rows).await
+}
+
+#[tauri::command]
+pub fn reorder_paste_queue(ids: Vec<String>, app: AppHandle) -> Result<(), String> {
+    paste_queue::reorder(&app, ids)
+}
+
+#[tauri::command]
+pub fn remove_from_paste_queue(id: String, app: AppHandle) {
+    paste_queue::remove(&app, &id);
+}
+
+#[tauri::command]
+pub fn clear_paste_queue(app: AppHandle) {
+    paste_queue::clear(&app);
+}
+
+/// Bind the paste-queue hotkey; an empty string turns it off.
+#[tauri::command]
+pub fn register_paste_queue_shortcut(hotkey: String, app: AppHandle) -> Result<(), String> {
+    paste_queue::register_shortcut(&app, &hotkey)
+}
+
+/// Bind `modifiers`+1..9 to paste the Nth most recent clip; empty disables them.
+#[tauri::command]
+pub fn register_quick_paste_shortcuts(modifiers: String, app: AppHandle) -> Result<(), String> {
+    quick_paste::register_shortcuts(&app, &modifiers)
+}
+
+/// Placeholders, `{{input:…}}` names and errors in `template`, for the editor.
 #[tauri::command]
-pub async fn get_clip_detail(
-    id: String,
-    db: tauri::State<'_, Arc<Database>>,
-) -> Result<ClipboardItem, String> {
-    get_clip(id, db).await
+pub fn validate_template(template: String) -> templates::TemplateInfo {
+    templates::validate(&template)
 }
 
+/// Mark a text clip as a template so `paste_clip` expands its placeholders.
 #[tauri::command]
-pub async fn paste_clip(
+pub async fn set_clip_template(
     id: String,
+    is_template: bool,
     app: AppHandle,
-    window: tauri::WebviewWindow,
     db: tauri::State<'_, Arc<Database>>,
 ) -> Result<(), String> {
-    let pool = &db.pool;
+    let pool = &db.pool();
 
-    let clip: Option<Clip> = sqlx::query_as(r#"SELECT * FROM clips WHERE uuid = ?"#)
+    if is_template {
+        let (clip_type, content): (String, Vec<u8>) =
+            sqlx::query_as("SELECT clip_type, content FROM clips WHERE uuid = ?")
+                .bind(&id)
+                .fetch_optional(pool)
+                .await
+                .map_err(|e| e.to_string())?
+                .ok_or("Clip not found")?;
+        if clip_type == "image" {
+            return Err("Image clips can't be templates".to_string());
+        }
+        let info = templates::validate(&String::from_utf8_lossy(&content));
+        if let Some(error) = info.errors.first() {
+            return Err(error.message.clone());
+        }
+    }
+
+    let result = sqlx::query("UPDATE clips SET is_template = ? WHERE uuid = ?")
+        .bind(is_template)
         .bind(&id)
-        .fetch_optional(pool)
+        .execute(pool)
         .await
         .map_err(|e| e.to_string())?;
-
-    match clip {
-        Some(mut clip) => {
-            // Synchronize clipboard access across the app
-            let _guard = crate::clipboard::CLIPBOARD_SYNC.lock().await;
-
-            let content_hash = clip.content_hash.clone();
-            let uuid = clip.uuid.clone();
-
-            // Stop monitor
-            if let Err(e) = stop_listening().await {
-                log::error!("Failed to stop listener: {}", e);
-            }
-
-            let mut final_res = Ok(());
-
-            if clip.clip_type == "image" {
-                crate::clipboard::set_ignore_hash(content_hash.clone());
-                //crate::clipboard::set_last_stable_hash(content_hash.clone());
-
-                #[cfg(target_os = "macos")]
-                {
-                    let full_image_bytes = load_full_image_content(pool, &mut clip).await?;
-                    // Write PNG to temp file + file URL on pasteboard (fast path via disk)
-                    if let Err(e) = crate::clipboard::write_png_to_pasteboard(&full_image_bytes) {
-                        final_res = Err(format!("Failed to write image to clipboard: {}", e));
-                    }
-                }
-
-                #[cfg(not(target_os = "macos"))]
-                {
-                    // On Windows, frontend already writes image via navigator.clipboard API.
-                    // Avoid redundant backend file read to keep paste path fast.
-                }
-            } else {
-                let content_str = String::from_utf8_lossy(&clip.content).to_string();
-                crate::clipboard::set_ignore_hash(content_hash.clone());
-                //crate::clipboard::set_last_stable_hash(content_hash.clone());
-
-                let mut last_err = String::new();
-                for i in 0..5 {
-                    match write_text(content_str.clone()).await {
-                        Ok(_) => {
-                            last_err.clear();
-                            break;
-                        }
-                        Err(e) => {
-                            last_err = e.to_string();
-                            log::warn!(
-                                "Clipboard write (text) attempt {} failed: {}. Retrying...",
-                                i + 1,
-                                last_err
-                            );
-                            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
-                        }
-                    }
-                }
-                if !last_err.is_empty() {
-                    final_res = Err(format!("Failed to set clipboard text: {}", last_err));
-                }
-            }
-
-            // Manually perform the LRU bump (update created_at)
-            let _ =
-                sqlx::query(r#"UPDATE clips SET created_at = CURRENT_TIMESTAMP WHERE uuid = ?"#)
-                    .bind(&uuid)
-                    .execute(pool)
-                    .await;
-
-            // Restart monitor
-            let app_clone = app.clone();
-            if let Err(e) = start_listening(app_clone).await {
-                log::error!("Failed to restart listener: {}", e);
-            }
-
-            if final_res.is_ok() {
-                let content = if clip.clip_type == "image" {
-                    "[Image]".to_string()
-                } else {
-                    String::from_utf8_lossy(&clip.content).to_string()
-                };
-                let _ = window.emit("clipboard-write", &content);
-
-                // Check auto_paste setting
-                let manager = app.state::<Arc<SettingsManager>>();
-                let settings = manager.get();
-                let auto_paste = settings.auto_paste;
-
-                if auto_paste {
-                    // Auto-Paste Logic
-                    // 1. Hide window immediately to trigger focus switch to previous app
-                    crate::animate_window_hide(
-                        &window,
-                        Some(Box::new(move || {
-                            // 2. Callback executed AFTER window is hidden
-                            #[cfg(target_os = "windows")]
-                            {
-                                // Small buffer to ensure OS focus switch is complete
-                                std::thread::sleep(std::time::Duration::from_millis(200));
-                                crate::clipboard::send_paste_input();
-                            }
-                            #[cfg(target_os = "macos")]
-                            {
-                                std::thread::sleep(std::time::Duration::from_millis(100));
-                                crate::clipboard::send_paste_input();
-                            }
-                        })),
-                    );
-                } else {
-                    crate::animate_window_hide(&window, None);
-                }
-            }
-            final_res
-        }
-        None => Err("Clip not found".to_string()),
+    if result.rows_affected() == 0 {
+        return Err("Clip not found".to_string());
     }
+
+    let _ = app.emit("clipboard-change", ());
+    Ok(())
 }
 
 #[tauri::command]
@@ -628,7 +1272,7 @@ pub async fn delete_clip(
     hard_delete: bool,
     db: tauri::State<'_, Arc<Database>>,
 ) -> Result<(), String> {
-    let pool = &db.pool;
+    let pool = &db.pool();
 
     if hard_delete {
         delete_clip_image_file_by_uuid(pool, &id).await?;
@@ -660,13 +1304,25 @@ pub async fn move_to_folder(
     folder_id: Option<String>,
     db: tauri::State<'_, Arc<Database>>,
 ) -> Result<(), String> {
-    let pool = &db.pool;
+    let pool = &db.pool();
 
     let folder_id = match folder_id {
         Some(id) => Some(id.parse::<i64>().map_err(|_| "Invalid folder ID")?),
         None => None,
     };
 
+    if let Some(id) = folder_id {
+        let is_smart: Option<bool> =
+            sqlx::query_scalar("SELECT query IS NOT NULL FROM folders WHERE id = ?")
+                .bind(id)
+                .fetch_optional(pool)
+                .await
+                .map_err(|e| e.to_string())?;
+        if is_smart == Some(true) {
+            return Err("Clips can't be moved into a smart folder".to_string());
+        }
+    }
+
     sqlx::query(r#"UPDATE clips SET folder_id = ? WHERE uuid = ?"#)
         .bind(folder_id)
         .bind(&clip_id)
@@ -681,10 +1337,12 @@ pub async fn create_folder(
     name: String,
     icon: Option<String>,
     color: Option<String>,
+    query: Option<String>,
     db: tauri::State<'_, Arc<Database>>,
     window: tauri::WebviewWindow,
 ) -> Result<FolderItem, String> {
-    let pool = &db.pool;
+    let pool = &db.pool();
+    let query = normalize_folder_query(query)?;
 
     // Check if folder with same name exists (excluding system folders if we wanted, but name uniqueness is good generally)
     let exists: Option<i64> = sqlx::query_scalar("SELECT 1 FROM folders WHERE name = ?")
@@ -697,15 +1355,23 @@ pub async fn create_folder(
         return Err("A folder with this name already exists".to_string());
     }
 
-    let id = sqlx::query(r#"INSERT INTO folders (name, icon, color) VALUES (?, ?, ?)"#)
+    let id = sqlx::query(r#"INSERT INTO folders (name, icon, color, query) VALUES (?, ?, ?, ?)"#)
         .bind(&name)
         .bind(icon.as_ref())
         .bind(color.as_ref())
+        .bind(query.as_ref())
         .execute(pool)
         .await
         .map_err(|e| e.to_string())?
         .last_insert_rowid();
 
+    let item_count = match &query {
+        Some(q) => {
+            count_matching(pool, &search_query::parse(q).map_err(|e| e.to_string())?).await?
+        }
+        None => 0,
+    };
+
     let _ = window.emit("clipboard-change", ());
 
     Ok(FolderItem {
@@ -714,17 +1380,46 @@ pub async fn create_folder(
         icon,
         color,
         is_system: false,
-        item_count: 0,
+        item_count,
+        query,
+        clear_after_secs: None,
     })
 }
 
+/// Change the saved query of a smart folder. Regular folders can't be turned into smart ones.
+#[tauri::command]
+pub async fn update_smart_folder(
+    id: String,
+    query: String,
+    db: tauri::State<'_, Arc<Database>>,
+    window: tauri::WebviewWindow,
+) -> Result<(), String> {
+    let pool = &db.pool();
+
+    let folder_id: i64 = id.parse().map_err(|_| "Invalid folder ID")?;
+    let query = normalize_folder_query(Some(query))?.ok_or("Smart folder query can't be empty")?;
+
+    let result = sqlx::query(r#"UPDATE folders SET query = ? WHERE id = ? AND query IS NOT NULL"#)
+        .bind(query)
+        .bind(folder_id)
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    if result.rows_affected() == 0 {
+        return Err("Not a smart folder".to_string());
+    }
+
+    let _ = window.emit("clipboard-change", ());
+    Ok(())
+}
+
 #[tauri::command]
 pub async fn delete_folder(
     id: String,
     db: tauri::State<'_, Arc<Database>>,
     window: tauri::WebviewWindow,
 ) -> Result<(), String> {
-    let pool = &db.pool;
+    let pool = &db.pool();
 
     let folder_id: i64 = id.parse().map_err(|_| "Invalid folder ID")?;
     sqlx::query(r#"DELETE FROM folders WHERE id = ?"#)
@@ -744,7 +1439,7 @@ pub async fn rename_folder(
     db: tauri::State<'_, Arc<Database>>,
     window: tauri::WebviewWindow,
 ) -> Result<(), String> {
-    let pool = &db.pool;
+    let pool = &db.pool();
 
     let folder_id: i64 = id.parse().map_err(|_| "Invalid folder ID")?;
 
@@ -773,115 +1468,335 @@ pub async fn rename_folder(
     Ok(())
 }
 
+/// Exact mode pages newest first by cursor; fuzzy and regex results are ranked, so their cursor is an offset.
 #[tauri::command]
 pub async fn search_clips(
     query: String,
     filter_id: Option<String>,
     limit: i64,
-    offset: i64,
+    offset: Option<i64>,
+    cursor: Option<String>,
+    mode: Option<String>,
+    preview_only: Option<bool>,
     db: tauri::State<'_, Arc<Database>>,
-) -> Result<Vec<ClipboardItem>, String> {
-    let pool = &db.pool;
+) -> Result<ClipPage<ClipboardItem>, String> {
+    let pool = &db.pool();
+    let preview_only = preview_only.unwrap_or(false);
     let started = Instant::now();
 
-    let search_pattern = format!("%{}%", query);
+    let mode = SearchMode::from_str(mode.as_deref().unwrap_or_default())?;
+    let page_cursor = match cursor.as_deref() {
+        Some(value) => Some(Cursor::parse(value)?),
+        None => offset.filter(|o| *o > 0).map(Cursor::Offset),
+    };
 
-    let sql_started = Instant::now();
-    let clips: Vec<Clip> = match filter_id.as_deref() {
-        Some(id) => {
-            let folder_id_num = id.parse::<i64>().ok();
-            if let Some(numeric_id) = folder_id_num {
-                sqlx::query_as(r#"
-                    SELECT * FROM clips WHERE is_deleted = 0 AND folder_id = ? AND (text_preview LIKE ? OR content LIKE ?)
-                    ORDER BY created_at DESC LIMIT ? OFFSET ?
-                "#)
-                .bind(numeric_id)
-                .bind(&search_pattern)
-                .bind(&search_pattern)
-                .bind(limit)
-                .bind(offset)
-                .fetch_all(pool).await.map_err(|e| e.to_string())?
-            } else {
-                Vec::new()
-            }
+    let (folder_id, scope) = match resolve_filter(pool, filter_id.as_deref()).await? {
+        Some(resolved) => resolved,
+        None => {
+            return Ok(ClipPage {
+                items: Vec::new(),
+                next_cursor: None,
+            })
         }
-        None => sqlx::query_as(
-            r#"
-                SELECT * FROM clips WHERE is_deleted = 0 AND (text_preview LIKE ? OR content LIKE ?)
-                ORDER BY created_at DESC LIMIT ? OFFSET ?
-            "#,
-        )
-        .bind(&search_pattern)
-        .bind(&search_pattern)
-        .bind(limit)
-        .bind(offset)
-        .fetch_all(pool)
-        .await
-        .map_err(|e| e.to_string())?,
     };
-    let sql_ms = sql_started.elapsed().as_millis();
 
-    // Batch fetch image paths
-    let mut image_path_map: HashMap<String, String> = HashMap::new();
-    let image_uuids: Vec<String> = clips
-        .iter()
-        .filter(|c| c.clip_type == "image")
-        .map(|c| c.uuid.clone())
-        .collect();
+    // Regex mode takes the whole query as the pattern; the other modes use the query language.
+    let (parsed, regex) = match mode {
+        SearchMode::Regex => (
+            SearchQuery::default(),
+            Some(search_match::compile_regex(&query)?),
+        ),
+        _ => (
+            search_query::parse(&query).map_err(|e| e.to_string())?,
+            None,
+        ),
+    };
+    let terms: Vec<String> = parsed.text_terms().into_iter().map(String::from).collect();
+    let matcher = match (mode, regex) {
+        (SearchMode::Regex, Some(regex)) => Some(Matcher::Regex(regex)),
+        (SearchMode::Fuzzy, _) if !terms.is_empty() => Some(Matcher::Fuzzy(terms.clone())),
+        _ => None,
+    };
 
-    if !image_uuids.is_empty() {
-        let placeholders: Vec<String> = image_uuids.iter().map(|_| "?".to_string()).collect();
-        let query = format!(
-            "SELECT clip_uuid, file_path FROM clip_images WHERE clip_uuid IN ({})",
-            placeholders.join(",")
+    let sql_started = Instant::now();
+    let mut matches: HashMap<String, SearchMatch> = HashMap::new();
+    let (rows, next_cursor) = if let Some(matcher) = matcher {
+        // Filters run in SQL; text is matched and ranked here over the newest candidates.
+        let (where_sql, args) = parsed.without_text_terms().and(&scope).to_sql();
+        let sql = format!(
+            r#"
+            SELECT uuid, clip_type,
+                CASE WHEN clip_type = 'image' THEN CAST(text_preview AS BLOB) ELSE substr(content, 1, ?) END
+            FROM clips WHERE is_deleted = 0{} AND ({})
+            ORDER BY created_at DESC, id DESC LIMIT ?
+            "#,
+            if folder_id.is_some() {
+                " AND folder_id = ?"
+            } else {
+                ""
+            },
+            where_sql
         );
-
-        let mut query_builder = sqlx::query_as::<_, (String, Option<String>)>(&query);
-        for uuid in &image_uuids {
-            query_builder = query_builder.bind(uuid);
+        let mut candidates_query = sqlx::query_as::<_, (String, String, Vec<u8>)>(&sql)
+            .bind(search_match::MAX_MATCH_BYTES as i64);
+        if let Some(numeric_id) = folder_id {
+            candidates_query = candidates_query.bind(numeric_id);
         }
-
-        let results = query_builder
+        let candidates = search_query::bind_args(candidates_query, &args)
+            .bind(search_match::CANDIDATE_LIMIT)
             .fetch_all(pool)
             .await
             .map_err(|e| e.to_string())?;
-        for (uuid, path) in results {
-            if let Some(p) = path {
-                if !p.is_empty() {
-                    image_path_map.insert(uuid, p);
+
+        let ranked = tokio::task::spawn_blocking(move || search_match::rank(candidates, &matcher))
+            .await
+            .map_err(|e| e.to_string())?;
+        let start = match page_cursor {
+            Some(Cursor::Offset(value)) => value.max(0) as usize,
+            Some(Cursor::After { .. }) => {
+                return Err("Ranked search needs an offset cursor".to_string())
+            }
+            None => 0,
+        };
+        let end = start.saturating_add(limit.max(0) as usize);
+        let next_cursor = (ranked.len() > end).then(|| Cursor::Offset(end as i64).encode());
+        let page: Vec<(String, SearchMatch)> =
+            ranked.into_iter().skip(start).take(end - start).collect();
+
+        let uuids: Vec<String> = page.iter().map(|(uuid, _)| uuid.clone()).collect();
+        let rows = clip_list::fetch_by_uuid(pool, &uuids, preview_only).await?;
+        matches.extend(page);
+        (rows, next_cursor)
+    } else {
+        let (rows, next_cursor) = clip_list::fetch_page(
+            pool,
+            folder_id,
+            &parsed.clone().and(&scope),
+            page_cursor.as_ref(),
+            limit,
+            preview_only,
+        )
+        .await?;
+
+        if !terms.is_empty() {
+            let terms: Vec<&str> = terms.iter().map(String::as_str).collect();
+            for row in &rows {
+                let clip = &row.clip;
+                let (text, field) = if clip.clip_type == "image" {
+                    (clip.text_preview.clone(), "preview")
+                } else {
+                    (search_match::match_text(&clip.content), "content")
+                };
+                if let Some(scored) = search_match::exact_match(&terms, &text) {
+                    matches.insert(clip.uuid.clone(), scored.into_search_match(&text, field));
                 }
             }
         }
-    }
+        (rows, next_cursor)
+    };
+    let sql_ms = sql_started.elapsed().as_millis();
 
-    let image_rows = image_uuids.len();
-    let raw_bytes: usize = clips.iter().map(|clip| clip.content.len()).sum();
-    let map_started = Instant::now();
-    let items: Vec<ClipboardItem> = clips
+    let image_rows = rows
         .iter()
-        .map(|clip| clip_to_list_item(clip, image_path_map.get(&clip.uuid).map(|s| s.as_str())))
-        .collect();
+        .filter(|row| row.clip.clip_type == "image")
+        .count();
+    let raw_bytes: usize = rows.iter().map(|row| row.clip.content.len()).sum();
+    let map_started = Instant::now();
+    let mut items = clip_list_items(pool, &rows).await?;
+    for item in &mut items {
+        item.search_match = matches.remove(&item.id);
+    }
     let map_ms = map_started.elapsed().as_millis();
     let total_ms = started.elapsed().as_millis();
     log::info!(
-        "[perf][search_clips] sql_ms={} map_ms={} total_ms={} rows={} images={} raw_bytes={} filter_id={:?} offset={} limit={}",
+        "[perf][search_clips] sql_ms={} map_ms={} total_ms={} rows={} images={} raw_bytes={} preview_only={} filter_id={:?} mode={:?} cursor={:?} limit={}",
         sql_ms,
         map_ms,
         total_ms,
-        clips.len(),
+        rows.len(),
         image_rows,
         raw_bytes,
+        preview_only,
         filter_id,
-        offset,
+        mode,
+        page_cursor,
         limit
     );
 
+    Ok(ClipPage { items, next_cursor })
+}
+
+/// Rank clips by embedding similarity to `query`. `search_match.score` is the cosine similarity in thousandths.
+#[tauri::command]
+pub async fn semantic_search(
+    query: String,
+    filter_id: Option<String>,
+    limit: i64,
+    app: AppHandle,
+    db: tauri::State<'_, Arc<Database>>,
+) -> Result<Vec<ClipboardItem>, String> {
+    let pool = &db.pool();
+    let started = Instant::now();
+
+    let settings = app.state::<Arc<SettingsManager>>().get();
+    if !settings.semantic_search_enabled {
+        return Err("Semantic search is disabled in settings".to_string());
+    }
+    let config = AiConfig::for_embeddings(&settings)?;
+    let query = query.trim();
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+    let (folder_id, scope) = match resolve_filter(pool, filter_id.as_deref()).await? {
+        Some(resolved) => resolved,
+        None => return Ok(Vec::new()),
+    };
+
+    let model = settings.ai_embedding_model;
+    let query_vector = ai::embed_texts(&[query.to_string()], &model, &config)
+        .await
+        .map_err(|e| e.to_string())?
+        .pop()
+        .ok_or("No embedding returned for the query")?;
+    let embed_ms = started.elapsed().as_millis();
+
+    let ranked = embeddings::rank(
+        pool,
+        &query_vector,
+        &model,
+        folder_id,
+        &scope,
+        limit.max(0) as usize,
+    )
+    .await?;
+    let uuids: Vec<String> = ranked.iter().map(|(uuid, _)| uuid.clone()).collect();
+    let mut scores: HashMap<String, f32> = ranked.into_iter().collect();
+    let rows = clip_list::fetch_by_uuid(pool, &uuids, true).await?;
+
+    let mut items = clip_list_items(pool, &rows).await?;
+    for item in &mut items {
+        item.search_match = scores.remove(&item.id).map(|score| SearchMatch {
+            score: (score * 1000.0).round() as i64,
+            field: "content".to_string(),
+            ranges: Vec::new(),
+        });
+    }
+
+    log::info!(
+        "[perf][semantic_search] embed_ms={} total_ms={} rows={} filter_id={:?} limit={}",
+        embed_ms,
+        started.elapsed().as_millis(),
+        items.len(),
+        filter_id,
+        limit
+    );
     Ok(items)
 }
 
+#[tauri::command]
+pub async fn get_semantic_search_status(
+    app: AppHandle,
+    db: tauri::State<'_, Arc<Database>>,
+) -> Result<embeddings::SemanticIndexStatus, String> {
+    let settings = app.state::<Arc<SettingsManager>>().get();
+    let mut status = embeddings::status(
+        &db.pool(),
+        &settings.ai_embedding_model,
+        settings.semantic_search_enabled,
+    )
+    .await?;
+    if settings.semantic_search_enabled {
+        status.error = AiConfig::for_embeddings(&settings).err();
+    }
+    Ok(status)
+}
+
+/// Sensitive clips are kept out of AI features; their stored embedding is dropped.
+#[tauri::command]
+pub async fn set_clip_sensitive(
+    id: String,
+    sensitive: bool,
+    app: AppHandle,
+    db: tauri::State<'_, Arc<Database>>,
+) -> Result<(), String> {
+    let pool = &db.pool();
+
+    let result = sqlx::query("UPDATE clips SET is_sensitive = ? WHERE uuid = ?")
+        .bind(sensitive)
+        .bind(&id)
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    if result.rows_affected() == 0 {
+        return Err("Clip not found".to_string());
+    }
+
+    if sensitive {
+        sqlx::query("DELETE FROM clip_embeddings WHERE clip_uuid = ?")
+            .bind(&id)
+            .execute(pool)
+            .await
+            .map_err(|e| e.to_string())?;
+    } else {
+        embeddings::wake();
+    }
+
+    let _ = app.emit("clipboard-change", ());
+    Ok(())
+}
+
+/// Clear the system clipboard `seconds` after pasting this clip; `None` falls back to its folder.
+#[tauri::command]
+pub async fn set_clip_clear_after(
+    id: String,
+    seconds: Option<i64>,
+    app: AppHandle,
+    db: tauri::State<'_, Arc<Database>>,
+) -> Result<(), String> {
+    if seconds.is_some_and(|s| s < 0) {
+        return Err("Seconds can't be negative".to_string());
+    }
+    let result = sqlx::query("UPDATE clips SET clear_after_secs = ? WHERE uuid = ?")
+        .bind(seconds)
+        .bind(&id)
+        .execute(&db.pool())
+        .await
+        .map_err(|e| e.to_string())?;
+    if result.rows_affected() == 0 {
+        return Err("Clip not found".to_string());
+    }
+    let _ = app.emit("clipboard-change", ());
+    Ok(())
+}
+
+/// Default for clips in the folder that don't set their own; `None` turns it off.
+#[tauri::command]
+pub async fn set_folder_clear_after(
+    id: String,
+    seconds: Option<i64>,
+    app: AppHandle,
+    db: tauri::State<'_, Arc<Database>>,
+) -> Result<(), String> {
+    let folder_id: i64 = id.parse().map_err(|_| "Invalid folder ID")?;
+    if seconds.is_some_and(|s| s < 0) {
+        return Err("Seconds can't be negative".to_string());
+    }
+    let result = sqlx::query("UPDATE folders SET clear_after_secs = ? WHERE id = ?")
+        .bind(seconds)
+        .bind(folder_id)
+        .execute(&db.pool())
+        .await
+        .map_err(|e| e.to_string())?;
+    if result.rows_affected() == 0 {
+        return Err("Folder not found".to_string());
+    }
+    let _ = app.emit("clipboard-change", ());
+    Ok(())
+}
+
 #[tauri::command]
 pub async fn get_folders(db: tauri::State<'_, Arc<Database>>) -> Result<Vec<FolderItem>, String> {
-    let pool = &db.pool;
+    let pool = &db.pool();
 
     let folders: Vec<Folder> = sqlx::query_as(r#"SELECT * FROM folders ORDER BY created_at"#)
         .fetch_all(pool)
@@ -902,7 +1817,22 @@ pub async fn get_folders(db: tauri::State<'_, Arc<Database>>) -> Result<Vec<Fold
     .map_err(|e| e.to_string())?;
 
     // Create a map for easier lookup
-    let count_map: HashMap<i64, i64> = counts.into_iter().collect();
+    let mut count_map: HashMap<i64, i64> = counts.into_iter().collect();
+
+    // Smart folders are counted live from their query
+    for folder in &folders {
+        let Some(query) = &folder.query else {
+            continue;
+        };
+        let count = match search_query::parse(query) {
+            Ok(parsed) => count_matching(pool, &parsed).await?,
+            Err(e) => {
+                log::warn!("Smart folder {} has an invalid query: {}", folder.id, e);
+                0
+            }
+        };
+        count_map.insert(folder.id, count);
+    }
 
     let items: Vec<FolderItem> = folders
         .iter()
@@ -913,6 +1843,8 @@ pub async fn get_folders(db: tauri::State<'_, Arc<Database>>) -> Result<Vec<Fold
             color: folder.color.clone(),
             is_system: folder.is_system,
             item_count: *count_map.get(&folder.id).unwrap_or(&0),
+            query: folder.query.clone(),
+            clear_after_secs: folder.clear_after_secs,
         })
         .collect();
 
@@ -945,7 +1877,7 @@ pub fn test_log() -> Result<String, String> {
 pub async fn get_clipboard_history_size(
     db: tauri::State<'_, Arc<Database>>,
 ) -> Result<i64, String> {
-    let pool = &db.pool;
+    let pool = &db.pool();
 
     let count: i64 =
         sqlx::query_scalar::<_, i64>(r#"SELECT COUNT(*) FROM clips WHERE is_deleted = 0"#)
@@ -957,7 +1889,7 @@ pub async fn get_clipboard_history_size(
 
 #[tauri::command]
 pub async fn clear_clipboard_history(db: tauri::State<'_, Arc<Database>>) -> Result<(), String> {
-    let pool = &db.pool;
+    let pool = &db.pool();
 
     sqlx::query(r#"DELETE FROM clips WHERE is_deleted = 1"#)
         .execute(pool)
@@ -969,7 +1901,7 @@ pub async fn clear_clipboard_history(db: tauri::State<'_, Arc<Database>>) -> Res
 
 #[tauri::command]
 pub async fn clear_all_clips(db: tauri::State<'_, Arc<Database>>) -> Result<(), String> {
-    let pool = &db.pool;
+    let pool = &db.pool();
 
     cleanup_all_clip_image_files(pool).await?;
 
@@ -986,7 +1918,7 @@ pub async fn clear_all_clips(db: tauri::State<'_, Arc<Database>>) -> Result<(),
 
 #[tauri::command]
 pub async fn remove_duplicate_clips(db: tauri::State<'_, Arc<Database>>) -> Result<i64, String> {
-    let pool = &db.pool;
+    let pool = &db.pool();
 
     let result = sqlx::query(
         r#"
@@ -1007,38 +1939,58 @@ pub async fn remove_duplicate_clips(db: tauri::State<'_, Arc<Database>>) -> Resu
     Ok(result.rows_affected() as i64)
 }
 
+/// Bind `hotkey` to show and hide the window, keeping the other shortcuts as they are.
 #[tauri::command]
 pub async fn register_global_shortcut(
     hotkey: String,
     window: tauri::WebviewWindow,
 ) -> Result<(), String> {
-    use tauri_plugin_global_shortcut::ShortcutState;
+    Shortcut::from_str(&hotkey).map_err(|e| format!("Invalid hotkey: {:?}", e))?;
 
     let app = window.app_handle();
-    let shortcut = Shortcut::from_str(&hotkey).map_err(|e| format!("Invalid hotkey: {:?}", e))?;
-
-    if let Err(e) = app.global_shortcut().unregister_all() {
-        log::warn!("Failed to unregister existing shortcuts: {:?}", e);
+    let mut settings = app.state::<Arc<SettingsManager>>().get();
+    settings.hotkey = hotkey;
+    // The window hotkey is always the first binding
+    match shortcuts::register_all(app, &settings).into_iter().next() {
+        Some(BindingStatus { error: Some(e), .. }) => Err(e),
+        _ => Ok(()),
     }
+}
 
-    let main_window = app
-        .get_webview_window("main")
-        .ok_or_else(|| "Main window not found".to_string())?;
+/// Stop recording clipboard changes, for `minutes` if given or until `resume_capture`.
+#[tauri::command]
+pub fn pause_capture(minutes: Option<u64>, app: AppHandle) -> CaptureState {
+    capture_pause::pause(&app, minutes);
+    capture_pause::state()
+}
 
-    let win_clone = main_window.clone();
-    if let Err(e) = app
-        .global_shortcut()
-        .on_shortcut(shortcut, move |_app, _shortcut, event| {
-            if event.state() == ShortcutState::Pressed {
-                crate::position_window_at_bottom(&win_clone);
-            }
-        })
-    {
-        return Err(format!("Failed to register hotkey: {:?}", e));
-    }
+#[tauri::command]
+pub fn resume_capture(app: AppHandle) -> CaptureState {
+    capture_pause::resume(&app);
+    capture_pause::state()
+}
 
-    log::info!("Registered global shortcut: {}", hotkey);
-    Ok(())
+#[tauri::command]
+pub fn get_capture_state() -> CaptureState {
+    capture_pause::state()
+}
+
+#[tauri::command]
+pub fn get_shortcut_bindings() -> Vec<BindingStatus> {
+    shortcuts::statuses()
+}
+
+/// Save the action shortcuts and re-register them; each binding's status says if it's active.
+#[tauri::command]
+pub fn set_shortcut_bindings(
+    bindings: Vec<ShortcutBinding>,
+    app: AppHandle,
+) -> Result<Vec<BindingStatus>, String> {
+    let manager = app.state::<Arc<SettingsManager>>();
+    let mut settings = manager.get();
+    settings.shortcuts = bindings;
+    manager.save(settings.clone())?;
+    Ok(shortcuts::register_all(&app, &settings))
 }
 
 #[tauri::command]
@@ -1113,3 +2065,45 @@ pub async fn request_accessibility_permissions() -> Result<(), String> {
         Ok(())
     }
 }
+
+#[tauri::command]
+pub fn get_data_dir_info() -> crate::data_dir::DataDirInfo {
+    crate::data_dir::info()
+}
+
+#[tauri::command]
+pub async fn move_data_dir(
+    target: String,
+    app: AppHandle,
+    db: tauri::State<'_, Arc<Database>>,
+) -> Result<String, String> {
+    let target = std::path::PathBuf::from(target.trim());
+
+    // Block clipboard ingestion so the snapshot and image copy see a consistent state.
+    let _guard = crate::clipboard::CLIPBOARD_SYNC.lock().await;
+    let new_dir = crate::data_dir::move_data_dir(&db.pool(), &target).await?;
+
+    // The DB pool and settings file stay bound to the old location until restart.
+    app.request_restart();
+    Ok(new_dir.to_string_lossy().to_string())
+}
+
+#[tauri::command]
+pub async fn get_storage_usage(
+    largest_limit: Option<i64>,
+    db: tauri::State<'_, Arc<Database>>,
+) -> Result<crate::storage::StorageReport, String> {
+    let started = Instant::now();
+    let report = crate::storage::analyze(
+        &db.pool(),
+        &crate::data_dir::profile_dir(),
+        largest_limit.unwrap_or(20),
+    )
+    .await?;
+    log::info!(
+        "[perf][get_storage_usage] total_ms={} disk_bytes={}",
+        started.elapsed().as_millis(),
+        report.disk.total_bytes
+    );
+    Ok(report)
+}
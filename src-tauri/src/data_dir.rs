@@ -0,0 +1,545 @@
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+pub const DB_FILE_NAME: &str = "paste_paw.db";
+pub const SETTINGS_FILE_NAME: &str = "settings.json";
+pub const IMAGES_DIR_NAME: &str = "images";
+pub const PROFILES_FILE_NAME: &str = "profiles.json";
+pub const PROFILES_DIR_NAME: &str = "profiles";
+
+const APP_DIR_NAME: &str = "PastePaw";
+const PORTABLE_DIR_NAME: &str = "PastePawData";
+const PORTABLE_MARKER_FILE: &str = "portable";
+const REDIRECT_FILE_NAME: &str = "data_location.json";
+const DATA_DIR_ENV: &str = "PASTEPAW_DATA_DIR";
+const DATA_DIR_ARG: &str = "--data-dir";
+const PORTABLE_ARG: &str = "--portable";
+
+/// Where the active data directory came from, in priority order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DataDirSource {
+    CommandLine,
+    Environment,
+    Portable,
+    Relocated,
+    Default,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DataDirInfo {
+    pub data_dir: String,
+    pub source: DataDirSource,
+    pub profile_dir: String,
+    pub db_path: String,
+    pub images_dir: String,
+    pub settings_path: String,
+}
+
+/// Pointer written into the default data directory after the user moved their data.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct DataLocation {
+    data_dir: PathBuf,
+    #[serde(default)]
+    pending_cleanup: Option<PathBuf>,
+}
+
+static RESOLVED: Lazy<(PathBuf, DataDirSource)> = Lazy::new(resolve);
+
+/// Directory of the active profile. The default profile lives directly in the data directory.
+static PROFILE_DIR: Lazy<parking_lot::RwLock<Option<PathBuf>>> =
+    Lazy::new(|| parking_lot::RwLock::new(None));
+
+/// Resolve the data directory once at startup, create it, and finish any move
+/// that was started in a previous session.
+pub fn init() -> PathBuf {
+    let dir = data_dir();
+    if let Err(e) = std::fs::create_dir_all(&dir) {
+        log::error!("DATA_DIR: Failed to create {:?}: {}", dir, e);
+    }
+    finish_pending_cleanup();
+    dir
+}
+
+pub fn data_dir() -> PathBuf {
+    RESOLVED.0.clone()
+}
+
+pub fn source() -> DataDirSource {
+    RESOLVED.1
+}
+
+pub fn profile_dir() -> PathBuf {
+    PROFILE_DIR.read().clone().unwrap_or_else(data_dir)
+}
+
+pub fn set_profile_dir(dir: PathBuf) {
+    *PROFILE_DIR.write() = Some(dir);
+}
+
+pub fn db_path() -> PathBuf {
+    profile_dir().join(DB_FILE_NAME)
+}
+
+pub fn images_dir() -> PathBuf {
+    profile_dir().join(IMAGES_DIR_NAME)
+}
+
+pub fn settings_path() -> PathBuf {
+    profile_dir().join(SETTINGS_FILE_NAME)
+}
+
+pub fn info() -> DataDirInfo {
+    DataDirInfo {
+        data_dir: data_dir().to_string_lossy().to_string(),
+        source: source(),
+        profile_dir: profile_dir().to_string_lossy().to_string(),
+        db_path: db_path().to_string_lossy().to_string(),
+        images_dir: images_dir().to_string_lossy().to_string(),
+        settings_path: settings_path().to_string_lossy().to_string(),
+    }
+}
+
+fn resolve() -> (PathBuf, DataDirSource) {
+    let args: Vec<String> = std::env::args().collect();
+
+    if let Some(dir) = data_dir_from_args(&args) {
+        return (dir, DataDirSource::CommandLine);
+    }
+
+    if let Ok(dir) = std::env::var(DATA_DIR_ENV) {
+        if !dir.trim().is_empty() {
+            return (PathBuf::from(dir.trim()), DataDirSource::Environment);
+        }
+    }
+
+    if let Some(dir) = portable_dir(&args) {
+        return (dir, DataDirSource::Portable);
+    }
+
+    resolve_default(default_data_dir())
+}
+
+/// The default directory, or where `data_location.json` in it points.
+fn resolve_default(default_dir: PathBuf) -> (PathBuf, DataDirSource) {
+    match read_location(&default_dir) {
+        Some(location) => (location.data_dir, DataDirSource::Relocated),
+        None => (default_dir, DataDirSource::Default),
+    }
+}
+
+fn data_dir_from_args(args: &[String]) -> Option<PathBuf> {
+    let mut iter = args.iter().skip(1);
+    while let Some(arg) = iter.next() {
+        if arg == DATA_DIR_ARG {
+            return iter.next().map(PathBuf::from);
+        }
+        if let Some(value) = arg.strip_prefix("--data-dir=") {
+            if !value.is_empty() {
+                return Some(PathBuf::from(value));
+            }
+        }
+    }
+    None
+}
+
+/// Portable mode keeps data next to the executable. It is enabled with `--portable`
+/// or by placing an empty `portable` file beside the executable (or the .app bundle).
+fn portable_dir(args: &[String]) -> Option<PathBuf> {
+    let base = portable_base_dir()?;
+    let requested = args.iter().skip(1).any(|a| a == PORTABLE_ARG);
+    if requested || base.join(PORTABLE_MARKER_FILE).exists() {
+        Some(base.join(PORTABLE_DIR_NAME))
+    } else {
+        None
+    }
+}
+
+fn portable_base_dir() -> Option<PathBuf> {
+    let exe = std::env::current_exe().ok()?;
+    let exe_dir = exe.parent()?.to_path_buf();
+
+    // Writing inside a macOS bundle would break its signature, so use the folder holding the .app.
+    #[cfg(target_os = "macos")]
+    {
+        if let Some(bundle) = exe_dir.ancestors().find(|p| {
+            p.extension()
+                .map(|ext| ext.eq_ignore_ascii_case("app"))
+                .unwrap_or(false)
+        }) {
+            return bundle.parent().map(|p| p.to_path_buf());
+        }
+    }
+
+    Some(exe_dir)
+}
+
+pub fn default_data_dir() -> PathBuf {
+    let current_dir = std::env::current_dir().unwrap_or(PathBuf::from("."));
+    match dirs::data_dir() {
+        Some(path) => path.join(APP_DIR_NAME),
+        None => current_dir.join(APP_DIR_NAME),
+    }
+}
+
+fn read_location(default_dir: &Path) -> Option<DataLocation> {
+    let content = std::fs::read_to_string(default_dir.join(REDIRECT_FILE_NAME)).ok()?;
+    let location: DataLocation = serde_json::from_str(&content).ok()?;
+    if location.data_dir.as_os_str().is_empty() {
+        return None;
+    }
+    Some(location)
+}
+
+fn write_location(default_dir: &Path, location: &DataLocation) -> Result<(), String> {
+    std::fs::create_dir_all(default_dir).map_err(|e| e.to_string())?;
+    let path = default_dir.join(REDIRECT_FILE_NAME);
+    // Nothing left to redirect or clean up: the default location needs no pointer.
+    if location.data_dir == default_dir && location.pending_cleanup.is_none() {
+        return match std::fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.to_string()),
+            _ => Ok(()),
+        };
+    }
+    let json = serde_json::to_string_pretty(location).map_err(|e| e.to_string())?;
+    std::fs::write(path, json).map_err(|e| e.to_string())
+}
+
+/// Remove the data left behind by a move once the new location is in use.
+fn finish_pending_cleanup() {
+    finish_pending_cleanup_in(&default_data_dir(), &data_dir());
+}
+
+fn finish_pending_cleanup_in(default_dir: &Path, active_dir: &Path) {
+    let Some(mut location) = read_location(default_dir) else {
+        return;
+    };
+    let Some(old_dir) = location.pending_cleanup.take() else {
+        return;
+    };
+
+    if old_dir != active_dir && active_dir.join(DB_FILE_NAME).exists() {
+        log::info!(
+            "DATA_DIR: Removing data left at previous location {:?}",
+            old_dir
+        );
+        remove_data_files(&old_dir);
+    }
+
+    if let Err(e) = write_location(default_dir, &location) {
+        log::warn!("DATA_DIR: Failed to update data location file: {}", e);
+    }
+}
+
+fn db_file_names() -> [String; 3] {
+    [
+        DB_FILE_NAME.to_string(),
+        format!("{}-wal", DB_FILE_NAME),
+        format!("{}-shm", DB_FILE_NAME),
+    ]
+}
+
+/// What the app keeps in a data directory. Anything else there belongs to the user.
+fn owned_entries() -> Vec<String> {
+    db_file_names()
+        .into_iter()
+        .chain(
+            [
+                SETTINGS_FILE_NAME,
+                PROFILES_FILE_NAME,
+                IMAGES_DIR_NAME,
+                PROFILES_DIR_NAME,
+            ]
+            .map(String::from),
+        )
+        .collect()
+}
+
+fn remove_data_files(dir: &Path) {
+    for name in owned_entries() {
+        let path = dir.join(name);
+        let result = if path.is_dir() {
+            std::fs::remove_dir_all(&path)
+        } else {
+            std::fs::remove_file(&path)
+        };
+        if let Err(e) = result {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                log::warn!("DATA_DIR: Failed to delete {:?}: {}", path, e);
+            }
+        }
+    }
+}
+
+/// Copy the app's own files and folders from `from` into `to`.
+fn copy_owned_entries(from: &Path, to: &Path, skip: &dyn Fn(&Path) -> bool) -> Result<(), String> {
+    std::fs::create_dir_all(to).map_err(|e| e.to_string())?;
+    for name in owned_entries() {
+        let source = from.join(&name);
+        if skip(&source) {
+            continue;
+        }
+        if source.is_dir() {
+            copy_dir_recursive(&source, &to.join(&name), skip)?;
+        } else if source.is_file() {
+            std::fs::copy(&source, to.join(&name)).map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(())
+}
+
+fn copy_dir_recursive(from: &Path, to: &Path, skip: &dyn Fn(&Path) -> bool) -> Result<(), String> {
+    std::fs::create_dir_all(to).map_err(|e| e.to_string())?;
+    for entry in std::fs::read_dir(from).map_err(|e| e.to_string())? {
+        let entry = entry.map_err(|e| e.to_string())?;
+        let source = entry.path();
+        if skip(&source) {
+            continue;
+        }
+        let target = to.join(entry.file_name());
+        if entry.file_type().map_err(|e| e.to_string())?.is_dir() {
+            copy_dir_recursive(&source, &target, skip)?;
+        } else {
+            std::fs::copy(&source, &target).map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(())
+}
+
+/// Image paths are stored absolute; point the ones under `old_root` at `new_root`.
+async fn rewrite_image_paths(
+    db_path: &Path,
+    old_root: &Path,
+    new_root: &Path,
+) -> Result<(), String> {
+    let old_prefix = old_root.to_string_lossy().to_string();
+    let new_prefix = new_root.to_string_lossy().to_string();
+    let pool = crate::database::Database::connect(&db_path.to_string_lossy())
+        .await
+        .map_err(|e| e.to_string())?
+        .into_pool();
+    let result = sqlx::query(
+        r#"
+        UPDATE clip_images
+        SET file_path = ? || substr(file_path, ?)
+        WHERE substr(file_path, 1, ?) = ?
+        "#,
+    )
+    .bind(&new_prefix)
+    .bind(old_prefix.chars().count() as i64 + 1)
+    .bind(old_prefix.chars().count() as i64)
+    .bind(&old_prefix)
+    .execute(&pool)
+    .await;
+    pool.close().await;
+    result.map(|_| ()).map_err(|e| e.to_string())
+}
+
+/// Copy the databases, images, settings and profiles into `target` and point future launches at it.
+///
+/// The live database is snapshotted with `VACUUM INTO`, so it stays consistent while
+/// the app keeps running. Callers must hold `CLIPBOARD_SYNC` so no clip is written mid-copy,
+/// and restart the app afterwards; the old files are removed on the next launch.
+pub async fn move_data_dir(pool: &sqlx::SqlitePool, target: &Path) -> Result<PathBuf, String> {
+    match source() {
+        DataDirSource::CommandLine | DataDirSource::Environment | DataDirSource::Portable => {
+            return Err(
+                "Data directory is set by portable mode, --data-dir or PASTEPAW_DATA_DIR and cannot be moved"
+                    .to_string(),
+            );
+        }
+        DataDirSource::Relocated | DataDirSource::Default => {}
+    }
+    move_data_in(
+        pool,
+        &default_data_dir(),
+        &data_dir(),
+        &profile_dir(),
+        target,
+    )
+    .await
+}
+
+async fn move_data_in(
+    pool: &sqlx::SqlitePool,
+    default_dir: &Path,
+    current: &Path,
+    live_dir: &Path,
+    target: &Path,
+) -> Result<PathBuf, String> {
+    if target.as_os_str().is_empty() || !target.is_absolute() {
+        return Err("Target directory must be an absolute path".to_string());
+    }
+    if target == current {
+        return Err("Target directory is the current data directory".to_string());
+    }
+    if target.starts_with(current) {
+        return Err("Target directory cannot be inside the current data directory".to_string());
+    }
+    if target.join(DB_FILE_NAME).exists() || target.join(PROFILES_FILE_NAME).exists() {
+        return Err(format!(
+            "PastePaw data already exists at {}",
+            target.to_string_lossy()
+        ));
+    }
+
+    let target_live_dir = target.join(
+        live_dir
+            .strip_prefix(current)
+            .map_err(|_| "Active profile is outside the data directory".to_string())?,
+    );
+    let live_files: Vec<PathBuf> = db_file_names()
+        .iter()
+        .map(|name| live_dir.join(name))
+        .collect();
+
+    // 1. Everything except the live database, which may be mid-write
+    let skip = |path: &Path| live_files.iter().any(|f| f == path);
+    if let Err(e) = copy_owned_entries(current, target, &skip) {
+        remove_data_files(target);
+        return Err(format!("Failed to copy data: {}", e));
+    }
+
+    // 2. Consistent snapshot of the live database
+    let snapshot = async {
+        std::fs::create_dir_all(&target_live_dir).map_err(|e| e.to_string())?;
+        sqlx::query("VACUUM INTO ?")
+            .bind(
+                target_live_dir
+                    .join(DB_FILE_NAME)
+                    .to_string_lossy()
+                    .to_string(),
+            )
+            .execute(pool)
+            .await
+            .map_err(|e| e.to_string())
+    };
+    if let Err(e) = snapshot.await {
+        remove_data_files(target);
+        return Err(format!("Failed to copy database: {}", e));
+    }
+
+    // 3. Rewrite image paths in every profile's database
+    let mut databases = vec![target.join(DB_FILE_NAME)];
+    if let Ok(entries) = std::fs::read_dir(target.join(PROFILES_DIR_NAME)) {
+        databases.extend(entries.flatten().map(|e| e.path().join(DB_FILE_NAME)));
+    }
+    for db in databases.iter().filter(|p| p.exists()) {
+        if let Err(e) = rewrite_image_paths(db, current, target).await {
+            remove_data_files(target);
+            return Err(format!("Failed to update image paths: {}", e));
+        }
+    }
+
+    // 4. Point the next launch at the new directory
+    write_location(
+        default_dir,
+        &DataLocation {
+            data_dir: target.to_path_buf(),
+            pending_cleanup: Some(current.to_path_buf()),
+        },
+    )?;
+
+    log::info!("DATA_DIR: Moved data from {:?} to {:?}", current, target);
+    Ok(target.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_data_dir_from_args() {
+        let args = |v: &[&str]| v.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            data_dir_from_args(&args(&["app", "--data-dir", "/tmp/pp"])),
+            Some(PathBuf::from("/tmp/pp"))
+        );
+        assert_eq!(
+            data_dir_from_args(&args(&["app", "--flag1", "--data-dir=/tmp/pp"])),
+            Some(PathBuf::from("/tmp/pp"))
+        );
+        assert_eq!(data_dir_from_args(&args(&["app", "--flag1"])), None);
+    }
+
+    #[tokio::test]
+    async fn test_move_data_dir() {
+        let root = std::env::temp_dir().join(format!("pastepaw_move_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let current = root.join("old");
+        let target = root.join("new");
+        std::fs::create_dir_all(current.join(IMAGES_DIR_NAME)).unwrap();
+        std::fs::write(current.join(IMAGES_DIR_NAME).join("a.png"), b"png").unwrap();
+        std::fs::write(current.join(SETTINGS_FILE_NAME), "{}").unwrap();
+        std::fs::write(current.join(DB_FILE_NAME), b"live").unwrap();
+        std::fs::write(current.join("notes.txt"), "not ours").unwrap();
+
+        let db = crate::database::TestDb::new("move_data_dir").await;
+        let image_path = current.join(IMAGES_DIR_NAME).join("a.png");
+        sqlx::query("INSERT INTO clips (uuid, clip_type, content, text_preview, content_hash) VALUES ('img', 'image', '', '', 'h')")
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        sqlx::query(
+            "INSERT INTO clip_images (clip_uuid, full_content, file_path) VALUES ('img', '', ?)",
+        )
+        .bind(image_path.to_string_lossy().to_string())
+        .execute(&db.pool)
+        .await
+        .unwrap();
+
+        // The default directory is the current one, so the pointer is written there
+        move_data_in(&db.pool, &current, &current, &current, &target)
+            .await
+            .unwrap();
+        db.close().await;
+
+        assert!(target.join(IMAGES_DIR_NAME).join("a.png").exists());
+        assert!(target.join(SETTINGS_FILE_NAME).exists());
+        assert!(!target.join("notes.txt").exists());
+        assert!(!target.join(REDIRECT_FILE_NAME).exists());
+
+        let moved =
+            crate::database::Database::connect(&target.join(DB_FILE_NAME).to_string_lossy())
+                .await
+                .unwrap()
+                .into_pool();
+        let path: String =
+            sqlx::query_scalar("SELECT file_path FROM clip_images WHERE clip_uuid = 'img'")
+                .fetch_one(&moved)
+                .await
+                .unwrap();
+        moved.close().await;
+        assert_eq!(
+            PathBuf::from(path),
+            target.join(IMAGES_DIR_NAME).join("a.png")
+        );
+
+        assert_eq!(
+            resolve_default(current.clone()),
+            (target.clone(), DataDirSource::Relocated)
+        );
+
+        // Still running from the old directory: nothing is removed yet
+        finish_pending_cleanup_in(&current, &current);
+        assert!(current.join(DB_FILE_NAME).exists());
+
+        // Only once a launch runs from the new directory
+        write_location(
+            &current,
+            &DataLocation {
+                data_dir: target.clone(),
+                pending_cleanup: Some(current.clone()),
+            },
+        )
+        .unwrap();
+        finish_pending_cleanup_in(&current, &target);
+        assert!(!current.join(DB_FILE_NAME).exists());
+        assert!(!current.join(IMAGES_DIR_NAME).exists());
+        assert!(current.join("notes.txt").exists());
+        assert!(read_location(&current).unwrap().pending_cleanup.is_none());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -1,22 +1,46 @@
 use sqlx::SqlitePool;
 
-#[derive(Clone)]
 pub struct Database {
-    pub pool: SqlitePool,
+    // Swappable so switching profiles can retarget the managed state in place.
+    pool: parking_lot::RwLock<SqlitePool>,
 }
 
 impl Database {
     pub async fn new(db_path: &str) -> Self {
+        Self::connect(db_path).await.unwrap()
+    }
+
+    pub async fn connect(db_path: &str) -> Result<Self, sqlx::Error> {
         let options = sqlx::sqlite::SqliteConnectOptions::new()
             .filename(db_path)
-            .create_if_missing(true);
+            .create_if_missing(true)
+            // Used by `regex:` filters in searches and smart folders.
+            .with_regexp();
+
+        let pool = SqlitePool::connect_with(options).await?;
+
+        Ok(Self {
+            pool: parking_lot::RwLock::new(pool),
+        })
+    }
 
-        let pool = SqlitePool::connect_with(options).await.unwrap();
+    /// Current connection pool. Cheap to clone; don't hold it across a profile switch.
+    pub fn pool(&self) -> SqlitePool {
+        self.pool.read().clone()
+    }
+
+    pub fn into_pool(self) -> SqlitePool {
+        self.pool.into_inner()
+    }
 
-        Self { pool }
+    /// Swap in a new pool and hand back the previous one. Clones of it keep working until dropped.
+    pub fn replace_pool(&self, pool: SqlitePool) -> SqlitePool {
+        std::mem::replace(&mut *self.pool.write(), pool)
     }
 
     pub async fn migrate(&self) -> Result<(), sqlx::Error> {
+        let pool = &self.pool();
+
         sqlx::query(
             r#"
             CREATE TABLE IF NOT EXISTS folders (
@@ -29,7 +53,7 @@ impl Database {
             )
         "#,
         )
-        .execute(&self.pool)
+        .execute(pool)
         .await?;
 
         sqlx::query(
@@ -52,7 +76,7 @@ impl Database {
             )
         "#,
         )
-        .execute(&self.pool)
+        .execute(pool)
         .await?;
 
         sqlx::query(
@@ -60,7 +84,7 @@ impl Database {
             CREATE INDEX IF NOT EXISTS idx_clips_hash ON clips(content_hash);
         "#,
         )
-        .execute(&self.pool)
+        .execute(pool)
         .await?;
 
         sqlx::query(
@@ -68,7 +92,7 @@ impl Database {
             CREATE INDEX IF NOT EXISTS idx_clips_folder ON clips(folder_id);
         "#,
         )
-        .execute(&self.pool)
+        .execute(pool)
         .await?;
 
         sqlx::query(
@@ -76,7 +100,24 @@ impl Database {
             CREATE INDEX IF NOT EXISTS idx_clips_created ON clips(created_at);
         "#,
         )
-        .execute(&self.pool)
+        .execute(pool)
+        .await?;
+
+        // Keyset pagination walks these backwards; `id` breaks ties within the same second.
+        sqlx::query(
+            r#"
+            CREATE INDEX IF NOT EXISTS idx_clips_created_id ON clips(created_at, id);
+        "#,
+        )
+        .execute(pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE INDEX IF NOT EXISTS idx_clips_folder_created ON clips(folder_id, created_at, id);
+        "#,
+        )
+        .execute(pool)
         .await?;
 
         sqlx::query(
@@ -87,7 +128,7 @@ impl Database {
             )
         "#,
         )
-        .execute(&self.pool)
+        .execute(pool)
         .await?;
 
         sqlx::query(
@@ -98,12 +139,12 @@ impl Database {
             )
         "#,
         )
-        .execute(&self.pool)
+        .execute(pool)
         .await?;
 
         // Backward-compatible schema updates.
         add_column_if_missing(
-            &self.pool,
+            pool,
             "ALTER TABLE clips ADD COLUMN is_thumbnail INTEGER NOT NULL DEFAULT 0",
         )
         .await?;
@@ -122,7 +163,7 @@ impl Database {
             )
             "#,
         )
-        .execute(&self.pool)
+        .execute(pool)
         .await?;
 
         sqlx::query(
@@ -130,13 +171,145 @@ impl Database {
             CREATE INDEX IF NOT EXISTS idx_clip_images_storage ON clip_images(storage_kind);
             "#,
         )
-        .execute(&self.pool)
+        .execute(pool)
+        .await?;
+
+        add_column_if_missing(
+            pool,
+            "ALTER TABLE clips ADD COLUMN is_sensitive INTEGER NOT NULL DEFAULT 0",
+        )
+        .await?;
+
+        add_column_if_missing(
+            pool,
+            "ALTER TABLE clips ADD COLUMN is_template INTEGER NOT NULL DEFAULT 0",
+        )
+        .await?;
+
+        // Smart folders store a search query instead of holding clips.
+        add_column_if_missing(pool, "ALTER TABLE folders ADD COLUMN query TEXT").await?;
+
+        // Seconds after pasting to clear the system clipboard; a clip's value overrides its folder's.
+        add_column_if_missing(
+            pool,
+            "ALTER TABLE clips ADD COLUMN clear_after_secs INTEGER",
+        )
+        .await?;
+        add_column_if_missing(
+            pool,
+            "ALTER TABLE folders ADD COLUMN clear_after_secs INTEGER",
+        )
+        .await?;
+
+        // Conditions and actions are JSON, see `rules.rs`.
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS routing_rules (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL,
+                enabled INTEGER NOT NULL DEFAULT 1,
+                position INTEGER NOT NULL DEFAULT 0,
+                stop_processing INTEGER NOT NULL DEFAULT 0,
+                conditions TEXT NOT NULL DEFAULT '{}',
+                actions TEXT NOT NULL DEFAULT '[]',
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        // Little-endian f32 vectors; rows for another model or stale content get re-embedded.
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS clip_embeddings (
+                clip_uuid TEXT PRIMARY KEY,
+                model TEXT NOT NULL,
+                dimensions INTEGER NOT NULL,
+                vector BLOB NOT NULL,
+                content_hash TEXT NOT NULL,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                FOREIGN KEY (clip_uuid) REFERENCES clips(uuid) ON DELETE CASCADE
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        // Every AI result per clip; also looked up by prompt, model and content as a cache.
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS ai_results (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                clip_uuid TEXT NOT NULL,
+                action_id TEXT NOT NULL,
+                model TEXT NOT NULL,
+                prompt_hash TEXT NOT NULL,
+                content_hash TEXT NOT NULL,
+                result TEXT NOT NULL,
+                result_clip_uuid TEXT,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                FOREIGN KEY (clip_uuid) REFERENCES clips(uuid) ON DELETE CASCADE
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE INDEX IF NOT EXISTS idx_ai_results_clip ON ai_results(clip_uuid, created_at);
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE INDEX IF NOT EXISTS idx_ai_results_lookup ON ai_results(prompt_hash, model, content_hash);
+            "#,
+        )
+        .execute(pool)
         .await?;
 
         Ok(())
     }
 }
 
+/// A migrated database in a temp file for tests, deleted again by `close`.
+#[cfg(test)]
+pub struct TestDb {
+    pub pool: SqlitePool,
+    path: std::path::PathBuf,
+}
+
+#[cfg(test)]
+impl TestDb {
+    /// `name` keeps tests running in parallel on separate files.
+    pub async fn new(name: &str) -> Self {
+        let path =
+            std::env::temp_dir().join(format!("pastepaw_{}_{}.db", name, std::process::id()));
+        Self::remove_files(&path);
+        let db = Database::connect(&path.to_string_lossy()).await.unwrap();
+        db.migrate().await.unwrap();
+        Self {
+            pool: db.into_pool(),
+            path,
+        }
+    }
+
+    pub async fn close(self) {
+        self.pool.close().await;
+        Self::remove_files(&self.path);
+    }
+
+    fn remove_files(path: &std::path::Path) {
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.to_string_lossy(), suffix));
+        }
+    }
+}
+
 async fn add_column_if_missing(pool: &SqlitePool, sql: &str) -> Result<(), sqlx::Error> {
     match sqlx::query(sql).execute(pool).await {
         Ok(_) => Ok(()),
@@ -0,0 +1,305 @@
+//! Clip embeddings for `semantic_search`, filled in by a background backfill worker.
+
+use crate::ai::{self, AiConfig};
+use crate::database::Database;
+use crate::search_query::{self, SearchQuery};
+use crate::settings_manager::SettingsManager;
+use futures::TryStreamExt;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use sqlx::SqlitePool;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::sync::Notify;
+
+const BATCH_SIZE: i64 = 32;
+/// Inputs are cut to stay well inside the model's context window.
+const MAX_INPUT_CHARS: usize = 8000;
+const BATCH_PAUSE: Duration = Duration::from_millis(500);
+const IDLE_INTERVAL: Duration = Duration::from_secs(300);
+const ERROR_BACKOFF: Duration = Duration::from_secs(600);
+
+/// Clips worth embedding: text-like, not deleted and not marked sensitive.
+const EMBEDDABLE_SQL: &str =
+    "c.is_deleted = 0 AND c.is_sensitive = 0 AND c.clip_type IN ('text', 'url', 'html', 'rtf')";
+
+static WAKE: Lazy<Notify> = Lazy::new(Notify::new);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SemanticIndexStatus {
+    pub enabled: bool,
+    pub model: String,
+    pub indexed: i64,
+    pub pending: i64,
+    /// Why clips can't be embedded with the current settings, if they can't.
+    pub error: Option<String>,
+}
+
+/// Nudge the backfill worker, e.g. after a new clip or a settings change.
+pub fn wake() {
+    WAKE.notify_one();
+}
+
+pub fn encode_vector(vector: &[f32]) -> Vec<u8> {
+    vector.iter().flat_map(|x| x.to_le_bytes()).collect()
+}
+
+pub fn decode_vector(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|c| f32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+        .collect()
+}
+
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() || a.is_empty() {
+        return 0.0;
+    }
+    let (mut dot, mut norm_a, mut norm_b) = (0.0f32, 0.0f32, 0.0f32);
+    for (x, y) in a.iter().zip(b) {
+        dot += x * y;
+        norm_a += x * x;
+        norm_b += y * y;
+    }
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a.sqrt() * norm_b.sqrt())
+    }
+}
+
+fn embedding_input(clip_type: &str, content: &[u8], preview: Option<&str>) -> String {
+    let text = if clip_type == "text" || clip_type == "url" {
+        String::from_utf8_lossy(content).into_owned()
+    } else {
+        preview.unwrap_or_default().to_string()
+    };
+    text.trim().chars().take(MAX_INPUT_CHARS).collect()
+}
+
+type PendingRow = (String, String, String, Vec<u8>, Option<String>);
+
+/// Embed the next batch of clips without a current vector for `model`. Returns how many were stored.
+pub async fn backfill_batch(
+    pool: &SqlitePool,
+    config: &AiConfig,
+    model: &str,
+) -> Result<usize, String> {
+    let rows: Vec<PendingRow> = sqlx::query_as(&format!(
+        r#"
+        SELECT c.uuid, c.content_hash, c.clip_type, c.content, c.text_preview
+        FROM clips c
+        LEFT JOIN clip_embeddings e ON e.clip_uuid = c.uuid
+        WHERE {}
+          AND (e.clip_uuid IS NULL OR e.model != ? OR e.content_hash != c.content_hash)
+        ORDER BY c.created_at DESC
+        LIMIT ?
+        "#,
+        EMBEDDABLE_SQL
+    ))
+    .bind(model)
+    .bind(BATCH_SIZE)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    if rows.is_empty() {
+        return Ok(0);
+    }
+
+    let inputs: Vec<String> = rows
+        .iter()
+        .map(|(_, _, clip_type, content, preview)| {
+            embedding_input(clip_type, content, preview.as_deref())
+        })
+        .collect();
+    // Blank clips get an empty vector so they aren't picked up again.
+    let texts: Vec<String> = inputs.iter().filter(|t| !t.is_empty()).cloned().collect();
+    let mut vectors = if texts.is_empty() {
+        Vec::new()
+    } else {
+        ai::embed_texts(&texts, model, config)
+            .await
+            .map_err(|e| e.to_string())?
+    }
+    .into_iter();
+
+    let mut stored = 0;
+    for ((uuid, content_hash, _, _, _), input) in rows.iter().zip(&inputs) {
+        let vector = if input.is_empty() {
+            Vec::new()
+        } else {
+            vectors.next().unwrap_or_default()
+        };
+
+        // The clip may have changed, been marked sensitive or been deleted while we waited.
+        let result = sqlx::query(
+            r#"
+            INSERT INTO clip_embeddings (clip_uuid, model, dimensions, vector, content_hash, created_at)
+            SELECT ?, ?, ?, ?, ?, CURRENT_TIMESTAMP
+            WHERE EXISTS (SELECT 1 FROM clips WHERE uuid = ? AND content_hash = ? AND is_sensitive = 0)
+            ON CONFLICT(clip_uuid) DO UPDATE SET
+                model = excluded.model,
+                dimensions = excluded.dimensions,
+                vector = excluded.vector,
+                content_hash = excluded.content_hash,
+                created_at = excluded.created_at
+            "#,
+        )
+        .bind(uuid)
+        .bind(model)
+        .bind(vector.len() as i64)
+        .bind(encode_vector(&vector))
+        .bind(content_hash)
+        .bind(uuid)
+        .bind(content_hash)
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+        stored += result.rows_affected() as usize;
+    }
+
+    Ok(stored)
+}
+
+/// Embed batches until nothing is left or semantic search is turned off.
+async fn run_backfill(app: &AppHandle) -> Result<(), String> {
+    loop {
+        let settings = app.state::<Arc<SettingsManager>>().get();
+        if !settings.semantic_search_enabled {
+            return Ok(());
+        }
+        // Reported by `get_semantic_search_status`; retried when settings change
+        let Ok(config) = AiConfig::for_embeddings(&settings) else {
+            return Ok(());
+        };
+        // Fetched per batch so a profile switch takes effect right away.
+        let pool = app.state::<Arc<Database>>().pool();
+
+        let stored = backfill_batch(&pool, &config, &settings.ai_embedding_model).await?;
+        if stored == 0 {
+            return Ok(());
+        }
+        log::info!("EMBEDDINGS: Embedded {} clips", stored);
+        tokio::time::sleep(BATCH_PAUSE).await;
+    }
+}
+
+pub fn start_backfill(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match run_backfill(&app).await {
+                Ok(()) => {
+                    tokio::select! {
+                        _ = WAKE.notified() => {}
+                        _ = tokio::time::sleep(IDLE_INTERVAL) => {}
+                    }
+                }
+                Err(e) => {
+                    // Don't retry on every copy while the endpoint is failing.
+                    log::warn!("EMBEDDINGS: Backfill failed: {}", e);
+                    tokio::time::sleep(ERROR_BACKOFF).await;
+                }
+            }
+        }
+    });
+}
+
+/// Clip uuids most similar to `query`, best first, with their cosine similarity.
+pub async fn rank(
+    pool: &SqlitePool,
+    query: &[f32],
+    model: &str,
+    folder_id: Option<i64>,
+    scope: &SearchQuery,
+    limit: usize,
+) -> Result<Vec<(String, f32)>, String> {
+    let (scope_sql, scope_args) = scope.to_sql();
+    let sql = format!(
+        r#"
+        SELECT e.clip_uuid, e.vector
+        FROM clip_embeddings e
+        JOIN clips c ON c.uuid = e.clip_uuid
+        WHERE {} AND e.model = ? AND e.dimensions = ?{}{}
+        "#,
+        EMBEDDABLE_SQL,
+        if folder_id.is_some() {
+            " AND c.folder_id = ?"
+        } else {
+            ""
+        },
+        if scope.is_empty() {
+            String::new()
+        } else {
+            format!(
+                " AND c.uuid IN (SELECT uuid FROM clips WHERE {})",
+                scope_sql
+            )
+        }
+    );
+    let mut rows_query = sqlx::query_as::<_, (String, Vec<u8>)>(&sql)
+        .bind(model)
+        .bind(query.len() as i64);
+    if let Some(id) = folder_id {
+        rows_query = rows_query.bind(id);
+    }
+    let rows_query = search_query::bind_args(rows_query, &scope_args);
+
+    // Streamed, keeping only the current top `limit`, so large histories don't sit in memory.
+    let mut top: Vec<(String, f32)> = Vec::with_capacity(limit + 1);
+    let mut rows = rows_query.fetch(pool);
+    while let Some((uuid, bytes)) = rows.try_next().await.map_err(|e| e.to_string())? {
+        let score = cosine_similarity(query, &decode_vector(&bytes));
+        if top.len() == limit && top.last().is_some_and(|(_, worst)| score <= *worst) {
+            continue;
+        }
+        let position = top.partition_point(|(_, s)| *s >= score);
+        top.insert(position, (uuid, score));
+        top.truncate(limit);
+    }
+
+    Ok(top)
+}
+
+pub async fn status(
+    pool: &SqlitePool,
+    model: &str,
+    enabled: bool,
+) -> Result<SemanticIndexStatus, String> {
+    let (total, indexed): (i64, i64) = sqlx::query_as(&format!(
+        r#"
+        SELECT COUNT(*),
+            COALESCE(SUM(CASE WHEN e.model = ? AND e.content_hash = c.content_hash THEN 1 ELSE 0 END), 0)
+        FROM clips c
+        LEFT JOIN clip_embeddings e ON e.clip_uuid = c.uuid
+        WHERE {}
+        "#,
+        EMBEDDABLE_SQL
+    ))
+    .bind(model)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(SemanticIndexStatus {
+        enabled,
+        model: model.to_string(),
+        indexed,
+        pending: total - indexed,
+        error: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vector_roundtrip_and_similarity() {
+        let vector = vec![0.25, -1.5, 3.0];
+        assert_eq!(decode_vector(&encode_vector(&vector)), vector);
+        assert!((cosine_similarity(&[1.0, 0.0], &[2.0, 0.0]) - 1.0).abs() < 1e-6);
+        assert_eq!(cosine_similarity(&[1.0, 0.0], &[0.0, 1.0]), 0.0);
+        assert_eq!(cosine_similarity(&[1.0], &[1.0, 2.0]), 0.0);
+    }
+}
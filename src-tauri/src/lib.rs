@@ -1,42 +1,67 @@
 #![allow(non_snake_case)]
 #![allow(unexpected_cfgs)] // objc crate macros check cfg(feature = "cargo-clippy") internally
 #![allow(deprecated)] // cocoa crate deprecated its API in favor of objc2; suppress until migration
-use std::fs;
 use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
 use std::sync::Arc;
 use tauri::{
     image::Image,
-    menu::{Menu, MenuItem, PredefinedMenuItem},
+    menu::{Menu, MenuItem, PredefinedMenuItem, Submenu},
     tray::TrayIconBuilder,
     Manager,
 };
 use tauri_plugin_aptabase::EventTracker;
 #[cfg(not(feature = "app-store"))]
 use tauri_plugin_autostart::MacosLauncher;
-use tauri_plugin_global_shortcut::{GlobalShortcutExt, ShortcutState};
 
 static IS_ANIMATING: AtomicBool = AtomicBool::new(false);
 static LAST_SHOW_TIME: AtomicI64 = AtomicI64::new(0);
 
 mod ai;
+mod ai_actions;
+mod ai_history;
+mod ai_provider;
+mod auto_clear;
+mod capture_pause;
+mod clip_list;
 mod clipboard;
+mod clipboard_snapshot;
 mod commands;
 mod constants;
+mod data_dir;
 mod database;
+mod embeddings;
+mod merge;
 mod models;
+mod paste_queue;
+mod paste_strategy;
+mod profile_commands;
+mod profiles;
+mod quick_paste;
+mod rule_commands;
+mod rules;
+mod search_match;
+mod search_query;
 mod settings_commands;
 mod settings_manager;
+mod shortcuts;
 #[cfg(target_os = "macos")]
 mod source_app_macos;
+mod storage;
+mod templates;
+mod transforms;
+mod type_out;
 
 use database::Database;
 use models::get_runtime;
+use profiles::ProfileManager;
 use settings_manager::SettingsManager;
 
 pub fn run_app() {
-    let data_dir = get_data_dir();
-    fs::create_dir_all(&data_dir).ok();
-    let db_path = data_dir.join("paste_paw.db");
+    data_dir::init();
+    let profile_manager = ProfileManager::load();
+    data_dir::set_profile_dir(ProfileManager::profile_dir(&profile_manager.get().active));
+    std::fs::create_dir_all(data_dir::profile_dir()).ok();
+    let db_path = data_dir::db_path();
     let db_path_str = db_path.to_str().unwrap_or("paste_paw.db").to_string();
 
     let rt = get_runtime().expect("Failed to get global tokio runtime");
@@ -113,6 +138,7 @@ pub fn run_app() {
         .plugin(tauri_plugin_process::init())
         .plugin(tauri_plugin_aptabase::Builder::new("A-US-2920723583").build())
         .manage(db_arc.clone())
+        .manage(Arc::new(profile_manager))
         .on_window_event(|window, event| {
             #[cfg(target_os = "macos")]
             {
@@ -229,28 +255,25 @@ pub fn run_app() {
             app.manage(Arc::new(settings_manager));
 
             let _ = app.track_event("startup", None);
-            log::info!("Database path: {}", db_path_str);
+            log::info!(
+                "Database path: {} (data dir source: {:?})",
+                db_path_str,
+                data_dir::source()
+            );
             if let Ok(log_dir) = app.path().app_log_dir() {
                 log::info!("Log directory: {:?}", log_dir);
             }
             let handle = app.handle().clone();
             let db_for_clipboard = db_arc.clone();
 
-            let version = env!("CARGO_PKG_VERSION");
-            let title = format!("v{}", version);
-            let title_i = MenuItem::with_id(app, "title", &title, false, None::<&str>)?;
-            let quit_i = MenuItem::with_id(app, "quit", "Quit PastePaw", true, None::<&str>)?;
-            let show_i = MenuItem::with_id(app, "show", "Show", true, None::<&str>)?;
-            let separator_i = PredefinedMenuItem::separator(app)?;
-            let menu = Menu::with_items(app, &[&title_i, &show_i, &separator_i, &quit_i])?;
+            let menu = build_tray_menu(&handle, &capture_pause::state())?;
 
-            let icon_data = include_bytes!("../icons/tray.png");
-            let icon = Image::from_bytes(icon_data).map_err(|e| {
+            let icon = Image::from_bytes(TRAY_ICON).map_err(|e| {
                 log::info!("Failed to load icon: {:?}", e);
                 e
             })?;
 
-            let tray_builder = TrayIconBuilder::new()
+            let tray_builder = TrayIconBuilder::with_id(TRAY_ID)
                 .icon(icon)
                 .menu(&menu);
 
@@ -260,12 +283,19 @@ pub fn run_app() {
             let _tray = tray_builder
                 .tooltip("PastePaw")
                 .on_menu_event(move |app, event| {
-                    if event.id.as_ref() == "quit" {
-                        app.exit(0);
-                    } else if event.id.as_ref() == "show" {
-                        if let Some(win) = app.get_webview_window("main") {
-                            position_window_at_bottom(&win);
+                    match event.id.as_ref() {
+                        "quit" => app.exit(0),
+                        "show" => {
+                            if let Some(win) = app.get_webview_window("main") {
+                                position_window_at_bottom(&win);
+                            }
                         }
+                        "pause_5" => capture_pause::pause(app, Some(5)),
+                        "pause_15" => capture_pause::pause(app, Some(15)),
+                        "pause_60" => capture_pause::pause(app, Some(60)),
+                        "pause" => capture_pause::pause(app, None),
+                        "resume" => capture_pause::resume(app),
+                        _ => {}
                     }
                 })
                 .on_tray_icon_event(|tray, event| {
@@ -278,6 +308,7 @@ pub fn run_app() {
                 .build(app)?;
 
             let app_handle = handle.clone();
+            #[cfg(any(target_os = "windows", target_os = "macos"))]
             let win = app_handle.get_webview_window("main").unwrap();
 
             #[cfg(target_os = "windows")]
@@ -316,29 +347,17 @@ pub fn run_app() {
                 crate::set_window_level(&win, 25);
             }
 
-            // Load saved hotkey from database or use default
+            // Window hotkey and action shortcuts from settings
             let manager = app_handle.state::<Arc<SettingsManager>>();
-            let saved_hotkey = manager.get().hotkey;
-
-            log::info!("Registering hotkey: {}", saved_hotkey);
-
-            // Parse the hotkey string into a Shortcut
-            use std::str::FromStr;
-            use tauri_plugin_global_shortcut::Shortcut;
-
-            if let Ok(shortcut) = Shortcut::from_str(&saved_hotkey) {
-                let win_clone = win.clone();
-                let _ = app_handle.global_shortcut().on_shortcut(shortcut, move |_app, _shortcut, event| {
-                    if event.state() == ShortcutState::Pressed {
-                        if win_clone.is_visible().unwrap_or(false) && win_clone.is_focused().unwrap_or(false) {
-                            crate::animate_window_hide(&win_clone, None);
-                        } else {
-                            position_window_at_bottom(&win_clone);
-                        }
-                    }
-                });
-            } else {
-                log::error!("Failed to parse hotkey: {}", saved_hotkey);
+            shortcuts::register_from_settings(&app_handle);
+
+            let queue_hotkey = manager.get().paste_queue_hotkey;
+            if let Err(e) = paste_queue::register_shortcut(&app_handle, &queue_hotkey) {
+                log::error!("{}", e);
+            }
+            let quick_paste_modifiers = manager.get().quick_paste_modifiers;
+            if let Err(e) = quick_paste::register_shortcuts(&app_handle, &quick_paste_modifiers) {
+                log::error!("{}", e);
             }
 
             #[cfg(target_os = "macos")]
@@ -348,10 +367,12 @@ pub fn run_app() {
             let db_for_clip = db_for_clipboard.clone();
             clipboard::init(&handle_for_clip, db_for_clip);
 
+            embeddings::start_backfill(app_handle.clone());
+
             // Start background image migration
             let db_for_migration = db_for_clipboard.clone();
             tauri::async_runtime::spawn(async move {
-                if let Err(e) = commands::migrate_images_to_files(&db_for_migration.pool).await {
+                if let Err(e) = commands::migrate_images_to_files(&db_for_migration.pool()).await {
                     log::error!("Background image migration failed: {}", e);
                 }
             });
@@ -364,12 +385,38 @@ pub fn run_app() {
             commands::get_clip,
             commands::get_clip_detail,
             commands::paste_clip,
+            commands::paste_clip_transformed,
+            commands::preview_transform,
+            commands::get_paste_transforms,
+            commands::validate_template,
+            commands::type_out_clip,
+            commands::merge_clips,
+            commands::enqueue_clips,
+            commands::get_paste_queue,
+            commands::reorder_paste_queue,
+            commands::remove_from_paste_queue,
+            commands::clear_paste_queue,
+            commands::register_paste_queue_shortcut,
+            commands::register_quick_paste_shortcuts,
+            commands::set_clip_template,
             commands::delete_clip,
             commands::move_to_folder,
             commands::create_folder,
             commands::rename_folder,
+            commands::update_smart_folder,
+            rule_commands::get_routing_rules,
+            rule_commands::save_routing_rule,
+            rule_commands::delete_routing_rule,
+            rule_commands::set_routing_rule_enabled,
+            rule_commands::reorder_routing_rules,
+            rule_commands::test_routing_rule,
             commands::delete_folder,
             commands::search_clips,
+            commands::semantic_search,
+            commands::get_semantic_search_status,
+            commands::set_clip_sensitive,
+            commands::set_clip_clear_after,
+            commands::set_folder_clear_after,
             commands::get_folders,
             // Replaced by settings_commands
             settings_commands::get_settings,
@@ -380,6 +427,11 @@ pub fn run_app() {
             commands::clear_all_clips,
             commands::remove_duplicate_clips,
             commands::register_global_shortcut,
+            commands::pause_capture,
+            commands::resume_capture,
+            commands::get_capture_state,
+            commands::get_shortcut_bindings,
+            commands::set_shortcut_bindings,
             commands::show_window,
             settings_commands::add_ignored_app,
             settings_commands::remove_ignored_app,
@@ -388,14 +440,111 @@ pub fn run_app() {
             commands::get_layout_config,
             commands::test_log,
             commands::ai_process_clip,
+            commands::get_ai_history,
+            commands::save_ai_result_as_clip,
+            commands::cancel_ai_request,
             commands::focus_window,
             commands::check_accessibility_permissions,
-            commands::request_accessibility_permissions
+            commands::request_accessibility_permissions,
+            commands::get_data_dir_info,
+            commands::move_data_dir,
+            commands::get_storage_usage,
+            profile_commands::get_profiles,
+            profile_commands::create_profile,
+            profile_commands::rename_profile,
+            profile_commands::delete_profile,
+            profile_commands::switch_profile,
+            profile_commands::set_profile_auto_switch,
+            profile_commands::set_profile_auto_switch_apps
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
 
+const TRAY_ID: &str = "main";
+const TRAY_ICON: &[u8] = include_bytes!("../icons/tray.png");
+
+/// Tray menu, with pause options or a resume item depending on `capture`.
+fn build_tray_menu(
+    app: &tauri::AppHandle,
+    capture: &capture_pause::CaptureState,
+) -> tauri::Result<Menu<tauri::Wry>> {
+    let version = env!("CARGO_PKG_VERSION");
+    let title = format!("v{}", version);
+    let title_i = MenuItem::with_id(app, "title", &title, false, None::<&str>)?;
+    let quit_i = MenuItem::with_id(app, "quit", "Quit PastePaw", true, None::<&str>)?;
+    let show_i = MenuItem::with_id(app, "show", "Show", true, None::<&str>)?;
+    let separator_i = PredefinedMenuItem::separator(app)?;
+
+    let capture_i: Box<dyn tauri::menu::IsMenuItem<tauri::Wry>> = if capture.paused {
+        let label = match capture
+            .resume_at
+            .as_deref()
+            .and_then(|at| chrono::DateTime::parse_from_rfc3339(at).ok())
+        {
+            Some(at) => format!(
+                "Resume Capture (paused until {})",
+                at.with_timezone(&chrono::Local).format("%H:%M")
+            ),
+            None => "Resume Capture".to_string(),
+        };
+        Box::new(MenuItem::with_id(app, "resume", label, true, None::<&str>)?)
+    } else {
+        Box::new(Submenu::with_items(
+            app,
+            "Pause Capture",
+            true,
+            &[
+                &MenuItem::with_id(app, "pause_5", "For 5 Minutes", true, None::<&str>)?,
+                &MenuItem::with_id(app, "pause_15", "For 15 Minutes", true, None::<&str>)?,
+                &MenuItem::with_id(app, "pause_60", "For 1 Hour", true, None::<&str>)?,
+                &MenuItem::with_id(app, "pause", "Until Resumed", true, None::<&str>)?,
+            ],
+        )?)
+    };
+
+    Menu::with_items(
+        app,
+        &[&title_i, &show_i, capture_i.as_ref(), &separator_i, &quit_i],
+    )
+}
+
+/// The tray icon, faded out while capture is paused.
+fn tray_icon(paused: bool) -> Option<Image<'static>> {
+    let mut rgba = image::load_from_memory(TRAY_ICON).ok()?.to_rgba8();
+    if paused {
+        for pixel in rgba.pixels_mut() {
+            pixel[3] = (pixel[3] as u16 * 2 / 5) as u8;
+        }
+    }
+    let (width, height) = rgba.dimensions();
+    Some(Image::new_owned(rgba.into_raw(), width, height))
+}
+
+/// Show the capture state in the tray: icon, tooltip and pause/resume menu.
+pub fn update_tray(app: &tauri::AppHandle, capture: &capture_pause::CaptureState) {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+    if let Err(e) = tray.set_icon(tray_icon(capture.paused)) {
+        log::warn!("Failed to update tray icon: {:?}", e);
+    }
+    #[cfg(target_os = "macos")]
+    let _ = tray.set_icon_as_template(true);
+    let tooltip = if capture.paused {
+        "PastePaw (capture paused)"
+    } else {
+        "PastePaw"
+    };
+    let _ = tray.set_tooltip(Some(tooltip));
+    match build_tray_menu(app, capture) {
+        Ok(menu) => {
+            let _ = tray.set_menu(Some(menu));
+        }
+        Err(e) => log::warn!("Failed to rebuild tray menu: {:?}", e),
+    }
+}
+
 pub fn position_window_at_bottom(window: &tauri::WebviewWindow) {
     animate_window_show(window);
 }
@@ -406,6 +555,9 @@ fn ease_linear(x: f64) -> f64 {
 }
 
 pub fn animate_window_show(window: &tauri::WebviewWindow) {
+    #[cfg(target_os = "windows")]
+    crate::clipboard::remember_target_app();
+
     // Atomically check if false and set to true. If already true, return.
     if IS_ANIMATING
         .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
@@ -547,16 +699,17 @@ pub fn animate_window_show(window: &tauri::WebviewWindow) {
     }
 }
 
+/// Returns `false`, without calling `on_done`, when another animation is still running.
 pub fn animate_window_hide(
     window: &tauri::WebviewWindow,
     on_done: Option<Box<dyn FnOnce() + Send>>,
-) {
+) -> bool {
     // Atomically check if false and set to true. If already true, return.
     if IS_ANIMATING
         .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
         .is_err()
     {
-        return;
+        return false;
     }
 
     let window = window.clone();
@@ -733,14 +886,8 @@ pub fn animate_window_hide(
             IS_ANIMATING.store(false, Ordering::SeqCst);
         });
     }
-}
 
-fn get_data_dir() -> std::path::PathBuf {
-    let current_dir = std::env::current_dir().unwrap_or(std::path::PathBuf::from("."));
-    match dirs::data_dir() {
-        Some(path) => path.join("PastePaw"),
-        None => current_dir.join("PastePaw"),
-    }
+    true
 }
 
 pub fn get_monitor_at_cursor(window: &tauri::WebviewWindow) -> Option<tauri::Monitor> {
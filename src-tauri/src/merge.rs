@@ -0,0 +1,298 @@
+//! Combining several clips into one: text joined with a separator, images stitched into one PNG.
+
+use image::{ImageEncoder, RgbaImage};
+use serde::Deserialize;
+use sqlx::SqlitePool;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum Separator {
+    Newline,
+    Comma,
+    Tab,
+    Custom(String),
+}
+
+impl Separator {
+    fn as_str(&self) -> &str {
+        match self {
+            Separator::Newline => "\n",
+            Separator::Comma => ",",
+            Separator::Tab => "\t",
+            Separator::Custom(s) => s,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    #[default]
+    Vertical,
+    Horizontal,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MergeOutput {
+    Paste,
+    NewClip,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MergeOptions {
+    pub separator: Separator,
+    /// Applied to each item before joining, see `join_text`.
+    #[serde(default)]
+    pub wrapper: Option<String>,
+    /// How images are stitched.
+    #[serde(default)]
+    pub direction: Direction,
+    pub output: MergeOutput,
+}
+
+/// Join `items`, each first wrapped by `wrapper` if given: `{{item}}` is the item text and
+/// `{{index}}` its 1-based position, e.g. `'{{item}}'` for a SQL `IN (...)` list.
+pub fn join_text(items: &[String], separator: &Separator, wrapper: Option<&str>) -> String {
+    let wrapper = wrapper.filter(|w| !w.is_empty());
+    items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| match wrapper {
+            Some(w) => w
+                .replace("{{index}}", &(i + 1).to_string())
+                .replace("{{item}}", item),
+            None => item.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join(separator.as_str())
+}
+
+/// Largest stitched image allowed, per side and in total: about 256 MB as RGBA.
+const MAX_STITCH_SIDE: u32 = 32_768;
+const MAX_STITCH_PIXELS: u64 = 64_000_000;
+
+/// Size of the canvas for images of `sizes` laid out in `direction`, within the limits above.
+fn canvas_size(sizes: &[(u32, u32)], direction: Direction) -> Result<(u32, u32), String> {
+    let too_large = || "Merged image would be too large".to_string();
+    let sum = |side: fn(&(u32, u32)) -> u32| {
+        sizes
+            .iter()
+            .map(side)
+            .try_fold(0u32, |total, n| total.checked_add(n))
+            .ok_or_else(too_large)
+    };
+    let max = |side: fn(&(u32, u32)) -> u32| sizes.iter().map(side).max().unwrap_or(0);
+    let (width, height) = match direction {
+        Direction::Vertical => (max(|s| s.0), sum(|s| s.1)?),
+        Direction::Horizontal => (sum(|s| s.0)?, max(|s| s.1)),
+    };
+    if width == 0 || height == 0 {
+        return Err("Nothing to stitch".to_string());
+    }
+    if width > MAX_STITCH_SIDE
+        || height > MAX_STITCH_SIDE
+        || width as u64 * height as u64 > MAX_STITCH_PIXELS
+    {
+        return Err(too_large());
+    }
+    Ok((width, height))
+}
+
+/// Stitch PNGs side by side or top to bottom; gaps from differing sizes stay transparent.
+pub fn stitch_images(
+    pngs: &[Vec<u8>],
+    direction: Direction,
+) -> Result<(Vec<u8>, u32, u32), String> {
+    // Check the size from the headers before decoding anything
+    let sizes = pngs
+        .iter()
+        .map(|bytes| {
+            image::io::Reader::new(std::io::Cursor::new(bytes))
+                .with_guessed_format()
+                .map_err(|e| e.to_string())?
+                .into_dimensions()
+                .map_err(|e| e.to_string())
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let (width, height) = canvas_size(&sizes, direction)?;
+
+    let images = pngs
+        .iter()
+        .map(|bytes| image::load_from_memory(bytes).map_err(|e| e.to_string()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut canvas = RgbaImage::new(width, height);
+    let mut offset = 0i64;
+    for image in &images {
+        let (x, y) = match direction {
+            Direction::Vertical => (0, offset),
+            Direction::Horizontal => (offset, 0),
+        };
+        image::imageops::replace(&mut canvas, &image.to_rgba8(), x, y);
+        offset += match direction {
+            Direction::Vertical => image.height(),
+            Direction::Horizontal => image.width(),
+        } as i64;
+    }
+
+    let mut png = Vec::new();
+    image::codecs::png::PngEncoder::new(&mut png)
+        .write_image(&canvas, width, height, image::ColorType::Rgba8)
+        .map_err(|e| e.to_string())?;
+    Ok((png, width, height))
+}
+
+/// Store merged content as a clip, or bump the existing clip with the same content.
+/// Image clips keep their PNG in the images directory, like captured ones.
+pub async fn save_clip(
+    pool: &SqlitePool,
+    clip_type: &str,
+    content: &[u8],
+    png: Option<(&[u8], u32, u32)>,
+) -> Result<String, String> {
+    let hash = crate::clipboard::calculate_hash(png.map_or(content, |(bytes, _, _)| bytes));
+
+    let existing: Option<String> =
+        sqlx::query_scalar(r#"SELECT uuid FROM clips WHERE content_hash = ?"#)
+            .bind(&hash)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| e.to_string())?;
+    if let Some(uuid) = existing {
+        sqlx::query(
+            r#"UPDATE clips SET created_at = CURRENT_TIMESTAMP, is_deleted = 0 WHERE uuid = ?"#,
+        )
+        .bind(&uuid)
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+        return Ok(uuid);
+    }
+
+    let uuid = uuid::Uuid::new_v4().to_string();
+    let (preview, metadata) = match png {
+        Some((bytes, width, height)) => (
+            "[Image]".to_string(),
+            Some(
+                serde_json::json!({
+                    "width": width,
+                    "height": height,
+                    "format": "png",
+                    "size_bytes": bytes.len()
+                })
+                .to_string(),
+            ),
+        ),
+        None => (
+            String::from_utf8_lossy(content).chars().take(200).collect(),
+            None,
+        ),
+    };
+
+    // Write the image file first so a failure leaves no half-stored clip behind
+    let file_path = match png {
+        Some((bytes, _, _)) => Some(crate::clipboard::persist_full_image_file(&uuid, bytes)?),
+        None => None,
+    };
+
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    sqlx::query(
+        r#"
+        INSERT INTO clips (uuid, clip_type, content, text_preview, content_hash, folder_id, is_deleted, is_thumbnail, source_app, source_icon, metadata, created_at, last_accessed)
+        VALUES (?, ?, ?, ?, ?, NULL, 0, 0, NULL, NULL, ?, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+        "#,
+    )
+    .bind(&uuid)
+    .bind(clip_type)
+    .bind(content)
+    .bind(&preview)
+    .bind(&hash)
+    .bind(&metadata)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    if let (Some(file_path), Some((bytes, _, _))) = (&file_path, png) {
+        sqlx::query(
+            r#"
+            INSERT OR REPLACE INTO clip_images (clip_uuid, full_content, file_path, file_size, storage_kind, mime_type, created_at)
+            VALUES (?, x'', ?, ?, 'file', 'image/png', CURRENT_TIMESTAMP)
+            "#,
+        )
+        .bind(&uuid)
+        .bind(file_path)
+        .bind(bytes.len() as i64)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+    }
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    Ok(uuid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::GenericImageView;
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        image::codecs::png::PngEncoder::new(&mut bytes)
+            .write_image(
+                &RgbaImage::from_pixel(width, height, image::Rgba([255, 0, 0, 255])),
+                width,
+                height,
+                image::ColorType::Rgba8,
+            )
+            .unwrap();
+        bytes
+    }
+
+    #[test]
+    fn test_join_text_with_wrapper() {
+        let items = vec!["a".to_string(), "b".to_string()];
+        assert_eq!(join_text(&items, &Separator::Newline, None), "a\nb");
+        assert_eq!(
+            join_text(&items, &Separator::Comma, Some("'{{item}}'")),
+            "'a','b'"
+        );
+        assert_eq!(
+            join_text(
+                &items,
+                &Separator::Custom(" | ".to_string()),
+                Some("{{index}}. {{item}}")
+            ),
+            "1. a | 2. b"
+        );
+    }
+
+    #[test]
+    fn test_stitch_images() {
+        let pngs = vec![png(4, 2), png(3, 5)];
+        let (bytes, width, height) = stitch_images(&pngs, Direction::Vertical).unwrap();
+        assert_eq!((width, height), (4, 7));
+        let stitched = image::load_from_memory(&bytes).unwrap();
+        assert_eq!(stitched.dimensions(), (4, 7));
+        // Right of the narrower second image is left transparent
+        assert_eq!(stitched.get_pixel(3, 6)[3], 0);
+
+        let (_, width, height) = stitch_images(&pngs, Direction::Horizontal).unwrap();
+        assert_eq!((width, height), (7, 5));
+    }
+
+    #[test]
+    fn test_canvas_size_limits() {
+        assert_eq!(
+            canvas_size(&[(10, 20), (30, 5)], Direction::Vertical),
+            Ok((30, 25))
+        );
+        assert!(canvas_size(&[], Direction::Vertical).is_err());
+        // Would overflow u32
+        assert!(canvas_size(&[(1, u32::MAX), (1, 1)], Direction::Vertical).is_err());
+        assert!(canvas_size(&[(20_000, 10); 2], Direction::Horizontal).is_err());
+        assert!(canvas_size(&[(10_000, 4_000); 2], Direction::Vertical).is_err());
+        assert!(canvas_size(&[(8_000, 4_000); 2], Direction::Vertical).is_ok());
+    }
+}
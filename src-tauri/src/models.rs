@@ -13,7 +13,19 @@ pub struct AppSettings {
     pub max_items: i64,
     pub auto_delete_days: i64,
     pub hotkey: String,
+    /// Pastes the next clip of the paste queue; empty disables it.
+    pub paste_queue_hotkey: String,
+    /// Modifiers that, with 1-9, paste the Nth most recent clip; empty disables them.
+    pub quick_paste_modifiers: String,
+    /// Folder quick paste counts clips in; `None` for the whole history.
+    pub quick_paste_folder_id: Option<i64>,
     pub auto_paste: bool,
+    /// Snapshot the clipboard before auto-pasting and put it back afterwards.
+    pub restore_clipboard_after_paste: bool,
+    /// Pause between characters when typing a clip out instead of pasting.
+    pub type_out_delay_ms: u64,
+    /// Longest text, in characters, that type-out will send.
+    pub type_out_max_chars: u64,
     pub ignore_ghost_clips: bool,
     pub startup_with_windows: bool,
 
@@ -22,19 +34,118 @@ pub struct AppSettings {
     pub ai_api_key: String,
     pub ai_model: String,
     pub ai_base_url: String,
-    pub ai_prompt_summarize: String,
-    pub ai_prompt_translate: String,
-    pub ai_prompt_explain_code: String,
-    pub ai_prompt_fix_grammar: String,
-    pub ai_title_summarize: String,
-    pub ai_title_translate: String,
-    pub ai_title_explain_code: String,
-    pub ai_title_fix_grammar: String,
+    /// Offered in the clip menu, in this order, and runnable from rules and shortcuts.
+    pub ai_actions: Vec<AiAction>,
+    /// Embed clips in the background and allow `semantic_search`.
+    pub semantic_search_enabled: bool,
+    pub ai_embedding_model: String,
+    /// OpenAI-compatible server for embeddings. Empty uses `ai_base_url` and `ai_api_key`,
+    /// which only works when `ai_provider` is OpenAI-compatible.
+    pub ai_embedding_base_url: String,
+    /// Key for `ai_embedding_base_url`; may be empty for a local server.
+    pub ai_embedding_api_key: String,
+
+    /// How to paste into specific apps; the first match wins.
+    pub paste_profiles: Vec<AppPasteProfile>,
+
+    /// Global shortcuts besides `hotkey`, each bound to an action.
+    pub shortcuts: Vec<ShortcutBinding>,
 
     // Privacy
     pub ignored_apps: HashSet<String>,
 }
 
+/// Keystroke sent to paste. `Default` is Shift+Insert on Windows and Cmd+V on macOS.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PasteChord {
+    #[default]
+    Default,
+    CtrlV,
+    CtrlShiftV,
+    ShiftInsert,
+    CmdV,
+    CmdShiftV,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppPasteProfile {
+    /// Matched case-insensitively against the target app's name, executable, path or bundle id.
+    pub app: String,
+    pub chord: PasteChord,
+    /// Extra wait before the paste keystroke, for apps slow to take focus.
+    pub delay_ms: u64,
+    /// Strip HTML markup and refuse image clips.
+    pub plain_text: bool,
+    /// Put the previous clipboard back after auto-pasting, even if the global setting is off.
+    pub restore_clipboard: bool,
+}
+
+/// Where an AI action's result goes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AiOutputMode {
+    /// Stored in the clip's metadata under `AiAction::metadata_key`.
+    #[default]
+    Metadata,
+    /// Saved as a new text clip.
+    NewClip,
+    /// Written to the system clipboard.
+    ReplaceClipboard,
+}
+
+/// A user-defined AI action.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AiAction {
+    /// Stable key that rules and shortcuts refer to.
+    pub id: String,
+    pub title: String,
+    pub system_prompt: String,
+    /// Used instead of `ai_model` when set.
+    pub model: Option<String>,
+    pub temperature: f64,
+    /// Clip types it's offered for; empty for all text types.
+    pub clip_types: Vec<String>,
+    pub output: AiOutputMode,
+}
+
+impl Default for AiAction {
+    fn default() -> Self {
+        Self {
+            id: String::new(),
+            title: String::new(),
+            system_prompt: String::new(),
+            model: None,
+            temperature: 0.7,
+            clip_types: Vec::new(),
+            output: AiOutputMode::Metadata,
+        }
+    }
+}
+
+/// What a global shortcut does.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum ShortcutAction {
+    ToggleWindow,
+    PasteLast,
+    /// Stop or resume recording clipboard changes.
+    PauseCapture,
+    /// Show the window with this folder selected.
+    OpenFolder(i64),
+    /// Run the AI action with this id on the most recent clip.
+    RunAiAction(String),
+    ClearClipboard,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShortcutBinding {
+    pub hotkey: String,
+    pub action: ShortcutAction,
+}
+
 impl Default for AppSettings {
     fn default() -> Self {
         Self {
@@ -49,7 +160,18 @@ impl Default for AppSettings {
                 "Ctrl+Shift+V"
             }
             .to_string(),
+            paste_queue_hotkey: if cfg!(target_os = "macos") {
+                "Cmd+Alt+Shift+V"
+            } else {
+                "Ctrl+Alt+Shift+V"
+            }
+            .to_string(),
+            quick_paste_modifiers: String::new(),
+            quick_paste_folder_id: None,
             auto_paste: false,
+            restore_clipboard_after_paste: false,
+            type_out_delay_ms: 10,
+            type_out_max_chars: 5000,
             ignore_ghost_clips: false,
             startup_with_windows: false,
 
@@ -57,17 +179,14 @@ impl Default for AppSettings {
             ai_api_key: "".to_string(),
             ai_model: "gpt-3.5-turbo".to_string(),
             ai_base_url: "https://api.openai.com/v1".to_string(),
+            ai_actions: crate::ai_actions::defaults(),
+            semantic_search_enabled: false,
+            ai_embedding_model: "text-embedding-3-small".to_string(),
+            ai_embedding_base_url: String::new(),
+            ai_embedding_api_key: String::new(),
 
-            ai_prompt_summarize: "Summarize this content concisely.".to_string(),
-            ai_prompt_translate: "Translate this to English (or user language).".to_string(),
-            ai_prompt_explain_code: "Explain this code snippet.".to_string(),
-            ai_prompt_fix_grammar: "Fix grammar and spelling.".to_string(),
-
-            ai_title_summarize: "Summarize".to_string(),
-            ai_title_translate: "Translate".to_string(),
-            ai_title_explain_code: "Explain Code".to_string(),
-            ai_title_fix_grammar: "Fix Grammar".to_string(),
-
+            paste_profiles: Vec::new(),
+            shortcuts: Vec::new(),
             ignored_apps: HashSet::new(),
         }
     }
@@ -84,6 +203,12 @@ pub struct Clip {
     pub folder_id: Option<i64>,
     pub is_deleted: bool,
     pub is_thumbnail: bool,
+    /// Excluded from AI features such as embeddings.
+    pub is_sensitive: bool,
+    /// Expanded with `templates::expand` when pasted.
+    pub is_template: bool,
+    /// Clear the system clipboard this long after pasting, see `auto_clear`.
+    pub clear_after_secs: Option<i64>,
     pub source_app: Option<String>,
     pub source_icon: Option<String>,
     pub metadata: Option<String>,
@@ -99,6 +224,10 @@ pub struct Folder {
     pub color: Option<String>,
     pub is_system: bool,
     pub created_at: chrono::DateTime<chrono::Utc>,
+    /// Saved search query for smart folders; `None` for regular folders.
+    pub query: Option<String>,
+    /// Default `clear_after_secs` for the folder's clips.
+    pub clear_after_secs: Option<i64>,
 }
 
 static RUNTIME: OnceLock<tokio::runtime::Runtime> = OnceLock::new();
@@ -129,6 +258,27 @@ pub struct ClipboardItem {
     pub source_app: Option<String>,
     pub source_icon: Option<String>,
     pub metadata: Option<String>,
+    #[serde(default)]
+    pub is_sensitive: bool,
+    #[serde(default)]
+    pub is_template: bool,
+    #[serde(default)]
+    pub clear_after_secs: Option<i64>,
+    /// Full length in characters when `content` is a cut-down list preview.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_length: Option<i64>,
+    /// Set by `search_clips`: relevance and highlight ranges.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub search_match: Option<SearchMatch>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchMatch {
+    pub score: i64,
+    /// Which item field the ranges index into: "content", or "preview" for images.
+    pub field: String,
+    /// Half-open `[start, end)` ranges in UTF-16 code units, as JavaScript strings index.
+    pub ranges: Vec<[usize; 2]>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -139,4 +289,7 @@ pub struct FolderItem {
     pub color: Option<String>,
     pub is_system: bool,
     pub item_count: i64,
+    /// Set for smart folders, whose clips are whatever currently matches it.
+    pub query: Option<String>,
+    pub clear_after_secs: Option<i64>,
 }
@@ -0,0 +1,208 @@
+//! Paste queue: clips queued in order and pasted one per press of the queue hotkey,
+//! without opening the window. Useful for filling in forms field by field.
+
+use crate::database::Database;
+use crate::models::Clip;
+use once_cell::sync::Lazy;
+use std::collections::{HashMap, VecDeque};
+use std::str::FromStr;
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+
+/// Clip uuids, next to paste first.
+static QUEUE: Lazy<parking_lot::Mutex<VecDeque<String>>> =
+    Lazy::new(|| parking_lot::Mutex::new(VecDeque::new()));
+
+/// The hotkey string currently bound to `paste_next`, so it can be swapped out.
+static HOTKEY: Lazy<parking_lot::Mutex<Option<String>>> =
+    Lazy::new(|| parking_lot::Mutex::new(None));
+
+/// Time for the user to let go of the hotkey's modifiers before the paste keystroke.
+const MODIFIER_RELEASE_DELAY_MS: u64 = 150;
+
+pub fn list() -> Vec<String> {
+    QUEUE.lock().iter().cloned().collect()
+}
+
+fn changed(app: &AppHandle, queue: &VecDeque<String>) {
+    let ids: Vec<&String> = queue.iter().collect();
+    let _ = app.emit("paste-queue-changed", ids);
+}
+
+/// Append clips that aren't queued yet, keeping their order.
+pub fn enqueue(app: &AppHandle, ids: Vec<String>) {
+    let mut queue = QUEUE.lock();
+    append_new(&mut queue, ids);
+    changed(app, &queue);
+}
+
+fn append_new(queue: &mut VecDeque<String>, ids: Vec<String>) {
+    for id in ids {
+        if !queue.contains(&id) {
+            queue.push_back(id);
+        }
+    }
+}
+
+/// Put the queue in the order of `ids`, which must hold exactly the queued clips.
+pub fn reorder(app: &AppHandle, ids: Vec<String>) -> Result<(), String> {
+    let mut queue = QUEUE.lock();
+    check_reorder(&queue, &ids)?;
+    *queue = ids.into();
+    changed(app, &queue);
+    Ok(())
+}
+
+fn check_reorder(queue: &VecDeque<String>, ids: &[String]) -> Result<(), String> {
+    let mut current: Vec<&String> = queue.iter().collect();
+    let mut requested: Vec<&String> = ids.iter().collect();
+    current.sort();
+    requested.sort();
+    if current != requested {
+        return Err("Queue changed, reload it and try again".to_string());
+    }
+    Ok(())
+}
+
+/// Put back a clip that failed to paste, so the next press tries it again.
+fn requeue(app: &AppHandle, id: String) {
+    let mut queue = QUEUE.lock();
+    if !queue.contains(&id) {
+        queue.push_front(id);
+    }
+    changed(app, &queue);
+}
+
+pub fn remove(app: &AppHandle, id: &str) {
+    let mut queue = QUEUE.lock();
+    queue.retain(|queued| queued != id);
+    changed(app, &queue);
+}
+
+pub fn clear(app: &AppHandle) {
+    let mut queue = QUEUE.lock();
+    if !queue.is_empty() {
+        queue.clear();
+        changed(app, &queue);
+    }
+}
+
+/// Bind `hotkey` to `paste_next`, replacing the previous queue hotkey. Empty disables it.
+pub fn register_shortcut(app: &AppHandle, hotkey: &str) -> Result<(), String> {
+    let shortcut = if hotkey.is_empty() {
+        None
+    } else {
+        Some(Shortcut::from_str(hotkey).map_err(|e| format!("Invalid hotkey: {:?}", e))?)
+    };
+
+    let mut current = HOTKEY.lock();
+    if let Some(old) = current.take().and_then(|old| Shortcut::from_str(&old).ok()) {
+        let _ = app.global_shortcut().unregister(old);
+    }
+    let Some(shortcut) = shortcut else {
+        return Ok(());
+    };
+
+    // Fire on release so the hotkey's own keys are up before the paste keystroke is sent
+    app.global_shortcut()
+        .on_shortcut(shortcut, |app, _shortcut, event| {
+            if event.state() == ShortcutState::Released {
+                let app = app.clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = paste_next(&app).await {
+                        log::error!("CLIPBOARD: Paste queue failed: {}", e);
+                    }
+                });
+            }
+        })
+        .map_err(|e| format!("Failed to register hotkey: {:?}", e))?;
+    *current = Some(hotkey.to_string());
+    log::info!("Registered paste queue shortcut: {}", hotkey);
+    Ok(())
+}
+
+/// Paste the next queued clip into the focused app. Clips deleted since queuing are skipped;
+/// a clip that fails to paste stays at the front of the queue.
+pub async fn paste_next(app: &AppHandle) -> Result<(), String> {
+    let pool = app.state::<Arc<Database>>().pool();
+
+    let (id, mut clip) = loop {
+        let Some(id) = QUEUE.lock().pop_front() else {
+            return Ok(());
+        };
+        let clip: Option<Clip> =
+            match sqlx::query_as(r#"SELECT * FROM clips WHERE uuid = ? AND is_deleted = 0"#)
+                .bind(&id)
+                .fetch_optional(&pool)
+                .await
+            {
+                Ok(clip) => clip,
+                Err(e) => {
+                    requeue(app, id);
+                    return Err(e.to_string());
+                }
+            };
+        if let Some(clip) = clip {
+            break (id, clip);
+        }
+    };
+
+    let remaining = {
+        let queue = QUEUE.lock();
+        changed(app, &queue);
+        queue.len()
+    };
+
+    let mut plan = crate::paste_strategy::plan(app, true).await;
+    let final_res = crate::commands::write_clip_for_paste(
+        app,
+        &pool,
+        &mut clip,
+        &mut plan,
+        &HashMap::new(),
+        false,
+    )
+    .await
+    .map(|_| ());
+
+    if final_res.is_err() {
+        requeue(app, id);
+        return final_res;
+    }
+    tokio::time::sleep(std::time::Duration::from_millis(MODIFIER_RELEASE_DELAY_MS)).await;
+    let send_app = app.clone();
+    let _ =
+        tauri::async_runtime::spawn_blocking(move || crate::paste_strategy::send(&send_app, plan))
+            .await;
+    if remaining == 0 {
+        let _ = app.emit("paste-queue-empty", ());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    #[test]
+    fn test_append_new_skips_queued() {
+        let mut queue = VecDeque::new();
+        append_new(&mut queue, ids(&["a", "b"]));
+        append_new(&mut queue, ids(&["b", "c", "a", "d"]));
+        assert_eq!(queue, ids(&["a", "b", "c", "d"]));
+    }
+
+    #[test]
+    fn test_check_reorder() {
+        let queue: VecDeque<String> = ids(&["a", "b", "c"]).into();
+        assert!(check_reorder(&queue, &ids(&["c", "a", "b"])).is_ok());
+        assert!(check_reorder(&queue, &ids(&["a", "b"])).is_err());
+        assert!(check_reorder(&queue, &ids(&["a", "b", "c", "d"])).is_err());
+        assert!(check_reorder(&queue, &ids(&["a", "a", "b"])).is_err());
+    }
+}
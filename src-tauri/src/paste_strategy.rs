@@ -0,0 +1,223 @@
+//! Per-app paste behaviour: which keystroke pastes, how long to wait first, plain text only, and
+//! whether the previous clipboard is put back afterwards.
+
+use crate::clipboard_snapshot::ClipboardSnapshot;
+use crate::models::AppPasteProfile;
+use crate::settings_manager::SettingsManager;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_clipboard_x::{start_listening, stop_listening};
+
+/// Time for the target app to read the clipboard before the previous contents go back.
+const RESTORE_DELAY_MS: u64 = 500;
+
+/// How long after restoring that clipboard changes are still not recorded, covering the
+/// listener's debounce.
+const RESTORE_SUPPRESS_MS: u64 = 1000;
+
+/// First profile whose `app` equals one of `names`, ignoring case.
+pub fn matching_profile<'a>(
+    profiles: &'a [AppPasteProfile],
+    names: &[String],
+) -> Option<&'a AppPasteProfile> {
+    profiles.iter().find(|profile| {
+        let app = profile.app.trim();
+        !app.is_empty() && names.iter().any(|name| name.eq_ignore_ascii_case(app))
+    })
+}
+
+/// Refuse clips a plain-text-only app can't take.
+pub fn check_clip_type(profile: &AppPasteProfile, clip_type: &str) -> Result<(), String> {
+    if profile.plain_text && clip_type == "image" {
+        return Err(format!("{} is set to plain text only", profile.app));
+    }
+    Ok(())
+}
+
+/// The text to paste for a clip, without HTML markup when the app wants plain text.
+pub fn text_for(profile: &AppPasteProfile, clip_type: &str, text: String) -> String {
+    if profile.plain_text && clip_type == "html" {
+        strip_html(&text)
+    } else {
+        text
+    }
+}
+
+/// Reduce HTML to its text: tags dropped, block ends as line breaks, common entities decoded.
+pub fn strip_html(html: &str) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        out.push_str(&rest[..start]);
+        let Some(len) = rest[start..].find('>') else {
+            rest = &rest[start..];
+            break;
+        };
+        let tag = rest[start + 1..start + len].trim().to_ascii_lowercase();
+        let name = tag
+            .trim_start_matches('/')
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or_default();
+        rest = &rest[start + len + 1..];
+
+        // Skip what's inside <script> and <style> entirely
+        if !tag.starts_with('/') && (name == "script" || name == "style") {
+            let close = format!("</{}", name);
+            rest = match rest.to_ascii_lowercase().find(&close) {
+                Some(end) => &rest[end..],
+                None => "",
+            };
+            continue;
+        }
+        let breaks_line = name == "br"
+            || (tag.starts_with('/')
+                && matches!(
+                    name,
+                    "p" | "div" | "li" | "tr" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6"
+                ));
+        if breaks_line {
+            out.push('\n');
+        }
+    }
+    out.push_str(rest);
+
+    out.replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+        .trim_end()
+        .to_string()
+}
+
+/// How to finish a paste once the clip is on the clipboard.
+#[derive(Debug, Default)]
+pub struct PastePlan {
+    pub profile: AppPasteProfile,
+    /// Left-arrow presses after pasting, for a template's `{{cursor}}`.
+    pub cursor_back: usize,
+    /// Clipboard contents to put back once the paste keystroke is sent.
+    pub restore: Option<ClipboardSnapshot>,
+}
+
+/// The target app's profile, plus a snapshot of the clipboard if it is to be restored.
+/// `always_pastes` is for callers that send the paste keystroke regardless of `auto_paste`.
+/// Call before writing the clip to the clipboard.
+pub async fn plan(app: &AppHandle, always_pastes: bool) -> PastePlan {
+    let settings = app.state::<Arc<SettingsManager>>().get();
+    let names = crate::clipboard::target_app_names();
+    let profile = matching_profile(&settings.paste_profiles, &names)
+        .cloned()
+        .unwrap_or_default();
+    if !profile.app.is_empty() {
+        log::info!("CLIPBOARD: Using paste profile for {}", profile.app);
+    }
+
+    // Without a paste keystroke the clip is meant to stay on the clipboard
+    let wants_restore = settings.restore_clipboard_after_paste || profile.restore_clipboard;
+    let restore = if wants_restore && (always_pastes || settings.auto_paste) {
+        match tauri::async_runtime::spawn_blocking(ClipboardSnapshot::capture)
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|res| res)
+        {
+            Ok(snapshot) => Some(snapshot),
+            Err(e) => {
+                log::warn!("CLIPBOARD: Failed to snapshot clipboard: {}", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+    PastePlan {
+        profile,
+        cursor_back: 0,
+        restore,
+    }
+}
+
+/// Send the paste keystroke, then move the caret and restore the clipboard as planned.
+/// Blocks while waiting, so run it off the async runtime once the target app has focus.
+pub fn send(app: &AppHandle, plan: PastePlan) {
+    std::thread::sleep(Duration::from_millis(plan.profile.delay_ms));
+
+    #[cfg(any(target_os = "windows", target_os = "macos"))]
+    {
+        crate::clipboard::send_paste_chord(plan.profile.chord);
+        if plan.cursor_back > 0 {
+            // Let the target app insert the text before moving the caret
+            std::thread::sleep(Duration::from_millis(50));
+            crate::clipboard::send_cursor_left(plan.cursor_back);
+        }
+    }
+
+    if let Some(snapshot) = plan.restore {
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            restore_snapshot(&app, snapshot).await;
+        });
+    }
+}
+
+async fn restore_snapshot(app: &AppHandle, snapshot: ClipboardSnapshot) {
+    tokio::time::sleep(Duration::from_millis(RESTORE_DELAY_MS)).await;
+
+    // Synchronize clipboard access across the app
+    let _guard = crate::clipboard::CLIPBOARD_SYNC.lock().await;
+
+    // Stop monitor and suppress capture so the restore isn't recorded as a new clip
+    if let Err(e) = stop_listening().await {
+        log::error!("Failed to stop listener: {}", e);
+    }
+    crate::clipboard::suppress_capture_for(Duration::from_millis(RESTORE_SUPPRESS_MS));
+    match tauri::async_runtime::spawn_blocking(move || snapshot.restore())
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|res| res)
+    {
+        Ok(()) => log::info!("CLIPBOARD: Restored previous clipboard"),
+        Err(e) => log::error!("CLIPBOARD: Failed to restore previous clipboard: {}", e),
+    }
+    // Count the suppression from when the clipboard actually changed
+    crate::clipboard::suppress_capture_for(Duration::from_millis(RESTORE_SUPPRESS_MS));
+    if let Err(e) = start_listening(app.clone()).await {
+        log::error!("Failed to restart listener: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matching_profile() {
+        let profiles = vec![
+            AppPasteProfile {
+                app: "WindowsTerminal.exe".to_string(),
+                ..Default::default()
+            },
+            AppPasteProfile {
+                app: "com.apple.Terminal".to_string(),
+                plain_text: true,
+                ..Default::default()
+            },
+        ];
+        let names = vec!["Terminal".to_string(), "COM.APPLE.TERMINAL".to_string()];
+        assert!(matching_profile(&profiles, &names).unwrap().plain_text);
+        assert!(matching_profile(&profiles, &["Notepad.exe".to_string()]).is_none());
+    }
+
+    #[test]
+    fn test_strip_html() {
+        assert_eq!(
+            strip_html(
+                "<style>p{}</style><p>Fish &amp; <b>chips</b></p><div>a<br/>b</div><script>x()</script>&lt;3"
+            ),
+            "Fish & chips\na\nb\n<3"
+        );
+    }
+}
@@ -0,0 +1,59 @@
+use crate::profiles::{Profile, ProfileManager, ProfilesConfig};
+use std::sync::Arc;
+use tauri::{AppHandle, Manager};
+
+#[tauri::command]
+pub async fn get_profiles(app: AppHandle) -> Result<ProfilesConfig, String> {
+    let manager = app.state::<Arc<ProfileManager>>();
+    Ok(manager.get())
+}
+
+#[tauri::command]
+pub async fn create_profile(name: String, app: AppHandle) -> Result<Profile, String> {
+    let manager = app.state::<Arc<ProfileManager>>();
+    let profile = manager.create(&name)?;
+
+    // Seed the new profile with the current settings instead of factory defaults.
+    let settings = crate::data_dir::settings_path();
+    if settings.exists() {
+        let target =
+            ProfileManager::profile_dir(&profile.id).join(crate::data_dir::SETTINGS_FILE_NAME);
+        if let Err(e) = std::fs::copy(&settings, &target) {
+            log::warn!("PROFILE: Failed to copy settings to new profile: {}", e);
+        }
+    }
+    Ok(profile)
+}
+
+#[tauri::command]
+pub async fn rename_profile(id: String, name: String, app: AppHandle) -> Result<(), String> {
+    let manager = app.state::<Arc<ProfileManager>>();
+    manager.rename(&id, &name)
+}
+
+#[tauri::command]
+pub async fn delete_profile(id: String, app: AppHandle) -> Result<(), String> {
+    let manager = app.state::<Arc<ProfileManager>>();
+    manager.delete(&id)
+}
+
+#[tauri::command]
+pub async fn switch_profile(id: String, app: AppHandle) -> Result<(), String> {
+    crate::profiles::switch_profile(&app, &id).await
+}
+
+#[tauri::command]
+pub async fn set_profile_auto_switch(enabled: bool, app: AppHandle) -> Result<(), String> {
+    let manager = app.state::<Arc<ProfileManager>>();
+    manager.set_auto_switch(enabled)
+}
+
+#[tauri::command]
+pub async fn set_profile_auto_switch_apps(
+    id: String,
+    apps: Vec<String>,
+    app: AppHandle,
+) -> Result<(), String> {
+    let manager = app.state::<Arc<ProfileManager>>();
+    manager.set_auto_switch_apps(&id, apps)
+}
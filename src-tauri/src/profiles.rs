@@ -0,0 +1,359 @@
+use crate::database::Database;
+use crate::settings_manager::SettingsManager;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use tauri::{AppHandle, Emitter, Manager};
+
+pub const DEFAULT_PROFILE_ID: &str = "default";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Profile {
+    pub id: String,
+    pub name: String,
+    /// Apps (name, exe or full path) whose copies switch to this profile automatically.
+    #[serde(default)]
+    pub auto_switch_apps: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProfilesConfig {
+    pub active: String,
+    pub auto_switch: bool,
+    pub profiles: Vec<Profile>,
+}
+
+impl Default for ProfilesConfig {
+    fn default() -> Self {
+        Self {
+            active: DEFAULT_PROFILE_ID.to_string(),
+            auto_switch: false,
+            profiles: vec![Profile {
+                id: DEFAULT_PROFILE_ID.to_string(),
+                name: "Default".to_string(),
+                auto_switch_apps: Vec::new(),
+            }],
+        }
+    }
+}
+
+pub struct ProfileManager {
+    /// The data directory, which holds `profiles.json` and the default profile.
+    root: PathBuf,
+    file_path: PathBuf,
+    config: RwLock<ProfilesConfig>,
+}
+
+impl ProfileManager {
+    pub fn load() -> Self {
+        Self::load_in(crate::data_dir::data_dir())
+    }
+
+    fn load_in(root: PathBuf) -> Self {
+        let path = root.join(crate::data_dir::PROFILES_FILE_NAME);
+        let mut config: ProfilesConfig = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        if !config.profiles.iter().any(|p| p.id == DEFAULT_PROFILE_ID) {
+            config
+                .profiles
+                .insert(0, ProfilesConfig::default().profiles.remove(0));
+        }
+        if !config.profiles.iter().any(|p| p.id == config.active) {
+            log::warn!(
+                "PROFILE: Active profile {} not found, using default",
+                config.active
+            );
+            config.active = DEFAULT_PROFILE_ID.to_string();
+        }
+
+        Self {
+            root,
+            file_path: path,
+            config: RwLock::new(config),
+        }
+    }
+
+    pub fn get(&self) -> ProfilesConfig {
+        self.config.read().unwrap().clone()
+    }
+
+    /// Written to disk first, so a failed write leaves the loaded config unchanged.
+    pub fn save(&self, config: ProfilesConfig) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(&config).map_err(|e| e.to_string())?;
+        fs::write(&self.file_path, json).map_err(|e| e.to_string())?;
+        *self.config.write().unwrap() = config;
+        Ok(())
+    }
+
+    /// The default profile keeps using the data directory root, so existing installs need no migration.
+    pub fn profile_dir(id: &str) -> PathBuf {
+        profile_dir_in(crate::data_dir::data_dir(), id)
+    }
+
+    /// Profile that should receive a clip copied from the given app, if it differs from the active one.
+    pub fn auto_switch_target(&self, app_names: &[Option<&str>]) -> Option<String> {
+        let config = self.config.read().unwrap();
+        if !config.auto_switch {
+            return None;
+        }
+
+        let names: Vec<String> = app_names
+            .iter()
+            .flatten()
+            .map(|name| name.to_lowercase())
+            .collect();
+        if names.is_empty() {
+            return None;
+        }
+
+        config
+            .profiles
+            .iter()
+            .find(|profile| {
+                profile
+                    .auto_switch_apps
+                    .iter()
+                    .any(|app| names.contains(&app.to_lowercase()))
+            })
+            .filter(|profile| profile.id != config.active)
+            .map(|profile| profile.id.clone())
+    }
+
+    pub fn create(&self, name: &str) -> Result<Profile, String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("Profile name cannot be empty".to_string());
+        }
+        let mut config = self.get();
+        if config.profiles.iter().any(|p| p.name == name) {
+            return Err("A profile with this name already exists".to_string());
+        }
+
+        let profile = Profile {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            auto_switch_apps: Vec::new(),
+        };
+        fs::create_dir_all(profile_dir_in(self.root.clone(), &profile.id))
+            .map_err(|e| e.to_string())?;
+        config.profiles.push(profile.clone());
+        self.save(config)?;
+        Ok(profile)
+    }
+
+    pub fn rename(&self, id: &str, name: &str) -> Result<(), String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("Profile name cannot be empty".to_string());
+        }
+        let mut config = self.get();
+        if config.profiles.iter().any(|p| p.name == name && p.id != id) {
+            return Err("A profile with this name already exists".to_string());
+        }
+        let profile = config
+            .profiles
+            .iter_mut()
+            .find(|p| p.id == id)
+            .ok_or("Profile not found")?;
+        profile.name = name.to_string();
+        self.save(config)
+    }
+
+    pub fn delete(&self, id: &str) -> Result<(), String> {
+        let mut config = self.get();
+        if id == DEFAULT_PROFILE_ID {
+            return Err("The default profile cannot be deleted".to_string());
+        }
+        if id == config.active {
+            return Err("Switch to another profile before deleting this one".to_string());
+        }
+        let before = config.profiles.len();
+        config.profiles.retain(|p| p.id != id);
+        if config.profiles.len() == before {
+            return Err("Profile not found".to_string());
+        }
+        self.save(config)?;
+
+        let dir = profile_dir_in(self.root.clone(), id);
+        if let Err(e) = fs::remove_dir_all(&dir) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                log::warn!("PROFILE: Failed to delete profile data {:?}: {}", dir, e);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn set_auto_switch(&self, enabled: bool) -> Result<(), String> {
+        let mut config = self.get();
+        config.auto_switch = enabled;
+        self.save(config)
+    }
+
+    pub fn set_auto_switch_apps(&self, id: &str, apps: Vec<String>) -> Result<(), String> {
+        let mut config = self.get();
+        let profile = config
+            .profiles
+            .iter_mut()
+            .find(|p| p.id == id)
+            .ok_or("Profile not found")?;
+        profile.auto_switch_apps = apps
+            .into_iter()
+            .map(|a| a.trim().to_string())
+            .filter(|a| !a.is_empty())
+            .collect();
+        self.save(config)
+    }
+}
+
+fn profile_dir_in(root: PathBuf, id: &str) -> PathBuf {
+    if id == DEFAULT_PROFILE_ID {
+        root
+    } else {
+        root.join(crate::data_dir::PROFILES_DIR_NAME).join(id)
+    }
+}
+
+/// Switch the managed database, image directory and settings to another profile.
+pub async fn switch_profile(app: &AppHandle, id: &str) -> Result<(), String> {
+    let _guard = crate::clipboard::CLIPBOARD_SYNC.lock().await;
+    switch_profile_locked(app, id).await
+}
+
+/// Same as [`switch_profile`], for callers that already hold `CLIPBOARD_SYNC`.
+pub async fn switch_profile_locked(app: &AppHandle, id: &str) -> Result<(), String> {
+    let manager = app.state::<Arc<ProfileManager>>();
+    let mut config = manager.get();
+    let profile = config
+        .profiles
+        .iter()
+        .find(|p| p.id == id)
+        .cloned()
+        .ok_or("Profile not found")?;
+    if config.active == profile.id {
+        return Ok(());
+    }
+
+    let dir = ProfileManager::profile_dir(&profile.id);
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let db_path = dir.join(crate::data_dir::DB_FILE_NAME);
+
+    let new_db = Database::connect(&db_path.to_string_lossy())
+        .await
+        .map_err(|e| format!("Failed to open profile database: {}", e))?;
+    new_db.migrate().await.map_err(|e| e.to_string())?;
+
+    let settings_path = dir.join(crate::data_dir::SETTINGS_FILE_NAME);
+    let settings_manager = app.state::<Arc<SettingsManager>>();
+    let settings = settings_manager.load_profile_file(&settings_path)?;
+    config.active = profile.id.clone();
+    manager.save(config)?;
+
+    // Nothing below can fail, so the app never runs on a mix of two profiles
+    let db = app.state::<Arc<Database>>();
+    // Work still holding the old pool finishes on it; it closes when the last clone is dropped
+    drop(db.replace_pool(new_db.into_pool()));
+    crate::data_dir::set_profile_dir(dir);
+    settings_manager.switch_file(settings_path, settings);
+
+    log::info!("PROFILE: Switched to {} ({})", profile.name, profile.id);
+    let _ = app.emit("profile-changed", &profile);
+    let _ = app.emit("clipboard-change", ());
+    crate::rules::invalidate();
+    crate::paste_queue::clear(app);
+    crate::shortcuts::register_from_settings(app);
+    crate::embeddings::wake();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manager(name: &str) -> ProfileManager {
+        let root = std::env::temp_dir().join(format!("pastepaw_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        ProfileManager::load_in(root)
+    }
+
+    #[test]
+    fn test_profile_dir_layout() {
+        let root = PathBuf::from("data");
+        assert_eq!(profile_dir_in(root.clone(), DEFAULT_PROFILE_ID), root);
+        assert_eq!(
+            profile_dir_in(root.clone(), "work"),
+            root.join("profiles").join("work")
+        );
+    }
+
+    #[test]
+    fn test_create_rename_delete() {
+        let manager = manager("profiles_crud");
+        assert_eq!(manager.get().active, DEFAULT_PROFILE_ID);
+
+        let work = manager.create("  Work ").unwrap();
+        assert_eq!(work.name, "Work");
+        assert!(profile_dir_in(manager.root.clone(), &work.id).is_dir());
+        assert!(manager.create("Work").is_err());
+        assert!(manager.create(" ").is_err());
+
+        let other = manager.create("Other").unwrap();
+        assert!(manager.rename(&work.id, "Other").is_err());
+        manager.rename(&work.id, "Client").unwrap();
+        assert!(manager.rename("missing", "X").is_err());
+
+        // Saved to profiles.json
+        let reloaded = ProfileManager::load_in(manager.root.clone());
+        let names: Vec<String> = reloaded
+            .get()
+            .profiles
+            .into_iter()
+            .map(|p| p.name)
+            .collect();
+        assert_eq!(names, ["Default", "Client", "Other"]);
+
+        assert!(manager.delete(DEFAULT_PROFILE_ID).is_err());
+        let mut config = manager.get();
+        config.active = other.id.clone();
+        manager.save(config).unwrap();
+        assert!(manager.delete(&other.id).is_err());
+        manager.delete(&work.id).unwrap();
+        assert!(!profile_dir_in(manager.root.clone(), &work.id).exists());
+        assert!(manager.delete(&work.id).is_err());
+
+        let _ = fs::remove_dir_all(&manager.root);
+    }
+
+    #[test]
+    fn test_auto_switch_target() {
+        let manager = manager("profiles_auto_switch");
+        let work = manager.create("Work").unwrap();
+        manager
+            .set_auto_switch_apps(&work.id, vec![" Slack.exe ".to_string(), "".to_string()])
+            .unwrap();
+        assert_eq!(manager.get().profiles[1].auto_switch_apps, ["Slack.exe"]);
+
+        // Off until enabled
+        assert_eq!(manager.auto_switch_target(&[Some("slack.exe")]), None);
+        manager.set_auto_switch(true).unwrap();
+        assert_eq!(
+            manager.auto_switch_target(&[None, Some("SLACK.EXE")]),
+            Some(work.id.clone())
+        );
+        assert_eq!(manager.auto_switch_target(&[Some("code.exe")]), None);
+        assert_eq!(manager.auto_switch_target(&[None]), None);
+
+        // Already active
+        let mut config = manager.get();
+        config.active = work.id.clone();
+        manager.save(config).unwrap();
+        assert_eq!(manager.auto_switch_target(&[Some("slack.exe")]), None);
+
+        let _ = fs::remove_dir_all(&manager.root);
+    }
+}
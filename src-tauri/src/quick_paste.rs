@@ -0,0 +1,130 @@
+//! Quick paste: modifiers plus 1-9 paste the Nth most recent clip, or the Nth clip of one
+//! folder, straight into the focused app without showing the window.
+
+use crate::database::Database;
+use crate::models::Clip;
+use crate::settings_manager::SettingsManager;
+use once_cell::sync::Lazy;
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+
+/// The modifiers currently bound, e.g. "Ctrl+Alt", so the shortcuts can be swapped out.
+static MODIFIERS: Lazy<parking_lot::Mutex<Option<String>>> =
+    Lazy::new(|| parking_lot::Mutex::new(None));
+
+/// Time for the user to let go of the shortcut's modifiers before the paste keystroke.
+const MODIFIER_RELEASE_DELAY_MS: u64 = 150;
+
+fn shortcuts(modifiers: &str) -> Result<Vec<(usize, Shortcut)>, String> {
+    (1..=9)
+        .map(|n| {
+            let hotkey = format!("{}+{}", modifiers, n);
+            Shortcut::from_str(&hotkey)
+                .map(|shortcut| (n, shortcut))
+                .map_err(|e| format!("Invalid hotkey {}: {:?}", hotkey, e))
+        })
+        .collect()
+}
+
+/// Bind `modifiers`+1 through `modifiers`+9, replacing the previous ones. Empty disables them.
+/// Digits that fail to register (e.g. taken by another app) are reported together; the rest
+/// stay bound.
+pub fn register_shortcuts(app: &AppHandle, modifiers: &str) -> Result<(), String> {
+    let modifiers = modifiers.trim();
+    let new = if modifiers.is_empty() {
+        Vec::new()
+    } else {
+        shortcuts(modifiers)?
+    };
+
+    let mut current = MODIFIERS.lock();
+    if let Some(old) = current.take() {
+        for (_, shortcut) in shortcuts(&old).unwrap_or_default() {
+            let _ = app.global_shortcut().unregister(shortcut);
+        }
+    }
+    if new.is_empty() {
+        return Ok(());
+    }
+
+    let mut failed = Vec::new();
+    for (n, shortcut) in new {
+        // Fire on release so the shortcut's own keys are up before the paste keystroke is sent
+        let res = app
+            .global_shortcut()
+            .on_shortcut(shortcut, move |app, _shortcut, event| {
+                if event.state() == ShortcutState::Released {
+                    let app = app.clone();
+                    tauri::async_runtime::spawn(async move {
+                        let folder_id = app
+                            .state::<Arc<SettingsManager>>()
+                            .get()
+                            .quick_paste_folder_id;
+                        if let Err(e) = paste_nth(&app, n, folder_id).await {
+                            log::error!("CLIPBOARD: Quick paste {} failed: {}", n, e);
+                        }
+                    });
+                }
+            });
+        if let Err(e) = res {
+            failed.push(format!("{}+{} ({:?})", modifiers, n, e));
+        }
+    }
+    *current = Some(modifiers.to_string());
+    if !failed.is_empty() {
+        return Err(format!("Failed to register {}", failed.join(", ")));
+    }
+    log::info!("Registered quick paste shortcuts: {}+1..9", modifiers);
+    Ok(())
+}
+
+/// The `n`th (1-based) clip in history order, within `folder_id` if given.
+async fn nth_clip(
+    pool: &SqlitePool,
+    n: usize,
+    folder_id: Option<i64>,
+) -> Result<Option<Clip>, String> {
+    let folder_filter = if folder_id.is_some() {
+        " AND folder_id = ?"
+    } else {
+        ""
+    };
+    let sql = format!(
+        "SELECT * FROM clips WHERE is_deleted = 0{} ORDER BY created_at DESC, id DESC LIMIT 1 OFFSET ?",
+        folder_filter
+    );
+    let mut query = sqlx::query_as(&sql);
+    if let Some(id) = folder_id {
+        query = query.bind(id);
+    }
+    query
+        .bind(n.saturating_sub(1) as i64)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Paste the `n`th clip, within `folder_id` if given, into the focused app.
+/// Does nothing if there are fewer clips.
+pub async fn paste_nth(app: &AppHandle, n: usize, folder_id: Option<i64>) -> Result<(), String> {
+    let pool = app.state::<Arc<Database>>().pool();
+
+    let Some(mut clip) = nth_clip(&pool, n, folder_id).await? else {
+        log::info!("CLIPBOARD: No clip {} to quick paste", n);
+        return Ok(());
+    };
+
+    let mut plan = crate::paste_strategy::plan(app, true).await;
+    crate::commands::write_clip_for_paste(app, &pool, &mut clip, &mut plan, &HashMap::new(), true)
+        .await?;
+
+    tokio::time::sleep(std::time::Duration::from_millis(MODIFIER_RELEASE_DELAY_MS)).await;
+    let app = app.clone();
+    let _ =
+        tauri::async_runtime::spawn_blocking(move || crate::paste_strategy::send(&app, plan)).await;
+    Ok(())
+}
@@ -0,0 +1,305 @@
+use crate::ai_actions;
+use crate::clip_list;
+use crate::database::Database;
+use crate::models::ClipboardItem;
+use crate::rules::{self, ClipFacts, CompiledRule, RoutingRule};
+use crate::search_match;
+use crate::settings_manager::SettingsManager;
+use futures::TryStreamExt;
+use serde::Serialize;
+use sqlx::SqlitePool;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// How far back `test_routing_rule` looks.
+const TEST_SCAN_LIMIT: i64 = 10_000;
+
+#[derive(Debug, Serialize)]
+pub struct RuleTestResult {
+    pub scanned: i64,
+    pub matched: i64,
+    /// Clips left unchecked once the time budget ran out.
+    pub skipped: i64,
+    /// The newest matches, up to the requested limit.
+    pub clips: Vec<ClipboardItem>,
+}
+
+#[tauri::command]
+pub async fn get_routing_rules(
+    db: tauri::State<'_, Arc<Database>>,
+) -> Result<Vec<RoutingRule>, String> {
+    rules::list(&db.pool()).await
+}
+
+/// Create the rule when `id` is 0, otherwise update it. New rules go last.
+#[tauri::command]
+pub async fn save_routing_rule(
+    rule: RoutingRule,
+    db: tauri::State<'_, Arc<Database>>,
+    settings: tauri::State<'_, Arc<SettingsManager>>,
+) -> Result<RoutingRule, String> {
+    let pool = &db.pool();
+    let mut rule = CompiledRule::new(rule)?.rule;
+    rule.name = rule.name.trim().to_string();
+    let ai_actions = settings.get().ai_actions;
+    for action in &rule.actions {
+        if let rules::RuleAction::RunAi { action } = action {
+            if ai_actions::find(&ai_actions, action).is_none() {
+                return Err(format!("Unknown AI action '{}'", action));
+            }
+        }
+        if let rules::RuleAction::MoveToFolder { folder_id } = action {
+            let is_regular: Option<bool> =
+                sqlx::query_scalar("SELECT query IS NULL FROM folders WHERE id = ?")
+                    .bind(folder_id)
+                    .fetch_optional(pool)
+                    .await
+                    .map_err(|e| e.to_string())?;
+            match is_regular {
+                None => return Err("Folder not found".to_string()),
+                Some(false) => return Err("Rules can't move clips into a smart folder".to_string()),
+                Some(true) => {}
+            }
+        }
+    }
+    let conditions = serde_json::to_string(&rule.conditions).map_err(|e| e.to_string())?;
+    let actions = serde_json::to_string(&rule.actions).map_err(|e| e.to_string())?;
+
+    if rule.id == 0 {
+        rule.position =
+            sqlx::query_scalar("SELECT COALESCE(MAX(position) + 1, 0) FROM routing_rules")
+                .fetch_one(pool)
+                .await
+                .map_err(|e| e.to_string())?;
+        rule.id = sqlx::query(
+            r#"INSERT INTO routing_rules (name, enabled, position, stop_processing, conditions, actions) VALUES (?, ?, ?, ?, ?, ?)"#,
+        )
+        .bind(&rule.name)
+        .bind(rule.enabled)
+        .bind(rule.position)
+        .bind(rule.stop_processing)
+        .bind(&conditions)
+        .bind(&actions)
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?
+        .last_insert_rowid();
+    } else {
+        let result = sqlx::query(
+            r#"UPDATE routing_rules SET name = ?, enabled = ?, stop_processing = ?, conditions = ?, actions = ? WHERE id = ?"#,
+        )
+        .bind(&rule.name)
+        .bind(rule.enabled)
+        .bind(rule.stop_processing)
+        .bind(&conditions)
+        .bind(&actions)
+        .bind(rule.id)
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+        if result.rows_affected() == 0 {
+            return Err("Rule not found".to_string());
+        }
+        rule.position = sqlx::query_scalar("SELECT position FROM routing_rules WHERE id = ?")
+            .bind(rule.id)
+            .fetch_one(pool)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    rules::invalidate();
+    Ok(rule)
+}
+
+#[tauri::command]
+pub async fn delete_routing_rule(
+    id: i64,
+    db: tauri::State<'_, Arc<Database>>,
+) -> Result<(), String> {
+    sqlx::query("DELETE FROM routing_rules WHERE id = ?")
+        .bind(id)
+        .execute(&db.pool())
+        .await
+        .map_err(|e| e.to_string())?;
+    rules::invalidate();
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn set_routing_rule_enabled(
+    id: i64,
+    enabled: bool,
+    db: tauri::State<'_, Arc<Database>>,
+) -> Result<(), String> {
+    let result = sqlx::query("UPDATE routing_rules SET enabled = ? WHERE id = ?")
+        .bind(enabled)
+        .bind(id)
+        .execute(&db.pool())
+        .await
+        .map_err(|e| e.to_string())?;
+    if result.rows_affected() == 0 {
+        return Err("Rule not found".to_string());
+    }
+    rules::invalidate();
+    Ok(())
+}
+
+/// Set the evaluation order to `ids`, first to last.
+#[tauri::command]
+pub async fn reorder_routing_rules(
+    ids: Vec<i64>,
+    db: tauri::State<'_, Arc<Database>>,
+) -> Result<(), String> {
+    let mut tx = db.pool().begin().await.map_err(|e| e.to_string())?;
+    for (position, id) in ids.iter().enumerate() {
+        sqlx::query("UPDATE routing_rules SET position = ? WHERE id = ?")
+            .bind(position as i64)
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    tx.commit().await.map_err(|e| e.to_string())?;
+    rules::invalidate();
+    Ok(())
+}
+
+/// Check a rule, saved or not, against recent history without applying its actions.
+/// History only records the app name, so `source_app` can't match on executables here.
+#[tauri::command]
+pub async fn test_routing_rule(
+    rule: RoutingRule,
+    limit: Option<i64>,
+    db: tauri::State<'_, Arc<Database>>,
+) -> Result<RuleTestResult, String> {
+    let pool = &db.pool();
+    let compiled = CompiledRule::new(rule)?;
+    let limit = limit.unwrap_or(20).max(0) as usize;
+
+    let (result, uuids) = scan(pool, &compiled, limit, search_match::TIME_BUDGET).await?;
+    let list_rows = clip_list::fetch_by_uuid(pool, &uuids, true).await?;
+    let clips = crate::commands::clip_list_items(pool, &list_rows).await?;
+    Ok(RuleTestResult { clips, ..result })
+}
+
+/// Run `compiled` over recent history, newest first, until `budget` is spent. Returns the
+/// counts and the uuids of the first `limit` matches.
+async fn scan(
+    pool: &SqlitePool,
+    compiled: &CompiledRule,
+    limit: usize,
+    budget: Duration,
+) -> Result<(RuleTestResult, Vec<String>), String> {
+    let total: i64 = sqlx::query_scalar("SELECT MIN(COUNT(*), ?) FROM clips WHERE is_deleted = 0")
+        .bind(TEST_SCAN_LIMIT)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut rows = sqlx::query_as::<_, (String, String, Option<String>, Vec<u8>, i64)>(
+        r#"
+        SELECT uuid, clip_type, source_app,
+            CASE WHEN clip_type = 'image' THEN CAST(COALESCE(text_preview, '') AS BLOB) ELSE substr(content, 1, ?) END,
+            CASE WHEN clip_type = 'image'
+                THEN COALESCE((SELECT file_size FROM clip_images WHERE clip_uuid = clips.uuid), length(content))
+                ELSE length(content) END
+        FROM clips WHERE is_deleted = 0
+        ORDER BY created_at DESC, id DESC LIMIT ?
+        "#,
+    )
+    .bind(search_match::MAX_MATCH_BYTES as i64)
+    .bind(TEST_SCAN_LIMIT)
+    .fetch(pool);
+
+    let started = Instant::now();
+    let (mut scanned, mut matched) = (0, 0);
+    let mut uuids = Vec::new();
+    while let Some((uuid, clip_type, source_app, content, size)) =
+        rows.try_next().await.map_err(|e| e.to_string())?
+    {
+        scanned += 1;
+        let text = search_match::match_text(&content);
+        let facts = ClipFacts {
+            clip_type: &clip_type,
+            app_names: source_app.as_deref().into_iter().collect(),
+            text: &text,
+            size,
+        };
+        if compiled.matches(&facts) {
+            matched += 1;
+            if uuids.len() < limit {
+                uuids.push(uuid);
+            }
+        }
+        if started.elapsed() > budget {
+            log::warn!(
+                "RULES: Time budget exceeded, tested {} of {} clips",
+                scanned,
+                total
+            );
+            break;
+        }
+    }
+    drop(rows);
+
+    let result = RuleTestResult {
+        scanned,
+        matched,
+        skipped: (total - scanned).max(0),
+        clips: Vec::new(),
+    };
+    Ok((result, uuids))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::TestDb;
+    use crate::rules::{RuleAction, RuleConditions};
+
+    fn regex_rule(pattern: String) -> Result<CompiledRule, String> {
+        CompiledRule::new(RoutingRule {
+            id: 0,
+            name: "test".to_string(),
+            enabled: true,
+            position: 0,
+            stop_processing: false,
+            conditions: RuleConditions {
+                content_regex: Some(pattern),
+                ..Default::default()
+            },
+            actions: vec![RuleAction::Discard],
+        })
+    }
+
+    #[tokio::test]
+    async fn test_scan_counts() {
+        let db = TestDb::new("rule_scan").await;
+        let pool = &db.pool;
+        for (i, text) in ["token sk-1", "hello", "token sk-2", "bye"]
+            .iter()
+            .enumerate()
+        {
+            sqlx::query("INSERT INTO clips (uuid, clip_type, content, text_preview, content_hash) VALUES (?, 'text', ?, '', ?)")
+                .bind(format!("u{}", i))
+                .bind(text.as_bytes())
+                .bind(format!("h{}", i))
+                .execute(pool)
+                .await
+                .unwrap();
+        }
+        let rule = regex_rule("sk-\\d".to_string()).unwrap();
+
+        let (result, uuids) = scan(pool, &rule, 1, Duration::from_secs(60)).await.unwrap();
+        assert_eq!((result.scanned, result.matched, result.skipped), (4, 2, 0));
+        assert_eq!(uuids.len(), 1);
+
+        // Out of time after the first clip
+        let (result, _) = scan(pool, &rule, 10, Duration::ZERO).await.unwrap();
+        assert_eq!((result.scanned, result.skipped), (1, 3));
+
+        assert!(regex_rule("a".repeat(2000)).is_err());
+
+        db.close().await;
+    }
+}
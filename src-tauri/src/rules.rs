@@ -0,0 +1,438 @@
+//! Routing rules run on every captured clip: file it into a folder, tag it, run an AI action,
+//! mark it sensitive or drop it.
+//!
+//! Rules live in the profile's database. The enabled ones are cached so capturing a clip doesn't
+//! query them; every change goes through [`invalidate`].
+
+use crate::search_match;
+use crate::search_query::CLIP_TYPES;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, SqlitePool};
+use std::sync::Arc;
+
+static ENABLED_RULES: Lazy<parking_lot::RwLock<Option<Arc<Vec<CompiledRule>>>>> =
+    Lazy::new(|| parking_lot::RwLock::new(None));
+
+/// All set conditions must hold; an empty set matches every clip.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RuleConditions {
+    /// Case-insensitive substring of the source app name, executable or path.
+    pub source_app: Option<String>,
+    /// Regex over the clip text; image clips match on their preview.
+    pub content_regex: Option<String>,
+    /// Any of these clip types; empty means any type.
+    pub clip_types: Vec<String>,
+    /// Content size bounds in bytes, inclusive. Images use the full image size.
+    pub min_size: Option<i64>,
+    pub max_size: Option<i64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RuleAction {
+    MoveToFolder {
+        folder_id: i64,
+    },
+    AddTags {
+        tags: Vec<String>,
+    },
+    /// The id of one of the `ai_actions`, run in the background after capture.
+    RunAi {
+        action: String,
+    },
+    MarkSensitive,
+    /// Don't record the clip at all. Stops later rules.
+    Discard,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoutingRule {
+    #[serde(default)]
+    pub id: i64,
+    pub name: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Rules run in ascending position; a later folder move wins over an earlier one.
+    #[serde(default)]
+    pub position: i64,
+    /// Skip the remaining rules once this one matches.
+    #[serde(default)]
+    pub stop_processing: bool,
+    #[serde(default)]
+    pub conditions: RuleConditions,
+    pub actions: Vec<RuleAction>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(FromRow)]
+struct RuleRow {
+    id: i64,
+    name: String,
+    enabled: bool,
+    position: i64,
+    stop_processing: bool,
+    conditions: String,
+    actions: String,
+}
+
+impl TryFrom<RuleRow> for RoutingRule {
+    type Error = String;
+
+    fn try_from(row: RuleRow) -> Result<Self, Self::Error> {
+        Ok(RoutingRule {
+            id: row.id,
+            name: row.name,
+            enabled: row.enabled,
+            position: row.position,
+            stop_processing: row.stop_processing,
+            conditions: serde_json::from_str(&row.conditions).map_err(|e| e.to_string())?,
+            actions: serde_json::from_str(&row.actions).map_err(|e| e.to_string())?,
+        })
+    }
+}
+
+/// A rule with its regex compiled, ready to evaluate.
+#[derive(Debug)]
+pub struct CompiledRule {
+    pub rule: RoutingRule,
+    regex: Option<Regex>,
+}
+
+impl CompiledRule {
+    pub fn new(rule: RoutingRule) -> Result<Self, String> {
+        if rule.name.trim().is_empty() {
+            return Err("Rule name can't be empty".to_string());
+        }
+        if rule.actions.is_empty() {
+            return Err("Rule needs at least one action".to_string());
+        }
+        for clip_type in &rule.conditions.clip_types {
+            if !CLIP_TYPES.contains(&clip_type.as_str()) {
+                return Err(format!("Unknown clip type '{}'", clip_type));
+            }
+        }
+        for action in &rule.actions {
+            if let RuleAction::RunAi { action } = action {
+                if action.trim().is_empty() {
+                    return Err("Choose an AI action to run".to_string());
+                }
+            }
+        }
+        let regex = match rule.conditions.content_regex.as_deref() {
+            Some(pattern) if !pattern.is_empty() => Some(search_match::compile_regex(pattern)?),
+            _ => None,
+        };
+        Ok(Self { rule, regex })
+    }
+
+    pub fn matches(&self, clip: &ClipFacts) -> bool {
+        let conditions = &self.rule.conditions;
+        if let Some(app) = conditions.source_app.as_deref().filter(|a| !a.is_empty()) {
+            let app = app.to_lowercase();
+            if !clip
+                .app_names
+                .iter()
+                .any(|name| name.to_lowercase().contains(&app))
+            {
+                return false;
+            }
+        }
+        if !conditions.clip_types.is_empty()
+            && !conditions.clip_types.iter().any(|t| t == clip.clip_type)
+        {
+            return false;
+        }
+        if conditions.min_size.is_some_and(|min| clip.size < min)
+            || conditions.max_size.is_some_and(|max| clip.size > max)
+        {
+            return false;
+        }
+        match &self.regex {
+            Some(regex) => regex.is_match(clip.text),
+            None => true,
+        }
+    }
+}
+
+/// What the rules see of a clip.
+pub struct ClipFacts<'a> {
+    pub clip_type: &'a str,
+    /// Source app name, executable and path, whichever are known.
+    pub app_names: Vec<&'a str>,
+    pub text: &'a str,
+    pub size: i64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RuleOutcome {
+    /// Names of the rules that matched, in order.
+    pub matched: Vec<String>,
+    pub folder_id: Option<i64>,
+    pub tags: Vec<String>,
+    pub ai_actions: Vec<String>,
+    pub sensitive: bool,
+    pub discard: bool,
+}
+
+/// Run `rules` in order against a clip.
+pub fn evaluate(rules: &[CompiledRule], clip: &ClipFacts) -> RuleOutcome {
+    let mut outcome = RuleOutcome::default();
+    for compiled in rules.iter().filter(|r| r.rule.enabled) {
+        if !compiled.matches(clip) {
+            continue;
+        }
+        outcome.matched.push(compiled.rule.name.clone());
+        for action in &compiled.rule.actions {
+            match action {
+                RuleAction::MoveToFolder { folder_id } => outcome.folder_id = Some(*folder_id),
+                RuleAction::AddTags { tags } => {
+                    for tag in tags {
+                        let tag = tag.trim();
+                        if !tag.is_empty()
+                            && !outcome.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+                        {
+                            outcome.tags.push(tag.to_string());
+                        }
+                    }
+                }
+                RuleAction::RunAi { action } => {
+                    if !outcome.ai_actions.contains(action) {
+                        outcome.ai_actions.push(action.clone());
+                    }
+                }
+                RuleAction::MarkSensitive => outcome.sensitive = true,
+                RuleAction::Discard => {
+                    outcome.discard = true;
+                    return outcome;
+                }
+            }
+        }
+        if compiled.rule.stop_processing {
+            break;
+        }
+    }
+    outcome
+}
+
+/// Drop the cached rules, e.g. after an edit or a profile switch.
+pub fn invalidate() {
+    *ENABLED_RULES.write() = None;
+}
+
+pub async fn list(pool: &SqlitePool) -> Result<Vec<RoutingRule>, String> {
+    let rows: Vec<RuleRow> = sqlx::query_as(
+        "SELECT id, name, enabled, position, stop_processing, conditions, actions FROM routing_rules ORDER BY position, id",
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    rows.into_iter().map(RoutingRule::try_from).collect()
+}
+
+/// Enabled rules in order, from the cache when possible. Rules that no longer compile are skipped.
+pub async fn enabled_rules(pool: &SqlitePool) -> Result<Arc<Vec<CompiledRule>>, String> {
+    if let Some(rules) = ENABLED_RULES.read().as_ref() {
+        return Ok(rules.clone());
+    }
+    let rules: Vec<CompiledRule> = list(pool)
+        .await?
+        .into_iter()
+        .filter(|rule| rule.enabled)
+        .filter_map(|rule| {
+            let name = rule.name.clone();
+            CompiledRule::new(rule)
+                .map_err(|e| log::warn!("RULES: Skipping rule '{}': {}", name, e))
+                .ok()
+        })
+        .collect();
+    let rules = Arc::new(rules);
+    *ENABLED_RULES.write() = Some(rules.clone());
+    Ok(rules)
+}
+
+/// Apply the database side of an outcome to a stored clip. AI actions are left to the caller.
+pub async fn apply(
+    pool: &SqlitePool,
+    clip_uuid: &str,
+    outcome: &RuleOutcome,
+) -> Result<(), String> {
+    if let Some(folder_id) = outcome.folder_id {
+        // A clip filed by hand stays where it is; smart folders can't hold clips.
+        sqlx::query(
+            r#"
+            UPDATE clips SET folder_id = ?
+            WHERE uuid = ? AND folder_id IS NULL
+              AND EXISTS (SELECT 1 FROM folders WHERE id = ? AND query IS NULL)
+            "#,
+        )
+        .bind(folder_id)
+        .bind(clip_uuid)
+        .bind(folder_id)
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    }
+
+    if !outcome.tags.is_empty() {
+        let metadata: Option<String> =
+            sqlx::query_scalar("SELECT metadata FROM clips WHERE uuid = ?")
+                .bind(clip_uuid)
+                .fetch_optional(pool)
+                .await
+                .map_err(|e| e.to_string())?
+                .flatten();
+        let mut metadata: serde_json::Value = metadata
+            .and_then(|m| serde_json::from_str(&m).ok())
+            .filter(|m: &serde_json::Value| m.is_object())
+            .unwrap_or(serde_json::json!({}));
+        let mut tags: Vec<String> = metadata["tags"]
+            .as_array()
+            .map(|t| {
+                t.iter()
+                    .filter_map(|v| v.as_str().map(String::from))
+                    .collect()
+            })
+            .unwrap_or_default();
+        for tag in &outcome.tags {
+            if !tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+                tags.push(tag.clone());
+            }
+        }
+        metadata["tags"] = serde_json::json!(tags);
+        sqlx::query("UPDATE clips SET metadata = ? WHERE uuid = ?")
+            .bind(metadata.to_string())
+            .bind(clip_uuid)
+            .execute(pool)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    if outcome.sensitive {
+        sqlx::query("UPDATE clips SET is_sensitive = 1 WHERE uuid = ?")
+            .bind(clip_uuid)
+            .execute(pool)
+            .await
+            .map_err(|e| e.to_string())?;
+        sqlx::query("DELETE FROM clip_embeddings WHERE clip_uuid = ?")
+            .bind(clip_uuid)
+            .execute(pool)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(name: &str, conditions: RuleConditions, actions: Vec<RuleAction>) -> CompiledRule {
+        CompiledRule::new(RoutingRule {
+            id: 0,
+            name: name.to_string(),
+            enabled: true,
+            position: 0,
+            stop_processing: false,
+            conditions,
+            actions,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_evaluate_in_order() {
+        let rules = vec![
+            rule(
+                "terminal",
+                RuleConditions {
+                    source_app: Some("term".to_string()),
+                    ..Default::default()
+                },
+                vec![
+                    RuleAction::MoveToFolder { folder_id: 1 },
+                    RuleAction::AddTags {
+                        tags: vec!["shell".to_string()],
+                    },
+                ],
+            ),
+            rule(
+                "secrets",
+                RuleConditions {
+                    content_regex: Some(r"^sk-[A-Za-z0-9]{8,}$".to_string()),
+                    clip_types: vec!["text".to_string()],
+                    ..Default::default()
+                },
+                vec![
+                    RuleAction::MarkSensitive,
+                    RuleAction::MoveToFolder { folder_id: 2 },
+                ],
+            ),
+            rule(
+                "huge",
+                RuleConditions {
+                    min_size: Some(1024),
+                    ..Default::default()
+                },
+                vec![RuleAction::Discard],
+            ),
+        ];
+
+        let clip = ClipFacts {
+            clip_type: "text",
+            app_names: vec!["Windows Terminal", "WindowsTerminal.exe"],
+            text: "sk-abcdefgh1234",
+            size: 15,
+        };
+        let outcome = evaluate(&rules, &clip);
+        assert_eq!(outcome.matched, vec!["terminal", "secrets"]);
+        assert_eq!(outcome.folder_id, Some(2));
+        assert_eq!(outcome.tags, vec!["shell"]);
+        assert!(outcome.sensitive);
+        assert!(!outcome.discard);
+
+        let big = ClipFacts {
+            clip_type: "image",
+            app_names: vec![],
+            text: "[Image]",
+            size: 4096,
+        };
+        assert!(evaluate(&rules, &big).discard);
+    }
+
+    #[test]
+    fn test_rule_validation() {
+        let invalid = |conditions: RuleConditions, actions: Vec<RuleAction>| {
+            CompiledRule::new(RoutingRule {
+                id: 0,
+                name: "x".to_string(),
+                enabled: true,
+                position: 0,
+                stop_processing: false,
+                conditions,
+                actions,
+            })
+            .is_err()
+        };
+        assert!(invalid(RuleConditions::default(), vec![]));
+        assert!(invalid(
+            RuleConditions {
+                content_regex: Some("(".to_string()),
+                ..Default::default()
+            },
+            vec![RuleAction::Discard]
+        ));
+        assert!(invalid(
+            RuleConditions::default(),
+            vec![RuleAction::RunAi {
+                action: String::new()
+            }]
+        ));
+    }
+}
@@ -0,0 +1,457 @@
+//! Scoring and highlight ranges for the `search_clips` modes.
+//!
+//! Fuzzy matching follows fzf: a subsequence match scored with bonuses for consecutive characters and
+//! word boundaries, falling back to whole words within a small edit distance so typos still match.
+
+use crate::models::SearchMatch;
+use regex::{Regex, RegexBuilder};
+use std::ops::Range;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+/// Only this much of each clip is scanned, which also bounds the cost of a single regex run.
+pub const MAX_MATCH_BYTES: usize = 256 * 1024;
+/// Most recent clips (after filters) scored in fuzzy and regex modes.
+pub const CANDIDATE_LIMIT: i64 = 5000;
+/// Scoring stops after this long and returns what was ranked so far.
+pub const TIME_BUDGET: Duration = Duration::from_secs(2);
+
+const MAX_PATTERN_LEN: usize = 1024;
+const REGEX_SIZE_LIMIT: usize = 2 * 1024 * 1024;
+const MAX_RANGES: usize = 64;
+
+const SCORE_MATCH: i64 = 16;
+const BONUS_BOUNDARY: i64 = 8;
+const BONUS_CAMEL: i64 = 7;
+const BONUS_CONSECUTIVE: i64 = 4;
+const PENALTY_GAP_START: i64 = 3;
+const PENALTY_GAP_EXTENSION: i64 = 1;
+const PENALTY_TYPO: i64 = 10;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum SearchMode {
+    #[default]
+    Exact,
+    Fuzzy,
+    Regex,
+}
+
+impl FromStr for SearchMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "" | "exact" => Ok(SearchMode::Exact),
+            "fuzzy" => Ok(SearchMode::Fuzzy),
+            "regex" => Ok(SearchMode::Regex),
+            _ => Err(format!("Unknown search mode: {}", s)),
+        }
+    }
+}
+
+/// Score plus byte ranges into the matched text.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Scored {
+    pub score: i64,
+    pub ranges: Vec<Range<usize>>,
+}
+
+impl Scored {
+    /// Convert byte ranges to UTF-16 offsets, which is what the frontend indexes strings by.
+    pub fn into_search_match(self, text: &str, field: &str) -> SearchMatch {
+        let mut ranges = Vec::with_capacity(self.ranges.len());
+        let mut units = 0;
+        let mut byte = 0;
+        let mut chars = text.char_indices().peekable();
+
+        let mut to_utf16 = |target: usize| {
+            while byte < target {
+                match chars.next() {
+                    Some((_, c)) => {
+                        units += c.len_utf16();
+                        byte = chars.peek().map(|&(i, _)| i).unwrap_or(text.len());
+                    }
+                    None => break,
+                }
+            }
+            units
+        };
+        for range in self.ranges {
+            let start = to_utf16(range.start);
+            let end = to_utf16(range.end);
+            ranges.push([start, end]);
+        }
+
+        SearchMatch {
+            score: self.score,
+            field: field.to_string(),
+            ranges,
+        }
+    }
+}
+
+/// Cut `bytes` to [`MAX_MATCH_BYTES`] and decode it for matching.
+pub fn match_text(bytes: &[u8]) -> String {
+    let end = bytes.len().min(MAX_MATCH_BYTES);
+    String::from_utf8_lossy(&bytes[..end]).into_owned()
+}
+
+fn merge_ranges(mut ranges: Vec<Range<usize>>) -> Vec<Range<usize>> {
+    ranges.sort_by_key(|r| r.start);
+    let mut merged: Vec<Range<usize>> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+    merged.truncate(MAX_RANGES);
+    merged
+}
+
+/// Case-insensitive substring match of every term, scored by occurrence count.
+pub fn exact_match(terms: &[&str], text: &str) -> Option<Scored> {
+    let haystack = text.to_lowercase();
+    // Lowercasing can change byte lengths; only highlight when offsets still line up.
+    let aligned = haystack.len() == text.len();
+    let mut scored = Scored::default();
+
+    for term in terms {
+        let needle = term.to_lowercase();
+        if needle.is_empty() {
+            continue;
+        }
+        let mut found = false;
+        for (start, _) in haystack.match_indices(&needle) {
+            found = true;
+            scored.score += 1;
+            if aligned && scored.ranges.len() < MAX_RANGES {
+                scored.ranges.push(start..start + needle.len());
+            }
+        }
+        if !found {
+            return None;
+        }
+    }
+
+    scored.ranges = merge_ranges(scored.ranges);
+    Some(scored)
+}
+
+/// Every term must match; scores add up.
+pub fn fuzzy_match(terms: &[&str], text: &str) -> Option<Scored> {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let mut scored = Scored::default();
+
+    for term in terms {
+        let term_scored = fuzzy_term(term, text, &chars)?;
+        scored.score += term_scored.score;
+        scored.ranges.extend(term_scored.ranges);
+    }
+
+    scored.ranges = merge_ranges(scored.ranges);
+    Some(scored)
+}
+
+fn fold(c: char, case_sensitive: bool) -> char {
+    if case_sensitive {
+        c
+    } else {
+        c.to_lowercase().next().unwrap_or(c)
+    }
+}
+
+fn fuzzy_term(term: &str, text: &str, chars: &[(usize, char)]) -> Option<Scored> {
+    // Smart case, as in fzf: an uppercase letter in the term makes it case-sensitive.
+    let case_sensitive = term.chars().any(char::is_uppercase);
+    let needle: Vec<char> = term.chars().map(|c| fold(c, case_sensitive)).collect();
+    if needle.is_empty() {
+        return Some(Scored::default());
+    }
+    let folded: Vec<char> = chars
+        .iter()
+        .map(|&(_, c)| fold(c, case_sensitive))
+        .collect();
+
+    let mut best: Option<Vec<usize>> = None;
+    let mut best_score = i64::MIN;
+    let mut consider = |positions: Vec<usize>| {
+        let score = score_positions(chars, &positions);
+        if score > best_score {
+            best_score = score;
+            best = Some(positions);
+        }
+    };
+
+    if let Some(start) = folded
+        .windows(needle.len())
+        .position(|window| window == needle.as_slice())
+    {
+        consider((start..start + needle.len()).collect());
+    }
+    if let Some(positions) = subsequence_positions(&folded, &needle) {
+        consider(positions);
+    }
+
+    match best {
+        Some(positions) => {
+            let ranges = positions
+                .iter()
+                .map(|&i| {
+                    let (start, c) = chars[i];
+                    start..start + c.len_utf8()
+                })
+                .collect();
+            Some(Scored {
+                score: best_score,
+                ranges: merge_ranges(ranges),
+            })
+        }
+        None => typo_match(&needle, text, case_sensitive),
+    }
+}
+
+/// fzf v1: first occurrence of the subsequence, then shrink the window by scanning back from its end.
+fn subsequence_positions(folded: &[char], needle: &[char]) -> Option<Vec<usize>> {
+    let mut matched = 0;
+    let mut end = None;
+    for (i, &c) in folded.iter().enumerate() {
+        if c == needle[matched] {
+            matched += 1;
+            if matched == needle.len() {
+                end = Some(i);
+                break;
+            }
+        }
+    }
+    let end = end?;
+
+    let mut positions = Vec::with_capacity(needle.len());
+    let mut remaining = needle.len();
+    for i in (0..=end).rev() {
+        if folded[i] == needle[remaining - 1] {
+            positions.push(i);
+            remaining -= 1;
+            if remaining == 0 {
+                break;
+            }
+        }
+    }
+    positions.reverse();
+    Some(positions)
+}
+
+fn score_positions(chars: &[(usize, char)], positions: &[usize]) -> i64 {
+    let mut score = 0;
+    let mut previous: Option<usize> = None;
+
+    for (k, &pos) in positions.iter().enumerate() {
+        let current = chars[pos].1;
+        let before = pos.checked_sub(1).map(|i| chars[i].1);
+        let mut bonus = match before {
+            None => BONUS_BOUNDARY,
+            Some(b) if !b.is_alphanumeric() => BONUS_BOUNDARY,
+            Some(b) if b.is_lowercase() && current.is_uppercase() => BONUS_CAMEL,
+            _ => 0,
+        };
+        if k == 0 {
+            bonus *= 2;
+        }
+        if let Some(prev) = previous {
+            if pos == prev + 1 {
+                bonus = bonus.max(BONUS_CONSECUTIVE);
+            } else {
+                let gap = (pos - prev - 1) as i64;
+                score -= PENALTY_GAP_START + (gap - 1) * PENALTY_GAP_EXTENSION;
+            }
+        }
+        score += SCORE_MATCH + bonus;
+        previous = Some(pos);
+    }
+
+    score
+}
+
+/// Closest word (or word prefix) within 1 edit for short terms and 2 for long ones.
+fn typo_match(needle: &[char], text: &str, case_sensitive: bool) -> Option<Scored> {
+    let allowed = match needle.len() {
+        0..=3 => return None,
+        4..=7 => 1,
+        _ => 2,
+    };
+
+    let mut best: Option<(usize, Range<usize>)> = None;
+    let mut word_start: Option<usize> = None;
+    let mut word: Vec<char> = Vec::new();
+
+    let check = |word: &[char], range: Range<usize>, best: &mut Option<(usize, Range<usize>)>| {
+        if word.len() + allowed < needle.len() {
+            return;
+        }
+        let prefix = &word[..word.len().min(needle.len() + allowed)];
+        let distance = (needle.len().saturating_sub(allowed)..=prefix.len())
+            .map(|len| edit_distance(needle, &prefix[..len]))
+            .min()
+            .unwrap_or(usize::MAX);
+        if distance <= allowed && !matches!(best, Some((d, _)) if *d <= distance) {
+            *best = Some((distance, range));
+        }
+    };
+
+    for (i, c) in text.char_indices() {
+        if c.is_alphanumeric() {
+            word_start.get_or_insert(i);
+            word.push(fold(c, case_sensitive));
+        } else if let Some(start) = word_start.take() {
+            check(&word, start..i, &mut best);
+            word.clear();
+        }
+    }
+    if let Some(start) = word_start {
+        check(&word, start..text.len(), &mut best);
+    }
+
+    best.map(|(distance, range)| Scored {
+        score: SCORE_MATCH * needle.len() as i64 / 2 - PENALTY_TYPO * distance as i64,
+        ranges: vec![range],
+    })
+}
+
+/// Optimal string alignment distance (Levenshtein plus adjacent transpositions).
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut rows = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    rows[0] = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut value = (rows[i - 1][j] + 1)
+                .min(rows[i][j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                value = value.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = value;
+        }
+    }
+    rows[a.len()][b.len()]
+}
+
+/// The regex crate runs in linear time; the size limits stop huge patterns from compiling.
+pub fn compile_regex(pattern: &str) -> Result<Regex, String> {
+    if pattern.len() > MAX_PATTERN_LEN {
+        return Err(format!(
+            "Regex is too long (max {} characters)",
+            MAX_PATTERN_LEN
+        ));
+    }
+    RegexBuilder::new(pattern)
+        .size_limit(REGEX_SIZE_LIMIT)
+        .dfa_size_limit(REGEX_SIZE_LIMIT)
+        .build()
+        .map_err(|e| format!("Invalid regex: {}", e))
+}
+
+/// Scored by the number of non-empty matches.
+pub fn regex_match(regex: &Regex, text: &str) -> Option<Scored> {
+    if !regex.is_match(text) {
+        return None;
+    }
+    let mut scored = Scored::default();
+    for m in regex.find_iter(text).filter(|m| !m.is_empty()) {
+        scored.score += 1;
+        if scored.ranges.len() == MAX_RANGES {
+            break;
+        }
+        scored.ranges.push(m.range());
+    }
+    Some(scored)
+}
+
+pub enum Matcher {
+    Fuzzy(Vec<String>),
+    Regex(Regex),
+}
+
+/// Score `(uuid, clip_type, text)` candidates, given newest first, best match first.
+/// Stops early once [`TIME_BUDGET`] is spent.
+pub fn rank(
+    candidates: Vec<(String, String, Vec<u8>)>,
+    matcher: &Matcher,
+) -> Vec<(String, SearchMatch)> {
+    let started = Instant::now();
+    let total = candidates.len();
+    let mut ranked = Vec::new();
+
+    for (index, (uuid, clip_type, bytes)) in candidates.into_iter().enumerate() {
+        if started.elapsed() > TIME_BUDGET {
+            log::warn!(
+                "[search] Time budget exceeded, ranked {} of {} candidates",
+                index,
+                total
+            );
+            break;
+        }
+        let text = match_text(&bytes);
+        let scored = match matcher {
+            Matcher::Fuzzy(terms) => {
+                let terms: Vec<&str> = terms.iter().map(String::as_str).collect();
+                fuzzy_match(&terms, &text)
+            }
+            Matcher::Regex(regex) => regex_match(regex, &text),
+        };
+        if let Some(scored) = scored {
+            let field = if clip_type == "image" {
+                "preview"
+            } else {
+                "content"
+            };
+            ranked.push((uuid, scored.into_search_match(&text, field)));
+        }
+    }
+
+    // Stable, so equal scores stay newest first.
+    ranked.sort_by_key(|(_, search_match)| std::cmp::Reverse(search_match.score));
+    ranked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_ranks_boundaries_and_substrings() {
+        let contiguous = fuzzy_match(&["cfg"], "load cfg file").unwrap();
+        let scattered = fuzzy_match(&["cfg"], "checking fragments").unwrap();
+        assert!(contiguous.score > scattered.score);
+        assert_eq!(contiguous.ranges, vec![5..8]);
+        assert!(fuzzy_match(&["xyz"], "checking fragments").is_none());
+    }
+
+    #[test]
+    fn test_fuzzy_tolerates_typos() {
+        let scored = fuzzy_match(&["recieve"], "please receive the files").unwrap();
+        assert_eq!(scored.ranges, vec![7..14]);
+        assert!(fuzzy_match(&["abc"], "acb").is_none());
+    }
+
+    #[test]
+    fn test_utf16_ranges() {
+        let text = "😀 héllo";
+        let scored = exact_match(&["héllo"], text).unwrap();
+        let search_match = scored.into_search_match(text, "content");
+        assert_eq!(search_match.ranges, vec![[3, 8]]);
+    }
+
+    #[test]
+    fn test_regex_limits() {
+        assert!(compile_regex(&"a".repeat(MAX_PATTERN_LEN + 1)).is_err());
+        assert!(compile_regex("(").is_err());
+        let regex = compile_regex(r"\d+").unwrap();
+        let scored = regex_match(&regex, "a1 b22").unwrap();
+        assert_eq!(scored.score, 2);
+        assert_eq!(scored.ranges, vec![1..2, 4..6]);
+    }
+}
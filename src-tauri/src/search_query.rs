@@ -0,0 +1,573 @@
+//! Structured search queries for `search_clips`, e.g.
+//! `type:image app:Chrome folder:SQL after:2026-09-01 "exact phrase" -excluded`.
+//!
+//! Smart folders save one of these and re-parse it on every read, so relative dates like `after:7d` stay current.
+//!
+//! Queries compile to a parameterized `WHERE` fragment over `clips`; user input is only ever bound.
+
+use crate::search_match;
+use chrono::{NaiveDate, TimeZone};
+use serde::Serialize;
+use std::fmt;
+
+pub const CLIP_TYPES: [&str; 6] = ["text", "image", "html", "rtf", "file", "url"];
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct QueryError {
+    pub message: String,
+    /// Character offset of the offending term in the query.
+    pub position: usize,
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Invalid search query at position {}: {}",
+            self.position, self.message
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Comparison {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Equal,
+}
+
+impl Comparison {
+    fn sql(self) -> &'static str {
+        match self {
+            Comparison::Less => "<",
+            Comparison::LessOrEqual => "<=",
+            Comparison::Greater => ">",
+            Comparison::GreaterOrEqual => ">=",
+            Comparison::Equal => "=",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    /// Substring match on the clip text.
+    Text(String),
+    Type(String),
+    /// Substring match on the source app name.
+    App(String),
+    /// Folder by name; `None` means unfiled clips.
+    Folder(Option<String>),
+    /// UTC timestamps (`YYYY-MM-DD HH:MM:SS`) for the start of the given local day, or `now - N` for `7d`-style values.
+    After(String),
+    Before(String),
+    Size(Comparison, i64),
+    /// `Some(key)` for a specific metadata key, `None` for any AI result.
+    HasAi(Option<String>),
+    /// Regex over the clip text; needs the `REGEXP` function registered on the connection.
+    Regex(String),
+    /// Exact tag from `metadata.tags`, ignoring case.
+    Tag(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Clause {
+    pub negated: bool,
+    pub filter: Filter,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SqlArg {
+    Text(String),
+    Int(i64),
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SearchQuery {
+    pub clauses: Vec<Clause>,
+}
+
+impl SearchQuery {
+    pub fn is_empty(&self) -> bool {
+        self.clauses.is_empty()
+    }
+
+    /// Plain text terms, for callers that rank or highlight matches.
+    pub fn text_terms(&self) -> Vec<&str> {
+        self.clauses
+            .iter()
+            .filter_map(|c| match (&c.filter, c.negated) {
+                (Filter::Text(t), false) => Some(t.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The same query minus its plain text terms, for modes that match text outside SQL.
+    pub fn without_text_terms(&self) -> SearchQuery {
+        SearchQuery {
+            clauses: self
+                .clauses
+                .iter()
+                .filter(|c| c.negated || !matches!(c.filter, Filter::Text(_)))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// Both queries' clauses, e.g. a search inside a smart folder.
+    pub fn and(mut self, other: &SearchQuery) -> SearchQuery {
+        self.clauses.extend(other.clauses.iter().cloned());
+        self
+    }
+
+    /// `WHERE` fragment (without the keyword) over the `clips` table, plus its bind values in order.
+    pub fn to_sql(&self) -> (String, Vec<SqlArg>) {
+        if self.clauses.is_empty() {
+            return ("1 = 1".to_string(), Vec::new());
+        }
+
+        let mut args = Vec::new();
+        let parts: Vec<String> = self
+            .clauses
+            .iter()
+            .map(|clause| {
+                let sql = filter_sql(&clause.filter, &mut args);
+                if clause.negated {
+                    format!("NOT ({})", sql)
+                } else {
+                    format!("({})", sql)
+                }
+            })
+            .collect();
+        (parts.join(" AND "), args)
+    }
+}
+
+type SqliteQueryAs<'q, O> =
+    sqlx::query::QueryAs<'q, sqlx::Sqlite, O, sqlx::sqlite::SqliteArguments<'q>>;
+
+/// Bind the values from [`SearchQuery::to_sql`] in order.
+pub fn bind_args<'q, O>(
+    mut query: SqliteQueryAs<'q, O>,
+    args: &'q [SqlArg],
+) -> SqliteQueryAs<'q, O> {
+    for arg in args {
+        query = match arg {
+            SqlArg::Text(value) => query.bind(value),
+            SqlArg::Int(value) => query.bind(*value),
+        };
+    }
+    query
+}
+
+fn like_pattern(value: &str) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{}%", escaped)
+}
+
+fn filter_sql(filter: &Filter, args: &mut Vec<SqlArg>) -> String {
+    match filter {
+        Filter::Text(text) => {
+            let pattern = like_pattern(text);
+            args.push(SqlArg::Text(pattern.clone()));
+            args.push(SqlArg::Text(pattern));
+            r#"COALESCE(clips.text_preview, '') LIKE ? ESCAPE '\' OR CAST(clips.content AS TEXT) LIKE ? ESCAPE '\'"#
+                .to_string()
+        }
+        Filter::Type(clip_type) => {
+            args.push(SqlArg::Text(clip_type.clone()));
+            "clips.clip_type = ?".to_string()
+        }
+        Filter::App(app) => {
+            args.push(SqlArg::Text(like_pattern(app)));
+            r#"COALESCE(clips.source_app, '') LIKE ? ESCAPE '\'"#.to_string()
+        }
+        Filter::Folder(Some(name)) => {
+            args.push(SqlArg::Text(name.clone()));
+            "clips.folder_id IS NOT NULL AND clips.folder_id IN (SELECT id FROM folders WHERE name = ? COLLATE NOCASE)"
+                .to_string()
+        }
+        Filter::Folder(None) => "clips.folder_id IS NULL".to_string(),
+        Filter::After(ts) => {
+            args.push(SqlArg::Text(ts.clone()));
+            "datetime(clips.created_at) >= datetime(?)".to_string()
+        }
+        Filter::Before(ts) => {
+            args.push(SqlArg::Text(ts.clone()));
+            "datetime(clips.created_at) < datetime(?)".to_string()
+        }
+        Filter::Size(cmp, bytes) => {
+            args.push(SqlArg::Int(*bytes));
+            format!(
+                "length(clips.content) + COALESCE((SELECT file_size FROM clip_images WHERE clip_uuid = clips.uuid), 0) {} ?",
+                cmp.sql()
+            )
+        }
+        Filter::HasAi(Some(key)) => {
+            args.push(SqlArg::Text(format!("$.{}", key)));
+            "CASE WHEN json_valid(clips.metadata) THEN json_extract(clips.metadata, ?) IS NOT NULL ELSE 0 END"
+                .to_string()
+        }
+        Filter::HasAi(None) => {
+            r#"CASE WHEN json_valid(clips.metadata) THEN EXISTS (SELECT 1 FROM json_each(clips.metadata) WHERE key LIKE 'ai\_%' ESCAPE '\') ELSE 0 END"#
+                .to_string()
+        }
+        Filter::Tag(tag) => {
+            args.push(SqlArg::Text(tag.clone()));
+            "CASE WHEN json_valid(clips.metadata) THEN EXISTS (SELECT 1 FROM json_each(clips.metadata, '$.tags') WHERE value = ? COLLATE NOCASE) ELSE 0 END"
+                .to_string()
+        }
+        Filter::Regex(pattern) => {
+            args.push(SqlArg::Text(pattern.clone()));
+            // Only text-like content is guaranteed to be valid UTF-8, which REGEXP requires.
+            format!(
+                "CASE WHEN clips.clip_type IN ('text', 'url', 'html', 'rtf') \
+                 THEN substr(CAST(clips.content AS TEXT), 1, {}) \
+                 ELSE COALESCE(clips.text_preview, '') END REGEXP ?",
+                search_match::MAX_MATCH_BYTES
+            )
+        }
+    }
+}
+
+struct Token {
+    negated: bool,
+    key: Option<String>,
+    value: String,
+    quoted: bool,
+    position: usize,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, QueryError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        if chars[i].is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        let position = i;
+        let mut negated = false;
+        if chars[i] == '-' && i + 1 < chars.len() && !chars[i + 1].is_whitespace() {
+            negated = true;
+            i += 1;
+        }
+
+        let mut key = None;
+        let mut value = String::new();
+        let mut quoted = false;
+
+        loop {
+            if i >= chars.len() || chars[i].is_whitespace() {
+                break;
+            }
+            if chars[i] == '"' {
+                let start = i;
+                i += 1;
+                let mut phrase = String::new();
+                while i < chars.len() && chars[i] != '"' {
+                    phrase.push(chars[i]);
+                    i += 1;
+                }
+                if i >= chars.len() {
+                    return Err(QueryError {
+                        message: "Unterminated quote".to_string(),
+                        position: start,
+                    });
+                }
+                i += 1;
+                value.push_str(&phrase);
+                quoted = true;
+                continue;
+            }
+            if chars[i] == ':' && key.is_none() && !quoted && !value.is_empty() {
+                key = Some(std::mem::take(&mut value));
+                i += 1;
+                continue;
+            }
+            value.push(chars[i]);
+            i += 1;
+        }
+
+        tokens.push(Token {
+            negated,
+            key,
+            value,
+            quoted,
+            position,
+        });
+    }
+
+    Ok(tokens)
+}
+
+fn parse_relative(value: &str) -> Option<chrono::Duration> {
+    let split = value.find(|c: char| !c.is_ascii_digit())?;
+    let (number, unit) = value.split_at(split);
+    let number: i64 = number.parse().ok()?;
+    match unit.to_lowercase().as_str() {
+        "h" => Some(chrono::Duration::hours(number)),
+        "d" => Some(chrono::Duration::days(number)),
+        "w" => Some(chrono::Duration::weeks(number)),
+        _ => None,
+    }
+}
+
+fn parse_date(value: &str, position: usize) -> Result<String, QueryError> {
+    if let Some(ago) = parse_relative(value) {
+        let ts = chrono::Utc::now().naive_utc() - ago;
+        return Ok(ts.format("%Y-%m-%d %H:%M:%S").to_string());
+    }
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| QueryError {
+        message: format!("Expected a date like 2026-09-01 or 7d, got '{}'", value),
+        position,
+    })?;
+    let midnight = date.and_hms_opt(0, 0, 0).unwrap_or_default();
+    // Clips are stored in UTC; the user means their local day.
+    let utc = chrono::Local
+        .from_local_datetime(&midnight)
+        .earliest()
+        .map(|dt| dt.with_timezone(&chrono::Utc).naive_utc())
+        .unwrap_or(midnight);
+    Ok(utc.format("%Y-%m-%d %H:%M:%S").to_string())
+}
+
+fn parse_size(value: &str, position: usize) -> Result<(Comparison, i64), QueryError> {
+    let (cmp, rest) = if let Some(rest) = value.strip_prefix(">=") {
+        (Comparison::GreaterOrEqual, rest)
+    } else if let Some(rest) = value.strip_prefix("<=") {
+        (Comparison::LessOrEqual, rest)
+    } else if let Some(rest) = value.strip_prefix('>') {
+        (Comparison::Greater, rest)
+    } else if let Some(rest) = value.strip_prefix('<') {
+        (Comparison::Less, rest)
+    } else if let Some(rest) = value.strip_prefix('=') {
+        (Comparison::Equal, rest)
+    } else {
+        (Comparison::GreaterOrEqual, value)
+    };
+
+    let lower = rest.trim().to_lowercase();
+    let split = lower
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(lower.len());
+    let (number, unit) = lower.split_at(split);
+    let multiplier: f64 = match unit {
+        "" | "b" => 1.0,
+        "k" | "kb" => 1024.0,
+        "m" | "mb" => 1024.0 * 1024.0,
+        "g" | "gb" => 1024.0 * 1024.0 * 1024.0,
+        _ => {
+            return Err(QueryError {
+                message: format!("Unknown size unit '{}', use b, kb, mb or gb", unit),
+                position,
+            })
+        }
+    };
+    let number: f64 = number.parse().map_err(|_| QueryError {
+        message: format!("Expected a size like >10kb, got '{}'", value),
+        position,
+    })?;
+    Ok((cmp, (number * multiplier).round() as i64))
+}
+
+/// `has:ai` for any AI result, or one AI action's result by id. The former built-in
+/// actions also go by the name of their result (`summary`, `translation`...).
+fn parse_has(value: &str, position: usize) -> Result<Filter, QueryError> {
+    let value = value.to_lowercase();
+    let action_id = match value.as_str() {
+        "ai" => return Ok(Filter::HasAi(None)),
+        "summary" => "summarize",
+        "translation" => "translate",
+        "explanation" => "explain_code",
+        "grammar" => "fix_grammar",
+        id if !id.is_empty()
+            && id
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_') =>
+        {
+            id
+        }
+        _ => {
+            return Err(QueryError {
+                message: format!(
+                    "Unknown has: value '{}', use ai, an AI action id, summary, translation, explanation or grammar",
+                    value
+                ),
+                position,
+            })
+        }
+    };
+    Ok(Filter::HasAi(Some(crate::ai_actions::metadata_key(
+        action_id,
+    ))))
+}
+
+pub fn parse(input: &str) -> Result<SearchQuery, QueryError> {
+    let mut clauses = Vec::new();
+
+    for token in tokenize(input)? {
+        let position = token.position;
+        let filter = match token.key.as_deref().map(|k| k.to_lowercase()) {
+            None => {
+                if token.value.is_empty() {
+                    continue;
+                }
+                Filter::Text(token.value)
+            }
+            Some(key) => {
+                if token.value.is_empty() && !token.quoted {
+                    return Err(QueryError {
+                        message: format!("Missing value for '{}:'", key),
+                        position,
+                    });
+                }
+                match key.as_str() {
+                    "type" | "is" => {
+                        let value = match token.value.to_lowercase().as_str() {
+                            "img" | "picture" => "image".to_string(),
+                            "link" => "url".to_string(),
+                            other => other.to_string(),
+                        };
+                        if !CLIP_TYPES.contains(&value.as_str()) {
+                            return Err(QueryError {
+                                message: format!(
+                                    "Unknown clip type '{}', use one of: {}",
+                                    token.value,
+                                    CLIP_TYPES.join(", ")
+                                ),
+                                position,
+                            });
+                        }
+                        Filter::Type(value)
+                    }
+                    "app" | "from" => Filter::App(token.value),
+                    "folder" | "in" => {
+                        if !token.quoted && token.value.eq_ignore_ascii_case("none") {
+                            Filter::Folder(None)
+                        } else {
+                            Filter::Folder(Some(token.value))
+                        }
+                    }
+                    "after" | "since" => Filter::After(parse_date(&token.value, position)?),
+                    "before" | "until" => Filter::Before(parse_date(&token.value, position)?),
+                    "size" => {
+                        let (cmp, bytes) = parse_size(&token.value, position)?;
+                        Filter::Size(cmp, bytes)
+                    }
+                    "has" => parse_has(&token.value, position)?,
+                    "tag" => Filter::Tag(token.value),
+                    "regex" => {
+                        search_match::compile_regex(&token.value)
+                            .map_err(|message| QueryError { message, position })?;
+                        Filter::Regex(token.value)
+                    }
+                    // Not a filter (e.g. "http://..." or "12:30"): search it as text.
+                    _ => Filter::Text(format!("{}:{}", key, token.value)),
+                }
+            }
+        };
+        clauses.push(Clause {
+            negated: token.negated,
+            filter,
+        });
+    }
+
+    Ok(SearchQuery { clauses })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filters(input: &str) -> Vec<(bool, Filter)> {
+        parse(input)
+            .unwrap()
+            .clauses
+            .into_iter()
+            .map(|c| (c.negated, c.filter))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_filters_and_phrases() {
+        let parsed =
+            filters(r#"type:image app:"Google Chrome" folder:SQL "exact phrase" -excluded"#);
+        assert_eq!(
+            parsed,
+            vec![
+                (false, Filter::Type("image".to_string())),
+                (false, Filter::App("Google Chrome".to_string())),
+                (false, Filter::Folder(Some("SQL".to_string()))),
+                (false, Filter::Text("exact phrase".to_string())),
+                (true, Filter::Text("excluded".to_string())),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_size_and_has() {
+        assert_eq!(
+            filters("size:>10kb has:summary -has:ai has:To_Spanish"),
+            vec![
+                (false, Filter::Size(Comparison::Greater, 10 * 1024)),
+                (false, Filter::HasAi(Some("ai_summary".to_string()))),
+                (true, Filter::HasAi(None)),
+                (false, Filter::HasAi(Some("ai_to_spanish".to_string()))),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_relative_dates_and_regex() {
+        let parsed = filters(r#"after:7d before:24h regex:"^\d{3}-\d{4}$""#);
+        assert!(matches!(parsed[0].1, Filter::After(_)));
+        assert!(matches!(parsed[1].1, Filter::Before(_)));
+        assert_eq!(parsed[2].1, Filter::Regex(r"^\d{3}-\d{4}$".to_string()));
+        assert!(parse("regex:(unclosed").is_err());
+        assert!(parse("after:7y").is_err());
+        assert_eq!(filters("tag:work")[0].1, Filter::Tag("work".to_string()));
+    }
+
+    #[test]
+    fn test_unknown_key_is_text() {
+        assert_eq!(
+            filters("https://example.com"),
+            vec![(false, Filter::Text("https://example.com".to_string()))]
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(parse(r#"foo "bar"#).unwrap_err().position, 4);
+        assert!(parse("type:video").is_err());
+        assert!(parse("after:yesterday").is_err());
+        assert!(parse("size:>10tb").is_err());
+        assert!(parse("app:").is_err());
+    }
+
+    #[test]
+    fn test_to_sql_binds_in_order() {
+        let (sql, args) = parse("-type:text foo").unwrap().to_sql();
+        assert_eq!(
+            sql,
+            r#"NOT (clips.clip_type = ?) AND (COALESCE(clips.text_preview, '') LIKE ? ESCAPE '\' OR CAST(clips.content AS TEXT) LIKE ? ESCAPE '\')"#
+        );
+        assert_eq!(
+            args,
+            vec![
+                SqlArg::Text("text".to_string()),
+                SqlArg::Text("%foo%".to_string()),
+                SqlArg::Text("%foo%".to_string()),
+            ]
+        );
+    }
+}
@@ -49,6 +49,7 @@ pub async fn save_settings(app: AppHandle, settings: serde_json::Value) -> Resul
     // Preserve ignored_apps from current state (as frontend doesn't send it in this call)
     let current = manager.get();
     new_settings.ignored_apps = current.ignored_apps;
+    crate::ai_actions::validate(&new_settings.ai_actions)?;
 
     // Window effect
     let theme_str = new_settings.theme.clone();
@@ -111,6 +112,8 @@ pub async fn save_settings(app: AppHandle, settings: serde_json::Value) -> Resul
     }
 
     manager.save(new_settings)?;
+    // Picks up a newly enabled semantic search or a changed embedding model.
+    crate::embeddings::wake();
     Ok(())
 }
 
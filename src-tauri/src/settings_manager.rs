@@ -1,44 +1,85 @@
 use crate::database::Database;
 use crate::models::AppSettings;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::RwLock;
 use tauri::AppHandle;
 use tauri::Manager;
 
 pub struct SettingsManager {
-    file_path: PathBuf,
+    file_path: RwLock<PathBuf>,
     settings: RwLock<AppSettings>,
 }
 
 impl SettingsManager {
     pub async fn new(app: &AppHandle, db: &Database) -> Self {
-        let path = app.path().app_data_dir().unwrap().join("settings.json");
+        let path = crate::data_dir::settings_path();
+        // Older versions kept settings.json in Tauri's app_data_dir, apart from the DB.
+        let legacy_path = app
+            .path()
+            .app_data_dir()
+            .ok()
+            .map(|dir| dir.join(crate::data_dir::SETTINGS_FILE_NAME))
+            .filter(|p| *p != path && p.exists());
+
         let settings = if path.exists() {
             // Load from file
-            match fs::read_to_string(&path) {
-                Ok(content) => serde_json::from_str(&content).unwrap_or_default(),
-                Err(_) => AppSettings::default(),
-            }
+            Self::load_file(&path)
+        } else if let Some(legacy) = &legacy_path {
+            log::info!("Migrating settings from legacy location {:?}", legacy);
+            Self::load_file(legacy)
         } else {
             // Migrate from SQLite or use default
             Self::migrate_from_sqlite(db).await
         };
 
         // Ensure we save it once immediately if migrating, so file exists
+        let exists = path.exists();
         let manager = Self {
-            file_path: path,
+            file_path: RwLock::new(path),
             settings: RwLock::new(settings.clone()),
         };
-        if !manager.file_path.exists() {
+        if !exists {
             let _ = manager.save(settings);
         }
         manager
     }
 
+    /// Settings for another profile's file, written out first if there isn't one yet. A
+    /// profile without one starts from the current settings so AI keys, theme and hotkey
+    /// carry over.
+    pub fn load_profile_file(&self, path: &Path) -> Result<AppSettings, String> {
+        if path.exists() {
+            return Ok(Self::load_file(path));
+        }
+        let settings = self.get();
+        let json = serde_json::to_string_pretty(&settings).map_err(|e| e.to_string())?;
+        fs::write(path, json).map_err(|e| e.to_string())?;
+        Ok(settings)
+    }
+
+    /// Switch to another profile's settings file, loaded by `load_profile_file`.
+    pub fn switch_file(&self, path: PathBuf, settings: AppSettings) {
+        *self.file_path.write().unwrap() = path;
+        *self.settings.write().unwrap() = settings;
+    }
+
+    fn load_file(path: &Path) -> AppSettings {
+        match fs::read_to_string(path) {
+            Ok(content) => match serde_json::from_str(&content) {
+                Ok(mut value) => {
+                    crate::ai_actions::migrate_legacy(&mut value);
+                    serde_json::from_value(value).unwrap_or_default()
+                }
+                Err(_) => AppSettings::default(),
+            },
+            Err(_) => AppSettings::default(),
+        }
+    }
+
     async fn migrate_from_sqlite(db: &Database) -> AppSettings {
         let mut settings = AppSettings::default();
-        let pool = &db.pool;
+        let pool = &db.pool();
 
         async fn get_val(pool: &sqlx::SqlitePool, key: &str) -> Option<String> {
             sqlx::query_scalar::<_, String>("SELECT value FROM settings WHERE key = ?")
@@ -97,31 +138,15 @@ impl SettingsManager {
             settings.ai_base_url = v;
         }
 
-        if let Some(v) = get_val(pool, "ai_prompt_summarize").await {
-            settings.ai_prompt_summarize = v;
-        }
-        if let Some(v) = get_val(pool, "ai_prompt_translate").await {
-            settings.ai_prompt_translate = v;
-        }
-        if let Some(v) = get_val(pool, "ai_prompt_explain_code").await {
-            settings.ai_prompt_explain_code = v;
-        }
-        if let Some(v) = get_val(pool, "ai_prompt_fix_grammar").await {
-            settings.ai_prompt_fix_grammar = v;
-        }
-
-        if let Some(v) = get_val(pool, "ai_title_summarize").await {
-            settings.ai_title_summarize = v;
-        }
-        if let Some(v) = get_val(pool, "ai_title_translate").await {
-            settings.ai_title_translate = v;
-        }
-        if let Some(v) = get_val(pool, "ai_title_explain_code").await {
-            settings.ai_title_explain_code = v;
-        }
-        if let Some(v) = get_val(pool, "ai_title_fix_grammar").await {
-            settings.ai_title_fix_grammar = v;
+        let mut legacy_ai = std::collections::HashMap::new();
+        for id in ["summarize", "translate", "explain_code", "fix_grammar"] {
+            for key in [format!("ai_title_{}", id), format!("ai_prompt_{}", id)] {
+                if let Some(v) = get_val(pool, &key).await {
+                    legacy_ai.insert(key, v);
+                }
+            }
         }
+        settings.ai_actions = crate::ai_actions::from_legacy(|key| legacy_ai.get(key).cloned());
 
         // Ignored Apps
         if let Ok(apps) = sqlx::query_scalar::<_, String>("SELECT app_name FROM ignored_apps")
@@ -145,7 +170,8 @@ impl SettingsManager {
         }
         // TODO - what happens if multiple threads call save at the same time?
         let json = serde_json::to_string_pretty(&new_settings).map_err(|e| e.to_string())?;
-        fs::write(&self.file_path, json).map_err(|e| e.to_string())?;
+        let path = self.file_path.read().unwrap().clone();
+        fs::write(path, json).map_err(|e| e.to_string())?;
 
         Ok(())
     }
@@ -0,0 +1,328 @@
+//! Global shortcuts bound to actions: the window hotkey from `hotkey` plus the bindings in
+//! `shortcuts`. Only the shortcuts registered here are unregistered on re-registration, so the
+//! paste queue and quick paste hotkeys stay bound.
+
+use crate::database::Database;
+use crate::models::{AppSettings, ShortcutAction, ShortcutBinding};
+use crate::settings_manager::SettingsManager;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+
+/// Shortcuts registered by `register_all`, and how each binding fared.
+static REGISTERED: Lazy<parking_lot::Mutex<(Vec<Shortcut>, Vec<BindingStatus>)>> =
+    Lazy::new(|| parking_lot::Mutex::new((Vec::new(), Vec::new())));
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BindingStatus {
+    pub hotkey: String,
+    pub action: ShortcutAction,
+    /// Why the binding isn't active: a conflict, a bad hotkey or a failed registration.
+    pub error: Option<String>,
+}
+
+/// Canonical form of a hotkey, so "shift+ctrl+KeyV" and "Ctrl+Shift+V" compare equal.
+pub fn normalize_hotkey(hotkey: &str) -> String {
+    let mut modifiers = Vec::new();
+    let mut key = String::new();
+    for token in hotkey
+        .split('+')
+        .map(|t| t.trim().to_ascii_uppercase())
+        .filter(|t| !t.is_empty())
+    {
+        let modifier = match token.as_str() {
+            "CTRL" | "CONTROL" => Some("CTRL"),
+            "ALT" | "OPTION" => Some("ALT"),
+            "SHIFT" => Some("SHIFT"),
+            "SUPER" | "CMD" | "COMMAND" | "META" => Some("SUPER"),
+            "CMDORCTRL" | "CMDORCONTROL" | "COMMANDORCTRL" | "COMMANDORCONTROL" => {
+                Some(if cfg!(target_os = "macos") {
+                    "SUPER"
+                } else {
+                    "CTRL"
+                })
+            }
+            _ => None,
+        };
+        match modifier {
+            Some(modifier) => modifiers.push(modifier),
+            None => {
+                key = ["KEY", "DIGIT"]
+                    .iter()
+                    .find_map(|prefix| token.strip_prefix(prefix))
+                    .filter(|k| k.len() == 1)
+                    .unwrap_or(&token)
+                    .to_string()
+            }
+        }
+    }
+    modifiers.sort_unstable();
+    modifiers.dedup();
+    modifiers.push(&key);
+    modifiers.join("+")
+}
+
+/// For each binding, why it conflicts with an earlier one or with a hotkey in `reserved`
+/// (hotkey, what it does), if it does.
+pub fn find_conflicts(
+    bindings: &[ShortcutBinding],
+    reserved: &[(String, String)],
+) -> Vec<Option<String>> {
+    let mut taken: HashMap<String, String> = reserved
+        .iter()
+        .filter(|(hotkey, _)| !hotkey.trim().is_empty())
+        .map(|(hotkey, what)| (normalize_hotkey(hotkey), what.clone()))
+        .collect();
+    bindings
+        .iter()
+        .map(|binding| {
+            if binding.hotkey.trim().is_empty() {
+                return Some("No hotkey set".to_string());
+            }
+            let key = normalize_hotkey(&binding.hotkey);
+            match taken.get(&key) {
+                Some(what) => Some(format!("{} is already used by {}", binding.hotkey, what)),
+                None => {
+                    taken.insert(key, format!("{:?}", binding.action));
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+/// The window hotkey first, then the user's bindings.
+fn bindings(settings: &AppSettings) -> Vec<ShortcutBinding> {
+    std::iter::once(ShortcutBinding {
+        hotkey: settings.hotkey.clone(),
+        action: ShortcutAction::ToggleWindow,
+    })
+    .chain(settings.shortcuts.iter().cloned())
+    .collect()
+}
+
+/// Hotkeys bound outside this module.
+fn reserved(settings: &AppSettings) -> Vec<(String, String)> {
+    let mut reserved = vec![(
+        settings.paste_queue_hotkey.clone(),
+        "the paste queue".to_string(),
+    )];
+    let modifiers = settings.quick_paste_modifiers.trim();
+    if !modifiers.is_empty() {
+        reserved
+            .extend((1..=9).map(|n| (format!("{}+{}", modifiers, n), "quick paste".to_string())));
+    }
+    reserved
+}
+
+/// Replace the shortcuts registered here with those in `settings`. A binding that conflicts
+/// or fails to register is skipped and reported in its status; the others are still bound.
+pub fn register_all(app: &AppHandle, settings: &AppSettings) -> Vec<BindingStatus> {
+    let mut registered = REGISTERED.lock();
+    for shortcut in registered.0.drain(..) {
+        let _ = app.global_shortcut().unregister(shortcut);
+    }
+
+    let bindings = bindings(settings);
+    let conflicts = find_conflicts(&bindings, &reserved(settings));
+    let mut statuses = Vec::with_capacity(bindings.len());
+    for (binding, conflict) in bindings.into_iter().zip(conflicts) {
+        let res = match conflict {
+            Some(conflict) => Err(conflict),
+            None => register(app, &binding),
+        };
+        let error = match res {
+            Ok(shortcut) => {
+                registered.0.push(shortcut);
+                None
+            }
+            Err(e) => {
+                log::warn!("Shortcut {} not registered: {}", binding.hotkey, e);
+                Some(e)
+            }
+        };
+        statuses.push(BindingStatus {
+            hotkey: binding.hotkey,
+            action: binding.action,
+            error,
+        });
+    }
+    registered.1 = statuses.clone();
+    statuses
+}
+
+/// How each binding fared at the last `register_all`.
+pub fn statuses() -> Vec<BindingStatus> {
+    REGISTERED.lock().1.clone()
+}
+
+fn register(app: &AppHandle, binding: &ShortcutBinding) -> Result<Shortcut, String> {
+    let shortcut =
+        Shortcut::from_str(&binding.hotkey).map_err(|e| format!("Invalid hotkey: {:?}", e))?;
+    // Pasting waits for release so the shortcut's own keys are up before the paste keystroke
+    let state = if binding.action == ShortcutAction::PasteLast {
+        ShortcutState::Released
+    } else {
+        ShortcutState::Pressed
+    };
+    let action = binding.action.clone();
+    app.global_shortcut()
+        .on_shortcut(shortcut, move |app, _shortcut, event| {
+            if event.state() == state {
+                run(app, &action);
+            }
+        })
+        .map_err(|e| format!("Failed to register hotkey: {:?}", e))?;
+    log::info!(
+        "Registered global shortcut: {} ({:?})",
+        binding.hotkey,
+        binding.action
+    );
+    Ok(shortcut)
+}
+
+/// Show the main window, or hide it if it's already in front.
+pub fn toggle_window(app: &AppHandle) {
+    let Some(win) = app.get_webview_window("main") else {
+        return;
+    };
+    if win.is_visible().unwrap_or(false) && win.is_focused().unwrap_or(false) {
+        crate::animate_window_hide(&win, None);
+    } else {
+        crate::position_window_at_bottom(&win);
+    }
+}
+
+fn run(app: &AppHandle, action: &ShortcutAction) {
+    match action {
+        ShortcutAction::ToggleWindow => toggle_window(app),
+        ShortcutAction::PauseCapture => crate::capture_pause::toggle(app),
+        ShortcutAction::OpenFolder(id) => {
+            if let Some(win) = app.get_webview_window("main") {
+                crate::position_window_at_bottom(&win);
+                let _ = win.emit("open-folder", id.to_string());
+            }
+        }
+        ShortcutAction::PasteLast => {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = crate::quick_paste::paste_nth(&app, 1, None).await {
+                    log::error!("CLIPBOARD: Paste last failed: {}", e);
+                }
+            });
+        }
+        ShortcutAction::RunAiAction(ai_action) => {
+            let app = app.clone();
+            let ai_action = ai_action.clone();
+            tauri::async_runtime::spawn(async move {
+                run_ai_on_last_clip(&app, &ai_action).await;
+            });
+        }
+        ShortcutAction::ClearClipboard => {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                // Synchronize clipboard access across the app
+                let _guard = crate::clipboard::CLIPBOARD_SYNC.lock().await;
+                if let Err(e) = crate::clipboard::clear_system_clipboard(&app).await {
+                    log::error!("CLIPBOARD: Failed to clear clipboard: {}", e);
+                }
+            });
+        }
+    }
+}
+
+#[derive(Clone, Serialize)]
+struct AiShortcutResult {
+    clip_id: Option<String>,
+    action: String,
+    result: Option<String>,
+    error: Option<String>,
+}
+
+/// Run `ai_action` on the most recent clip that isn't sensitive, storing the result like the AI
+/// menu does.
+async fn run_ai_on_last_clip(app: &AppHandle, ai_action: &str) {
+    let pool = app.state::<Arc<Database>>().pool();
+    let clip_id: Option<String> = sqlx::query_scalar(
+        r#"SELECT uuid FROM clips WHERE is_deleted = 0 AND is_sensitive = 0 ORDER BY created_at DESC, id DESC LIMIT 1"#,
+    )
+    .fetch_optional(&pool)
+    .await
+    .ok()
+    .flatten();
+
+    let res = match &clip_id {
+        Some(id) => crate::commands::run_ai_action(app, &pool, id, ai_action, None, false)
+            .await
+            .map(|entry| entry.result),
+        None => Err("No clips yet".to_string()),
+    };
+    if res.is_ok() {
+        let _ = app.emit("clipboard-change", ());
+    }
+    let (result, error) = match res {
+        Ok(result) => (Some(result), None),
+        Err(e) => {
+            log::error!("AI shortcut {} failed: {}", ai_action, e);
+            (None, Some(e))
+        }
+    };
+    let _ = app.emit(
+        "ai-shortcut-finished",
+        AiShortcutResult {
+            clip_id,
+            action: ai_action.to_string(),
+            result,
+            error,
+        },
+    );
+}
+
+/// Register the window hotkey and bindings from the current settings.
+pub fn register_from_settings(app: &AppHandle) -> Vec<BindingStatus> {
+    let settings = app.state::<Arc<SettingsManager>>().get();
+    register_all(app, &settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_hotkey() {
+        assert_eq!(normalize_hotkey("shift+ctrl+KeyV"), "CTRL+SHIFT+V");
+        assert_eq!(normalize_hotkey("Control + Shift + v"), "CTRL+SHIFT+V");
+        assert_eq!(normalize_hotkey("Cmd+Alt+Digit1"), "ALT+SUPER+1");
+        assert_eq!(normalize_hotkey("Ctrl+F12"), "CTRL+F12");
+    }
+
+    #[test]
+    fn test_find_conflicts() {
+        let binding = |hotkey: &str, action| ShortcutBinding {
+            hotkey: hotkey.to_string(),
+            action,
+        };
+        let bindings = vec![
+            binding("Ctrl+Shift+V", ShortcutAction::ToggleWindow),
+            binding("Ctrl+Alt+P", ShortcutAction::PauseCapture),
+            binding("shift+ctrl+v", ShortcutAction::PasteLast),
+            binding("Alt+Ctrl+1", ShortcutAction::ClearClipboard),
+        ];
+        let reserved = vec![("Ctrl+Alt+1".to_string(), "quick paste".to_string())];
+        let conflicts = find_conflicts(&bindings, &reserved);
+        assert_eq!(conflicts[0], None);
+        assert_eq!(conflicts[1], None);
+        assert_eq!(
+            conflicts[2].as_deref(),
+            Some("shift+ctrl+v is already used by ToggleWindow")
+        );
+        assert_eq!(
+            conflicts[3].as_deref(),
+            Some("Alt+Ctrl+1 is already used by quick paste")
+        );
+    }
+}
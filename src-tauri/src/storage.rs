@@ -0,0 +1,364 @@
+use serde::Serialize;
+use sqlx::SqlitePool;
+use std::collections::HashSet;
+use std::path::Path;
+
+/// Bytes a clip occupies inside the database (row payload plus any image blob).
+const CLIP_DB_BYTES: &str = r#"
+    length(c.content)
+    + COALESCE(length(c.text_preview), 0)
+    + COALESCE(length(c.metadata), 0)
+    + COALESCE(length(c.source_icon), 0)
+    + COALESCE(length(ci.full_content), 0)
+"#;
+
+/// Bytes a clip occupies on disk outside the database (full-size image files).
+const CLIP_FILE_BYTES: &str =
+    "COALESCE(CASE WHEN ci.storage_kind = 'file' THEN ci.file_size END, 0)";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StorageBucket {
+    pub key: String,
+    /// Display name where `key` is an id, i.e. the folder name in `by_folder`.
+    pub name: Option<String>,
+    pub count: i64,
+    pub db_bytes: i64,
+    pub file_bytes: i64,
+    pub total_bytes: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LargeClip {
+    pub id: String,
+    pub clip_type: String,
+    pub preview: String,
+    pub source_app: Option<String>,
+    pub created_at: String,
+    pub is_deleted: bool,
+    pub db_bytes: i64,
+    pub file_bytes: i64,
+    pub total_bytes: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiskUsage {
+    pub db_file_bytes: u64,
+    pub wal_file_bytes: u64,
+    pub shm_file_bytes: u64,
+    pub image_dir_bytes: u64,
+    pub image_file_count: u64,
+    /// Image files on disk that no clip references any more.
+    pub orphan_image_bytes: u64,
+    pub orphan_image_count: u64,
+    pub total_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StorageReport {
+    pub disk: DiskUsage,
+    pub active: StorageBucket,
+    /// Soft-deleted clips still kept in the database until history is cleared.
+    pub deleted: StorageBucket,
+    pub by_type: Vec<StorageBucket>,
+    pub by_source_app: Vec<StorageBucket>,
+    pub by_folder: Vec<StorageBucket>,
+    pub by_month: Vec<StorageBucket>,
+    pub largest_clips: Vec<LargeClip>,
+}
+
+type BucketRow = (String, Option<String>, i64, i64, i64);
+type LargeClipRow = (
+    String,
+    String,
+    String,
+    Option<String>,
+    String,
+    bool,
+    i64,
+    i64,
+);
+
+fn to_bucket((key, name, count, db_bytes, file_bytes): BucketRow) -> StorageBucket {
+    StorageBucket {
+        key,
+        name,
+        count,
+        db_bytes,
+        file_bytes,
+        total_bytes: db_bytes + file_bytes,
+    }
+}
+
+/// Active clips grouped by `key_expr`, with `name_expr` as each group's display name.
+async fn breakdown(
+    pool: &SqlitePool,
+    key_expr: &str,
+    name_expr: &str,
+    order_by: &str,
+) -> Result<Vec<StorageBucket>, String> {
+    let sql = format!(
+        r#"
+        SELECT {key} AS bucket, {name}, COUNT(*),
+               COALESCE(SUM({db}), 0) AS db_bytes, COALESCE(SUM({file}), 0) AS file_bytes
+        FROM clips c
+        LEFT JOIN clip_images ci ON ci.clip_uuid = c.uuid
+        LEFT JOIN folders f ON f.id = c.folder_id
+        WHERE c.is_deleted = 0
+        GROUP BY bucket
+        ORDER BY {order_by}
+        "#,
+        key = key_expr,
+        name = name_expr,
+        db = CLIP_DB_BYTES,
+        file = CLIP_FILE_BYTES,
+        order_by = order_by,
+    );
+
+    let rows: Vec<BucketRow> = sqlx::query_as(&sql)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(rows.into_iter().map(to_bucket).collect())
+}
+
+async fn totals(pool: &SqlitePool, is_deleted: bool, key: &str) -> Result<StorageBucket, String> {
+    let sql = format!(
+        r#"
+        SELECT ?, NULL, COUNT(*), COALESCE(SUM({db}), 0), COALESCE(SUM({file}), 0)
+        FROM clips c
+        LEFT JOIN clip_images ci ON ci.clip_uuid = c.uuid
+        WHERE c.is_deleted = ?
+        "#,
+        db = CLIP_DB_BYTES,
+        file = CLIP_FILE_BYTES,
+    );
+
+    let row: BucketRow = sqlx::query_as(&sql)
+        .bind(key)
+        .bind(is_deleted)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(to_bucket(row))
+}
+
+async fn largest_clips(pool: &SqlitePool, limit: i64) -> Result<Vec<LargeClip>, String> {
+    let sql = format!(
+        r#"
+        SELECT c.uuid, c.clip_type, COALESCE(c.text_preview, ''), c.source_app,
+               CAST(c.created_at AS TEXT), c.is_deleted, {db} AS db_bytes, {file} AS file_bytes
+        FROM clips c
+        LEFT JOIN clip_images ci ON ci.clip_uuid = c.uuid
+        ORDER BY db_bytes + file_bytes DESC
+        LIMIT ?
+        "#,
+        db = CLIP_DB_BYTES,
+        file = CLIP_FILE_BYTES,
+    );
+
+    let rows: Vec<LargeClipRow> = sqlx::query_as(&sql)
+        .bind(limit)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(rows
+        .into_iter()
+        .map(
+            |(id, clip_type, preview, source_app, created_at, is_deleted, db_bytes, file_bytes)| {
+                LargeClip {
+                    id,
+                    clip_type,
+                    preview: preview.chars().take(100).collect(),
+                    source_app,
+                    created_at,
+                    is_deleted,
+                    db_bytes,
+                    file_bytes,
+                    total_bytes: db_bytes + file_bytes,
+                }
+            },
+        )
+        .collect())
+}
+
+fn file_size(path: &Path) -> u64 {
+    std::fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+}
+
+async fn disk_usage(pool: &SqlitePool, data_dir: &Path) -> Result<DiskUsage, String> {
+    let db_path = data_dir.join(crate::data_dir::DB_FILE_NAME);
+    let db_file_bytes = file_size(&db_path);
+    let wal_file_bytes =
+        file_size(&data_dir.join(format!("{}-wal", crate::data_dir::DB_FILE_NAME)));
+    let shm_file_bytes =
+        file_size(&data_dir.join(format!("{}-shm", crate::data_dir::DB_FILE_NAME)));
+
+    let referenced: HashSet<String> =
+        sqlx::query_scalar::<_, Option<String>>(r#"SELECT file_path FROM clip_images"#)
+            .fetch_all(pool)
+            .await
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .flatten()
+            .filter(|p| !p.is_empty())
+            .collect();
+
+    let mut image_dir_bytes = 0u64;
+    let mut image_file_count = 0u64;
+    let mut orphan_image_bytes = 0u64;
+    let mut orphan_image_count = 0u64;
+    let images_dir = data_dir.join(crate::data_dir::IMAGES_DIR_NAME);
+    if let Ok(entries) = std::fs::read_dir(&images_dir) {
+        for entry in entries.flatten() {
+            let Ok(meta) = entry.metadata() else {
+                continue;
+            };
+            if !meta.is_file() {
+                continue;
+            }
+            image_dir_bytes += meta.len();
+            image_file_count += 1;
+            if !referenced.contains(entry.path().to_string_lossy().as_ref()) {
+                orphan_image_bytes += meta.len();
+                orphan_image_count += 1;
+            }
+        }
+    }
+
+    Ok(DiskUsage {
+        db_file_bytes,
+        wal_file_bytes,
+        shm_file_bytes,
+        image_dir_bytes,
+        image_file_count,
+        orphan_image_bytes,
+        orphan_image_count,
+        total_bytes: db_file_bytes + wal_file_bytes + shm_file_bytes + image_dir_bytes,
+    })
+}
+
+/// Break down where the data directory's bytes go, so cleanup can target the biggest offenders.
+pub async fn analyze(
+    pool: &SqlitePool,
+    data_dir: &Path,
+    largest_limit: i64,
+) -> Result<StorageReport, String> {
+    Ok(StorageReport {
+        disk: disk_usage(pool, data_dir).await?,
+        active: totals(pool, false, "active").await?,
+        deleted: totals(pool, true, "deleted").await?,
+        by_type: breakdown(pool, "c.clip_type", "NULL", "db_bytes + file_bytes DESC").await?,
+        by_source_app: breakdown(
+            pool,
+            "COALESCE(c.source_app, '(unknown)')",
+            "NULL",
+            "db_bytes + file_bytes DESC",
+        )
+        .await?,
+        // Keyed by id since folder names aren't unique; unfiled clips have no name
+        by_folder: breakdown(
+            pool,
+            "COALESCE(CAST(c.folder_id AS TEXT), '(unfiled)')",
+            "f.name",
+            "db_bytes + file_bytes DESC",
+        )
+        .await?,
+        by_month: breakdown(
+            pool,
+            "COALESCE(strftime('%Y-%m', c.created_at), '(unknown)')",
+            "NULL",
+            "bucket DESC",
+        )
+        .await?,
+        largest_clips: largest_clips(pool, largest_limit).await?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::TestDb;
+
+    #[tokio::test]
+    async fn test_analyze_buckets() {
+        let db = TestDb::new("storage").await;
+        let pool = &db.pool;
+        // Two folders with the same name must stay apart
+        for id in [1, 2] {
+            sqlx::query("INSERT INTO folders (id, name) VALUES (?, 'Work')")
+                .bind(id)
+                .execute(pool)
+                .await
+                .unwrap();
+        }
+        let clips = [
+            ("a", "text", "hello", Some(1), "2026-01-05 10:00:00", false),
+            ("b", "text", "hi", Some(2), "2026-02-01 10:00:00", false),
+            (
+                "c",
+                "url",
+                "https://x.io",
+                None,
+                "2026-02-03 10:00:00",
+                false,
+            ),
+            ("d", "text", "gone", Some(1), "2026-02-04 10:00:00", true),
+        ];
+        for (uuid, clip_type, content, folder_id, created_at, is_deleted) in clips {
+            sqlx::query(
+                "INSERT INTO clips (uuid, clip_type, content, text_preview, content_hash, folder_id, created_at, is_deleted) VALUES (?, ?, ?, '', ?, ?, ?, ?)",
+            )
+            .bind(uuid)
+            .bind(clip_type)
+            .bind(content.as_bytes())
+            .bind(uuid)
+            .bind(folder_id)
+            .bind(created_at)
+            .bind(is_deleted)
+            .execute(pool)
+            .await
+            .unwrap();
+        }
+
+        let dir = std::env::temp_dir();
+        let report = analyze(pool, &dir, 10).await.unwrap();
+        let find = |buckets: &[StorageBucket], key: &str| {
+            buckets
+                .iter()
+                .find(|b| b.key == key)
+                .cloned()
+                .unwrap_or_else(|| panic!("no bucket {}", key))
+        };
+
+        assert_eq!(report.active.count, 3);
+        assert_eq!(report.deleted.count, 1);
+
+        assert_eq!(report.by_type.len(), 2);
+        let text = find(&report.by_type, "text");
+        assert_eq!(text.count, 2);
+        assert_eq!(text.db_bytes, 7);
+        assert_eq!(text.name, None);
+
+        assert_eq!(report.by_folder.len(), 3);
+        let first = find(&report.by_folder, "1");
+        assert_eq!((first.count, first.name.as_deref()), (1, Some("Work")));
+        let second = find(&report.by_folder, "2");
+        assert_eq!((second.count, second.name.as_deref()), (1, Some("Work")));
+        let unfiled = find(&report.by_folder, "(unfiled)");
+        assert_eq!((unfiled.count, unfiled.name), (1, None));
+
+        let months: Vec<(String, i64)> = report
+            .by_month
+            .iter()
+            .map(|b| (b.key.clone(), b.count))
+            .collect();
+        assert_eq!(
+            months,
+            [("2026-02".to_string(), 2), ("2026-01".to_string(), 1)]
+        );
+
+        assert_eq!(report.largest_clips.len(), 4);
+        db.close().await;
+    }
+}
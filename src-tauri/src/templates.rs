@@ -0,0 +1,249 @@
+//! Template clips: placeholders expanded when the clip is pasted.
+//!
+//! `{{date}}`, `{{date:%d.%m.%Y}}`, `{{time}}`, `{{time:%H:%M}}`, `{{uuid}}`, `{{clipboard}}`,
+//! `{{input:Name}}` and `{{cursor}}`. `\{{` is a literal `{{`.
+
+use chrono::format::{Item, StrftimeItems};
+use serde::Serialize;
+use std::collections::HashMap;
+
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+const DEFAULT_TIME_FORMAT: &str = "%H:%M:%S";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Segment {
+    Text(String),
+    Date(String),
+    Time(String),
+    Uuid,
+    Clipboard,
+    Input(String),
+    Cursor,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TemplateError {
+    pub message: String,
+    /// Character offset of the placeholder in the template.
+    pub position: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TemplateInfo {
+    pub errors: Vec<TemplateError>,
+    /// `{{input:…}}` names in order of first use; the frontend prompts for these.
+    pub inputs: Vec<String>,
+    pub uses_clipboard: bool,
+    pub has_cursor: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expanded {
+    pub text: String,
+    /// Left-arrow presses that put the caret back on `{{cursor}}` after pasting.
+    pub cursor_back: usize,
+}
+
+pub struct Context<'a> {
+    pub now: chrono::DateTime<chrono::Local>,
+    pub clipboard: Option<&'a str>,
+    pub inputs: &'a HashMap<String, String>,
+}
+
+fn check_format(format: &str, position: usize) -> Result<String, TemplateError> {
+    if StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+        return Err(TemplateError {
+            message: format!("Invalid date/time format '{}'", format),
+            position,
+        });
+    }
+    Ok(format.to_string())
+}
+
+fn placeholder(body: &str, position: usize) -> Result<Segment, TemplateError> {
+    let (name, arg) = match body.split_once(':') {
+        Some((name, arg)) => (name.trim(), Some(arg)),
+        None => (body.trim(), None),
+    };
+    let no_arg = |segment: Segment| match arg {
+        Some(_) => Err(TemplateError {
+            message: format!("'{}' takes no argument", name),
+            position,
+        }),
+        None => Ok(segment),
+    };
+    match name {
+        "date" => Ok(Segment::Date(check_format(
+            arg.unwrap_or(DEFAULT_DATE_FORMAT),
+            position,
+        )?)),
+        "time" => Ok(Segment::Time(check_format(
+            arg.unwrap_or(DEFAULT_TIME_FORMAT),
+            position,
+        )?)),
+        "uuid" => no_arg(Segment::Uuid),
+        "clipboard" => no_arg(Segment::Clipboard),
+        "cursor" => no_arg(Segment::Cursor),
+        "input" => match arg.map(str::trim) {
+            Some(label) if !label.is_empty() => Ok(Segment::Input(label.to_string())),
+            _ => Err(TemplateError {
+                message: "'input' needs a name, e.g. {{input:Name}}".to_string(),
+                position,
+            }),
+        },
+        _ => Err(TemplateError {
+            message: format!("Unknown placeholder '{}'", name),
+            position,
+        }),
+    }
+}
+
+/// Split a template into text and placeholders, collecting every error.
+pub fn parse(template: &str) -> Result<Vec<Segment>, Vec<TemplateError>> {
+    let chars: Vec<char> = template.chars().collect();
+    let mut segments = Vec::new();
+    let mut errors = Vec::new();
+    let mut text = String::new();
+    let mut cursor_seen = false;
+    let mut i = 0;
+
+    while i < chars.len() {
+        if chars[i] == '\\' && chars.get(i + 1) == Some(&'{') && chars.get(i + 2) == Some(&'{') {
+            text.push_str("{{");
+            i += 3;
+            continue;
+        }
+        if chars[i] != '{' || chars.get(i + 1) != Some(&'{') {
+            text.push(chars[i]);
+            i += 1;
+            continue;
+        }
+
+        let position = i;
+        let close = (i + 2..chars.len().saturating_sub(1))
+            .find(|&j| chars[j] == '}' && chars[j + 1] == '}');
+        let Some(close) = close else {
+            errors.push(TemplateError {
+                message: "Unclosed '{{'".to_string(),
+                position,
+            });
+            break;
+        };
+        let body: String = chars[i + 2..close].iter().collect();
+        match placeholder(&body, position) {
+            Ok(Segment::Cursor) if cursor_seen => errors.push(TemplateError {
+                message: "Only one {{cursor}} is allowed".to_string(),
+                position,
+            }),
+            Ok(segment) => {
+                cursor_seen |= segment == Segment::Cursor;
+                if !text.is_empty() {
+                    segments.push(Segment::Text(std::mem::take(&mut text)));
+                }
+                segments.push(segment);
+            }
+            Err(e) => errors.push(e),
+        }
+        i = close + 2;
+    }
+
+    if !text.is_empty() {
+        segments.push(Segment::Text(text));
+    }
+    if errors.is_empty() {
+        Ok(segments)
+    } else {
+        Err(errors)
+    }
+}
+
+pub fn validate(template: &str) -> TemplateInfo {
+    let (segments, errors) = match parse(template) {
+        Ok(segments) => (segments, Vec::new()),
+        Err(errors) => (Vec::new(), errors),
+    };
+    let mut inputs: Vec<String> = Vec::new();
+    for segment in &segments {
+        if let Segment::Input(name) = segment {
+            if !inputs.contains(name) {
+                inputs.push(name.clone());
+            }
+        }
+    }
+    TemplateInfo {
+        errors,
+        inputs,
+        uses_clipboard: segments.contains(&Segment::Clipboard),
+        has_cursor: segments.contains(&Segment::Cursor),
+    }
+}
+
+/// Caret moves needed to go back over `text`; a line break is one move even as `\r\n`.
+fn caret_moves(text: &str) -> usize {
+    text.chars().count() - text.matches("\r\n").count()
+}
+
+pub fn expand(segments: &[Segment], ctx: &Context) -> Result<Expanded, String> {
+    let mut text = String::new();
+    let mut cursor_at = None;
+    for segment in segments {
+        match segment {
+            Segment::Text(t) => text.push_str(t),
+            Segment::Date(format) | Segment::Time(format) => {
+                text.push_str(&ctx.now.format(format).to_string())
+            }
+            Segment::Uuid => text.push_str(&uuid::Uuid::new_v4().to_string()),
+            Segment::Clipboard => text.push_str(ctx.clipboard.unwrap_or_default()),
+            Segment::Input(name) => text.push_str(
+                ctx.inputs
+                    .get(name)
+                    .ok_or_else(|| format!("Missing value for {{{{input:{}}}}}", name))?,
+            ),
+            Segment::Cursor => cursor_at = Some(text.len()),
+        }
+    }
+    let cursor_back = cursor_at.map_or(0, |at| caret_moves(&text[at..]));
+    Ok(Expanded { text, cursor_back })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_expand_placeholders() {
+        let segments =
+            parse("Hi {{input:Name}}, {{date:%d.%m.%Y}} {{time:%H:%M}}\n{{cursor}}-- \\{{x}} {{clipboard}}")
+                .unwrap();
+        let inputs = HashMap::from([("Name".to_string(), "Ada".to_string())]);
+        let ctx = Context {
+            now: chrono::Local.with_ymd_and_hms(2026, 3, 4, 9, 5, 0).unwrap(),
+            clipboard: Some("clip"),
+            inputs: &inputs,
+        };
+        let expanded = expand(&segments, &ctx).unwrap();
+        assert_eq!(expanded.text, "Hi Ada, 04.03.2026 09:05\n-- {{x}} clip");
+        assert_eq!(expanded.cursor_back, "-- {{x}} clip".len());
+
+        let missing = HashMap::new();
+        let ctx = Context {
+            inputs: &missing,
+            ..ctx
+        };
+        assert!(expand(&segments, &ctx).is_err());
+    }
+
+    #[test]
+    fn test_validate_reports_errors() {
+        let info =
+            validate("{{nope}} {{input:}} {{uuid:x}} {{date:%Q}} {{cursor}}{{cursor}} {{time");
+        let positions: Vec<usize> = info.errors.iter().map(|e| e.position).collect();
+        assert_eq!(positions, vec![0, 9, 20, 31, 53, 64]);
+
+        let info = validate("{{input:A}} {{input:B}} {{input:A}} {{clipboard}}");
+        assert!(info.errors.is_empty());
+        assert_eq!(info.inputs, vec!["A", "B"]);
+        assert!(info.uses_clipboard && !info.has_cursor);
+    }
+}
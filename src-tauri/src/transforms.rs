@@ -0,0 +1,215 @@
+//! Text transforms applied to a clip at paste time, e.g. `["trim", "json_minify", "base64_encode"]`.
+//!
+//! Transforms run left to right, each on the previous one's output.
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use serde::Serialize;
+
+pub struct Transform {
+    pub id: &'static str,
+    pub name: &'static str,
+    apply: fn(&str) -> Result<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TransformInfo {
+    pub id: &'static str,
+    pub name: &'static str,
+}
+
+pub static TRANSFORMS: &[Transform] = &[
+    Transform {
+        id: "trim",
+        name: "Trim",
+        apply: |s| Ok(s.trim().to_string()),
+    },
+    Transform {
+        id: "uppercase",
+        name: "UPPERCASE",
+        apply: |s| Ok(s.to_uppercase()),
+    },
+    Transform {
+        id: "lowercase",
+        name: "lowercase",
+        apply: |s| Ok(s.to_lowercase()),
+    },
+    Transform {
+        id: "title_case",
+        name: "Title Case",
+        apply: |s| Ok(title_case(s)),
+    },
+    Transform {
+        id: "collapse_whitespace",
+        name: "Collapse whitespace",
+        apply: |s| Ok(s.split_whitespace().collect::<Vec<_>>().join(" ")),
+    },
+    Transform {
+        id: "json_pretty",
+        name: "JSON: pretty-print",
+        apply: |s| serde_json::to_string_pretty(&parse_json(s)?).map_err(|e| e.to_string()),
+    },
+    Transform {
+        id: "json_minify",
+        name: "JSON: minify",
+        apply: |s| serde_json::to_string(&parse_json(s)?).map_err(|e| e.to_string()),
+    },
+    Transform {
+        id: "url_encode",
+        name: "URL-encode",
+        apply: |s| Ok(url_encode(s)),
+    },
+    Transform {
+        id: "url_decode",
+        name: "URL-decode",
+        apply: url_decode,
+    },
+    Transform {
+        id: "base64_encode",
+        name: "Base64-encode",
+        apply: |s| Ok(BASE64.encode(s.as_bytes())),
+    },
+    Transform {
+        id: "base64_decode",
+        name: "Base64-decode",
+        apply: |s| {
+            let bytes = BASE64
+                .decode(s.trim())
+                .map_err(|e| format!("Invalid base64: {}", e))?;
+            String::from_utf8(bytes).map_err(|_| "Decoded base64 is not text".to_string())
+        },
+    },
+    Transform {
+        id: "quote_string",
+        name: "Quoted string literal",
+        // JSON escaping is also valid in JavaScript, Python, Rust and most C-like languages.
+        apply: |s| serde_json::to_string(s).map_err(|e| e.to_string()),
+    },
+];
+
+pub fn list() -> Vec<TransformInfo> {
+    TRANSFORMS
+        .iter()
+        .map(|t| TransformInfo {
+            id: t.id,
+            name: t.name,
+        })
+        .collect()
+}
+
+/// Run the transforms named by `ids` in order.
+pub fn apply_all(text: &str, ids: &[String]) -> Result<String, String> {
+    let mut text = text.to_string();
+    for id in ids {
+        let transform = TRANSFORMS
+            .iter()
+            .find(|t| t.id == id)
+            .ok_or_else(|| format!("Unknown transform '{}'", id))?;
+        text = (transform.apply)(&text).map_err(|e| format!("{}: {}", transform.name, e))?;
+    }
+    Ok(text)
+}
+
+fn parse_json(s: &str) -> Result<serde_json::Value, String> {
+    serde_json::from_str(s).map_err(|e| format!("Invalid JSON: {}", e))
+}
+
+fn title_case(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut at_word_start = true;
+    for c in s.chars() {
+        if c.is_alphanumeric() || c == '\'' {
+            if at_word_start {
+                out.extend(c.to_uppercase());
+            } else {
+                out.extend(c.to_lowercase());
+            }
+            at_word_start = false;
+        } else {
+            out.push(c);
+            at_word_start = true;
+        }
+    }
+    out
+}
+
+/// Percent-encode everything but RFC 3986 unreserved characters.
+fn url_encode(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for byte in s.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.' | b'~') {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    out
+}
+
+/// Decode `%XX` escapes and `+` as a space.
+fn url_decode(s: &str) -> Result<String, String> {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let hex = s
+                    .get(i + 1..i + 3)
+                    .and_then(|h| u8::from_str_radix(h, 16).ok())
+                    .ok_or_else(|| format!("Invalid escape at position {}", i))?;
+                out.push(hex);
+                i += 3;
+            }
+            b'+' => {
+                out.push(b' ');
+                i += 1;
+            }
+            byte => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(out).map_err(|_| "Decoded text is not valid UTF-8".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(text: &str, ids: &[&str]) -> Result<String, String> {
+        let ids: Vec<String> = ids.iter().map(|s| s.to_string()).collect();
+        apply_all(text, &ids)
+    }
+
+    #[test]
+    fn test_transforms_compose_in_order() {
+        assert_eq!(
+            run("  hello   WORLD  ", &["collapse_whitespace", "title_case"]).unwrap(),
+            "Hello World"
+        );
+        assert_eq!(
+            run("{ \"a\": [1, 2] }", &["json_minify", "quote_string"]).unwrap(),
+            r#""{\"a\":[1,2]}""#
+        );
+        assert_eq!(
+            run("a b&c=é", &["url_encode"]).unwrap(),
+            "a%20b%26c%3D%C3%A9"
+        );
+        assert_eq!(
+            run("a b&c=é", &["url_encode", "url_decode"]).unwrap(),
+            "a b&c=é"
+        );
+        assert_eq!(
+            run("hi", &["base64_encode", "base64_decode", "uppercase"]).unwrap(),
+            "HI"
+        );
+    }
+
+    #[test]
+    fn test_transform_errors() {
+        assert!(run("{", &["json_pretty"]).is_err());
+        assert!(run("%zz", &["url_decode"]).is_err());
+        assert!(run("x", &["rot13"]).is_err());
+    }
+}
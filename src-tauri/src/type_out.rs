@@ -0,0 +1,214 @@
+//! Type-out mode: send a clip's text as synthesized keystrokes, for remote consoles, VM viewers
+//! and fields that block pasting. Pressing Escape stops typing.
+
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+static TYPING: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TypeOutResult {
+    /// Characters sent before finishing or aborting.
+    pub typed: usize,
+    pub total: usize,
+    pub aborted: bool,
+}
+
+/// Marks a type-out as running; a second one is refused until this is dropped.
+pub struct TypingGuard(());
+
+impl TypingGuard {
+    pub fn acquire() -> Result<Self, String> {
+        if TYPING
+            .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+            .is_err()
+        {
+            return Err("Already typing".to_string());
+        }
+        Ok(TypingGuard(()))
+    }
+}
+
+impl Drop for TypingGuard {
+    fn drop(&mut self) {
+        TYPING.store(false, Ordering::SeqCst);
+    }
+}
+
+/// What to send for one character: line breaks and tabs as real keys, everything else as text.
+#[derive(Debug, PartialEq)]
+enum Key {
+    Enter,
+    Tab,
+    Char(char),
+}
+
+fn keys(text: &str) -> Vec<Key> {
+    // "\r\n" and a lone "\r" are each one Enter
+    text.replace("\r\n", "\n")
+        .chars()
+        .map(|c| match c {
+            '\n' | '\r' => Key::Enter,
+            '\t' => Key::Tab,
+            c => Key::Char(c),
+        })
+        .collect()
+}
+
+/// Type `text` into the focused app, waiting `delay` between characters. Blocks until done.
+pub fn type_text(text: &str, delay: Duration) -> TypeOutResult {
+    let keys = keys(text);
+    let total = keys.len();
+    let mut typed = 0;
+    for key in &keys {
+        if escape_pressed() {
+            log::info!(
+                "CLIPBOARD: Type-out aborted after {} of {} characters",
+                typed,
+                total
+            );
+            return TypeOutResult {
+                typed,
+                total,
+                aborted: true,
+            };
+        }
+        send_key(key);
+        typed += 1;
+        std::thread::sleep(delay);
+    }
+    TypeOutResult {
+        typed,
+        total,
+        aborted: false,
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn escape_pressed() -> bool {
+    use windows::Win32::UI::Input::KeyboardAndMouse::{GetAsyncKeyState, VK_ESCAPE};
+    unsafe { (GetAsyncKeyState(VK_ESCAPE.0 as i32) as u16 & 0x8000) != 0 }
+}
+
+#[cfg(target_os = "windows")]
+fn send_key(key: &Key) {
+    use windows::Win32::UI::Input::KeyboardAndMouse::{
+        SendInput, INPUT, INPUT_0, INPUT_KEYBOARD, KEYBDINPUT, KEYBD_EVENT_FLAGS, KEYEVENTF_KEYUP,
+        KEYEVENTF_UNICODE, VIRTUAL_KEY, VK_RETURN, VK_TAB,
+    };
+
+    let input = |vk: VIRTUAL_KEY, scan: u16, flags: KEYBD_EVENT_FLAGS| INPUT {
+        r#type: INPUT_KEYBOARD,
+        Anonymous: INPUT_0 {
+            ki: KEYBDINPUT {
+                wVk: vk,
+                wScan: scan,
+                dwFlags: flags,
+                ..Default::default()
+            },
+        },
+    };
+
+    let inputs: Vec<INPUT> = match key {
+        Key::Enter | Key::Tab => {
+            let vk = if *key == Key::Enter {
+                VK_RETURN
+            } else {
+                VK_TAB
+            };
+            vec![
+                input(vk, 0, KEYBD_EVENT_FLAGS(0)),
+                input(vk, 0, KEYEVENTF_KEYUP),
+            ]
+        }
+        Key::Char(c) => {
+            // Characters outside the BMP are two UTF-16 units, sent as a pair
+            let mut units = [0u16; 2];
+            let units = c.encode_utf16(&mut units);
+            units
+                .iter()
+                .map(|&u| input(VIRTUAL_KEY(0), u, KEYEVENTF_UNICODE))
+                .chain(
+                    units
+                        .iter()
+                        .map(|&u| input(VIRTUAL_KEY(0), u, KEYEVENTF_UNICODE | KEYEVENTF_KEYUP)),
+                )
+                .collect()
+        }
+    };
+
+    unsafe {
+        SendInput(&inputs, std::mem::size_of::<INPUT>() as i32);
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn escape_pressed() -> bool {
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGEventSourceKeyState(state_id: i32, key: u16) -> bool;
+    }
+    // kCGEventSourceStateHIDSystemState = 1, kVK_Escape = 0x35
+    unsafe { CGEventSourceKeyState(1, 0x35) }
+}
+
+#[cfg(target_os = "macos")]
+fn send_key(key: &Key) {
+    use core_graphics::event::{CGEvent, CGEventTapLocation, CGKeyCode};
+    use core_graphics::event_source::{CGEventSource, CGEventSourceStateID};
+
+    let source = match CGEventSource::new(CGEventSourceStateID::HIDSystemState) {
+        Ok(src) => src,
+        Err(e) => {
+            log::error!("CLIPBOARD: Failed to create CGEventSource: {:?}", e);
+            return;
+        }
+    };
+
+    // kVK_Return = 0x24, kVK_Tab = 0x30; text goes through key code 0 with a Unicode string
+    let (key_code, text): (CGKeyCode, Option<String>) = match key {
+        Key::Enter => (0x24, None),
+        Key::Tab => (0x30, None),
+        Key::Char(c) => (0, Some(c.to_string())),
+    };
+
+    for key_down in [true, false] {
+        if let Ok(event) = CGEvent::new_keyboard_event(source.clone(), key_code, key_down) {
+            if let Some(text) = &text {
+                event.set_string(text);
+            }
+            event.post(CGEventTapLocation::HID);
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn escape_pressed() -> bool {
+    false
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn send_key(_key: &Key) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keys_map_line_breaks() {
+        assert_eq!(
+            keys("a\r\nb\tc😀\rd"),
+            vec![
+                Key::Char('a'),
+                Key::Enter,
+                Key::Char('b'),
+                Key::Tab,
+                Key::Char('c'),
+                Key::Char('😀'),
+                Key::Enter,
+                Key::Char('d'),
+            ]
+        );
+    }
+}